@@ -1,16 +1,34 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
 pub use solana_transaction_status_client_types::{
     EncodedConfirmedTransactionWithStatusMeta, EncodedTransactionWithStatusMeta, UiConfirmedBlock,
 };
 
+/// Error when parsing an enum from a string
+#[derive(Debug)]
+pub struct ParseError(pub anyhow::Error);
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 // Define an enum for the type of swap
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum SwapType {
     Sell,
     Buy,
     Token,
+    // token_in == token_out
+    Arbitrage,
     // Unknown
 }
 
@@ -21,6 +39,7 @@ impl SwapType {
             "Sell" => Ok(SwapType::Sell),
             "Buy" => Ok(SwapType::Buy),
             "Token" => Ok(SwapType::Token),
+            "Arbitrage" => Ok(SwapType::Arbitrage),
             _ => Err(anyhow!("Invalid swap type: {}", s)),
         }
     }
@@ -29,15 +48,41 @@ impl SwapType {
             SwapType::Sell => "Sell",
             SwapType::Buy => "Buy",
             SwapType::Token => "Token",
+            SwapType::Arbitrage => "Arbitrage",
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+impl Display for SwapType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_db())
+    }
+}
+
+impl FromStr for SwapType {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SwapType::from_db(s).map_err(ParseError)
+    }
+}
+
+impl TryFrom<&str> for SwapType {
+    type Error = ParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum DexType {
     Jupiterv6,
     Pumpfun,
     RaydiumAmm,
+    RaydiumStableAmm,
+    OpenBook,
+    Helium,
     Unknown,
 }
 
@@ -48,6 +93,9 @@ impl DexType {
             "Jupiterv6" => Ok(DexType::Jupiterv6),
             "Pumpfun" => Ok(DexType::Pumpfun),
             "RaydiumAmm" => Ok(DexType::RaydiumAmm),
+            "RaydiumStableAmm" => Ok(DexType::RaydiumStableAmm),
+            "OpenBook" => Ok(DexType::OpenBook),
+            "Helium" => Ok(DexType::Helium),
             "Unknown" => Ok(DexType::Unknown),
             _ => Err(anyhow!("Invalid dex type: {}", s)),
         }
@@ -57,12 +105,63 @@ impl DexType {
             DexType::Jupiterv6 => "Jupiterv6",
             DexType::Pumpfun => "Pumpfun",
             DexType::RaydiumAmm => "RaydiumAmm",
+            DexType::RaydiumStableAmm => "RaydiumStableAmm",
+            DexType::OpenBook => "OpenBook",
+            DexType::Helium => "Helium",
             DexType::Unknown => "Unknown",
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+impl Display for DexType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_db())
+    }
+}
+
+impl FromStr for DexType {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // also accept lowercase user input e.g. "pumpfun"
+        let dex_types = [
+            DexType::Jupiterv6,
+            DexType::Pumpfun,
+            DexType::RaydiumAmm,
+            DexType::RaydiumStableAmm,
+            DexType::OpenBook,
+            DexType::Helium,
+            DexType::Unknown,
+        ];
+        match dex_types
+            .into_iter()
+            .find(|dex| dex.to_db().eq_ignore_ascii_case(s))
+        {
+            Some(dex) => Ok(dex),
+            None => DexType::from_db(s).map_err(ParseError),
+        }
+    }
+}
+
+impl TryFrom<&str> for DexType {
+    type Error = ParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// a single AMM swap of a routed (aggregator) swap, amounts without decimals
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SwapHop {
+    pub amm: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub input_amount: u64,
+    pub output_amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SwapInfo {
     pub slot: u64,
     pub block_time: i64,
@@ -77,9 +176,96 @@ pub struct SwapInfo {
     pub token_in: String,
     pub amount_out: f64,
     pub token_out: String,
+    /// hops of aggregator swaps in execution order, empty for direct AMM swaps
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routing_path: Vec<SwapHop>,
 }
 
-#[derive(Serialize, Debug, PartialEq, Clone)]
+// f64 amounts are compared by their bit representation so that Eq, Hash and Ord are consistent.
+// the routing path only details how the swap was executed and is not compared
+impl SwapInfo {
+    fn cmp_fields(&self, other: &Self) -> Ordering {
+        self.slot
+            .cmp(&other.slot)
+            .then_with(|| self.signature.cmp(&other.signature))
+            .then_with(|| self.block_time.cmp(&other.block_time))
+            .then_with(|| self.signer.cmp(&other.signer))
+            .then_with(|| self.error.cmp(&other.error))
+            .then_with(|| self.dex.to_db().cmp(other.dex.to_db()))
+            .then_with(|| self.swap_type.to_db().cmp(other.swap_type.to_db()))
+            .then_with(|| self.amount_in.total_cmp(&other.amount_in))
+            .then_with(|| self.token_in.cmp(&other.token_in))
+            .then_with(|| self.amount_out.total_cmp(&other.amount_out))
+            .then_with(|| self.token_out.cmp(&other.token_out))
+    }
+}
+
+impl PartialEq for SwapInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp_fields(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SwapInfo {}
+
+impl Hash for SwapInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.slot.hash(state);
+        self.signature.hash(state);
+        self.amount_in.to_bits().hash(state);
+        self.amount_out.to_bits().hash(state);
+    }
+}
+
+/// Swaps are ordered by (slot, signature), remaining fields only break ties
+impl PartialOrd for SwapInfo {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SwapInfo {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_fields(other)
+    }
+}
+
+const WSOL: &str = "So11111111111111111111111111111111111111112";
+
+/// Price calculations on swap amounts
+pub trait PriceExtensions {
+    /// raw exchange rate: amount_out / amount_in or None if nothing was swapped in
+    fn price_ratio(&self) -> Option<f64>;
+    /// value of the swap in SOL or None for token to token swaps
+    fn sol_price(&self) -> Option<f64>;
+    /// value of the swap in USD(C) for a given SOL price
+    fn usdc_equivalent(&self, sol_usd_price: f64) -> Option<f64>;
+}
+
+impl PriceExtensions for SwapInfo {
+    fn price_ratio(&self) -> Option<f64> {
+        if self.amount_in == 0.0 {
+            return None;
+        }
+        Some(self.amount_out / self.amount_in)
+    }
+
+    fn sol_price(&self) -> Option<f64> {
+        if self.token_in == WSOL {
+            Some(self.amount_in)
+        } else if self.token_out == WSOL {
+            Some(self.amount_out)
+        } else {
+            None
+        }
+    }
+
+    fn usdc_equivalent(&self, sol_usd_price: f64) -> Option<f64> {
+        self.sol_price().map(|sol| sol * sol_usd_price)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct NewToken {
     pub block_time: i64,
     pub slot: u64,
@@ -93,9 +279,68 @@ pub struct NewToken {
     pub uri: String,
     pub initial_supply: Option<u64>,
     pub supply: Option<u64>,
+    /// royalty of metaplex tokens, None for other factories. Not stored in `tokens`
+    #[serde(default)]
+    pub seller_fee_basis_points: Option<u16>,
+    /// metaplex creators, None for other factories. Not stored in `tokens`
+    #[serde(default)]
+    pub creators: Option<Vec<TokenCreator>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct TokenCreator {
+    pub address: String,
+    pub verified: bool,
+    /// percentage of the royalties
+    pub share: u8,
+}
+
+impl Hash for NewToken {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.mint.hash(state);
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone, Default)]
+pub struct TokenMetadata {
+    pub mint: String,
+    pub image_url: Option<String>,
+    pub description: Option<String>,
+    pub twitter: Option<String>,
+    pub telegram: Option<String>,
+}
+
+// on-chain metadata changes (metaplex updateMetadataAccountV2): None = unchanged
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct MetadataUpdate {
+    pub slot: u64,
+    pub block_time: i64,
+    pub signature: String,
+    pub metadata_account: String,
+    /// None if the mint is not part of the transaction
+    pub mint: Option<String>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub uri: Option<String>,
+    pub seller_fee_basis_points: Option<u16>,
+    pub update_authority: Option<String>,
+    pub primary_sale_happened: Option<bool>,
+    pub is_mutable: Option<bool>,
 }
 
 #[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct SandwichAttack {
+    pub slot: u64,
+    pub attacker: String,
+    pub victim: String,
+    pub token: String,
+    pub front_run_sig: String,
+    pub back_run_sig: String,
+    pub victim_sig: String,
+    pub estimated_profit_sol: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
 pub struct SolTransfer {
     pub slot: u64,
     pub block_time: i64,
@@ -108,6 +353,29 @@ pub struct SolTransfer {
     pub sol: f64,
 }
 
+// sol is derived from lamports and not compared, a NaN would break Eq
+impl PartialEq for SolTransfer {
+    fn eq(&self, other: &Self) -> bool {
+        self.slot == other.slot
+            && self.block_time == other.block_time
+            && self.signature == other.signature
+            && self.from == other.from
+            && self.to == other.to
+            && self.lamports == other.lamports
+    }
+}
+
+impl Eq for SolTransfer {}
+
+impl Hash for SolTransfer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.signature.hash(state);
+        self.from.hash(state);
+        self.to.hash(state);
+        self.lamports.hash(state);
+    }
+}
+
 #[derive(Serialize, Debug, PartialEq, Clone)]
 pub struct SplTokenTransfer {
     pub slot: u64,
@@ -117,6 +385,8 @@ pub struct SplTokenTransfer {
     pub from_acc: String,
     pub to_acc: String,
     pub amount: f64,
+    /// `amount` without the f64 precision loss above 2^53
+    pub raw_amount: u64,
     pub authority: Option<String>,
 
     pub from: Option<String>,
@@ -143,6 +413,18 @@ pub enum SupplyChangeType {
     Burn,
 }
 
+/// the bonding curve of a pumpfun token completed, the token can migrate to an AMM pool
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct BondingCurveCompletion {
+    pub slot: u64,
+    pub block_time: i64,
+    pub signature: String,
+    pub mint: String,
+    pub bonding_curve: String,
+    /// the trader whose buy completed the curve
+    pub signer: String,
+}
+
 #[derive(Serialize, Debug, PartialEq, Clone)]
 pub struct SupplyChange {
     pub signature: String,
@@ -164,9 +446,16 @@ pub enum ComputeBudgetInstruction {
     Unknown,
 }
 
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
 pub struct BlockInfo {
     pub slot: u64,
     pub block_time: i64,
+    /// changes if the slot was reorganized between two fetches
+    pub blockhash: String,
+    /// the validator that produced the block, None if the block was fetched without rewards
+    pub leader: Option<String>,
+    /// fee reward of the leader in lamports
+    pub rewards: Option<u64>,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -182,9 +471,132 @@ pub enum ParserResultData {
     SolTransfer(SolTransfer),
     TokenTransfer(SplTokenTransfer),
     Swap(SwapInfo),
+    // instructions that settle trades between multiple parties, e.g. orderbook fills
+    MultiSwap(Vec<SwapInfo>),
     Token(NewToken),
+    MetadataUpdate(MetadataUpdate),
     Account(AccountInfo),
     Supply(SupplyChange),
+    BondingCurveComplete(BondingCurveCompletion),
+    // staking rewards, amount in token units
+    Reward {
+        mint: String,
+        amount: f64,
+        authority: String,
+    },
+    // a multisig transaction was executed, the executed instructions follow as separate results
+    MultisigExecution {
+        multisig: String,
+        // None if the index could not be recovered from the transaction account
+        transaction_index: Option<u32>,
+        executed_by: String,
+    },
+    // instructions that produce results of different types, e.g. close account + transfer
+    MultiResult(Vec<ParserResultData>),
     NoData,
     NoOp,
 }
+
+impl ParserResultData {
+    /// Expands (nested) MultiResult variants into their individual results
+    pub fn flatten(&self) -> Vec<&ParserResultData> {
+        match self {
+            ParserResultData::MultiResult(results) => {
+                results.iter().flat_map(|data| data.flatten()).collect()
+            }
+            _ => vec![self],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeSet, HashSet};
+
+    fn swap(slot: u64, signature: &str, amount_in: f64) -> SwapInfo {
+        SwapInfo {
+            slot,
+            block_time: 0,
+            signer: "signer".to_string(),
+            signature: signature.to_string(),
+            error: false,
+            dex: DexType::Pumpfun,
+            swap_type: SwapType::Buy,
+            amount_in,
+            token_in: "in".to_string(),
+            amount_out: 1.0,
+            token_out: "out".to_string(),
+            routing_path: vec![],
+        }
+    }
+
+    #[test]
+    fn test_price_extensions() {
+        // buy 500 tokens for 2 SOL
+        let mut buy = swap(1, "a", 2.0);
+        buy.token_in = WSOL.to_string();
+        buy.amount_out = 500.0;
+        assert_eq!(buy.price_ratio(), Some(250.0));
+        assert_eq!(buy.sol_price(), Some(2.0));
+        assert_eq!(buy.usdc_equivalent(150.0), Some(300.0));
+
+        // sell 500 tokens for 2 SOL
+        let mut sell = swap(1, "a", 500.0);
+        sell.swap_type = SwapType::Sell;
+        sell.amount_out = 2.0;
+        sell.token_out = WSOL.to_string();
+        assert_eq!(sell.price_ratio(), Some(0.004));
+        assert_eq!(sell.sol_price(), Some(2.0));
+        assert_eq!(sell.usdc_equivalent(150.0), Some(300.0));
+
+        let mut token = swap(1, "a", 4.0);
+        token.swap_type = SwapType::Token;
+        token.amount_out = 1.0;
+        assert_eq!(token.price_ratio(), Some(0.25));
+        assert_eq!(token.sol_price(), None);
+        assert_eq!(token.usdc_equivalent(150.0), None);
+
+        let empty = swap(1, "a", 0.0);
+        assert_eq!(empty.price_ratio(), None);
+    }
+
+    #[test]
+    fn test_swap_info_ordering() {
+        let swaps = vec![
+            swap(2, "a", 1.0),
+            swap(1, "b", 1.0),
+            swap(1, "a", 2.0),
+            swap(1, "a", 1.0),
+            swap(1, "b", 1.0),
+        ];
+        let sorted = swaps.iter().cloned().collect::<BTreeSet<_>>();
+        assert_eq!(
+            sorted.into_iter().collect::<Vec<_>>(),
+            vec![
+                swap(1, "a", 1.0),
+                swap(1, "a", 2.0),
+                swap(1, "b", 1.0),
+                swap(2, "a", 1.0)
+            ]
+        );
+        assert_eq!(swaps.into_iter().collect::<HashSet<_>>().len(), 4);
+    }
+
+    #[test]
+    fn test_sol_transfer_eq() {
+        let transfer = |lamports: u64, sol: f64| SolTransfer {
+            slot: 1,
+            block_time: 0,
+            signature: "a".to_string(),
+            from: "from".to_string(),
+            to: "to".to_string(),
+            lamports,
+            sol,
+        };
+        assert_eq!(transfer(1, f64::NAN), transfer(1, f64::NAN));
+        assert_ne!(transfer(1, 0.0), transfer(2, 0.0));
+        let transfers = [transfer(1, 0.0), transfer(1, f64::NAN), transfer(2, 0.0)];
+        assert_eq!(transfers.into_iter().collect::<HashSet<_>>().len(), 2);
+    }
+}
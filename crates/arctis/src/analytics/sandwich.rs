@@ -0,0 +1,154 @@
+use anyhow::Result;
+use arctis_types::SandwichAttack;
+use sol_db::solana_db::SolanaDatabase;
+
+struct BlockSwap {
+    slot: u64,
+    signer: String,
+    signature: String,
+    swap_type: String,
+    token: String,
+    token_in: String,
+    amount_in: f64,
+    token_out: String,
+    amount_out: f64,
+}
+
+fn get_swaps_in_block_order(db: &SolanaDatabase) -> Result<Vec<BlockSwap>> {
+    // swaps are appended in the order of their transactions in the block
+    // so the rowid gives us the position of the swap within its slot
+    let mut stmt = db.conn.prepare(
+        "
+      SELECT slot, signer, signature, swap_type::TEXT, token, token_in, amount_in::DOUBLE, token_out, amount_out::DOUBLE
+      FROM swaps
+      WHERE swap_type IN ('Buy', 'Sell')
+      ORDER BY slot, rowid
+      ",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(BlockSwap {
+            slot: row.get(0)?,
+            signer: row.get(1)?,
+            signature: row.get(2)?,
+            swap_type: row.get(3)?,
+            token: row.get(4)?,
+            token_in: row.get(5)?,
+            amount_in: row.get(6)?,
+            token_out: row.get(7)?,
+            amount_out: row.get(8)?,
+        })
+    })?;
+    let mut swaps = vec![];
+    for row in rows {
+        swaps.push(row?);
+    }
+    Ok(swaps)
+}
+
+fn find_sandwiches_in_slot(swaps: &[BlockSwap]) -> Vec<SandwichAttack> {
+    let mut attacks = vec![];
+    for (i, front) in swaps.iter().enumerate() {
+        if front.swap_type != "Buy" {
+            continue;
+        }
+        // first sell of the same token by the same signer closes the sandwich
+        let back = swaps.iter().enumerate().skip(i + 1).find(|(_, swap)| {
+            swap.swap_type == "Sell"
+                && swap.signer == front.signer
+                && swap.token == front.token
+                && swap.token_out == front.token_in
+        });
+        let Some((k, back)) = back else {
+            continue;
+        };
+
+        let victims = swaps[i + 1..k].iter().filter(|swap| {
+            swap.swap_type == "Buy"
+                && swap.signer != front.signer
+                && swap.token == front.token
+                && swap.token_in == front.token_in
+        });
+        for victim in victims {
+            attacks.push(SandwichAttack {
+                slot: front.slot,
+                attacker: front.signer.clone(),
+                victim: victim.signer.clone(),
+                token: front.token.clone(),
+                front_run_sig: front.signature.clone(),
+                back_run_sig: back.signature.clone(),
+                victim_sig: victim.signature.clone(),
+                estimated_profit_sol: back.amount_out - front.amount_in,
+            });
+        }
+    }
+    attacks
+}
+
+/// Finds swaps where the same signer bought and sold a token around another signer's buy in the same slot.
+/// Assumes the quote token is SOL: profit is the SOL received by the back-run minus the SOL spent by the front-run.
+pub fn detect_sandwiches(db: &SolanaDatabase) -> Result<Vec<SandwichAttack>> {
+    let swaps = get_swaps_in_block_order(db)?;
+    let attacks = swaps
+        .chunk_by(|a, b| a.slot == b.slot)
+        .flat_map(find_sandwiches_in_slot)
+        .collect();
+    Ok(attacks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arctis_types::{DexType, SwapInfo, SwapType};
+
+    const WSOL: &str = "So11111111111111111111111111111111111111112";
+
+    fn swap(signer: &str, idx: u32, swap_type: SwapType, sol: f64, amount: f64) -> SwapInfo {
+        let (token_in, amount_in, token_out, amount_out) = match swap_type {
+            SwapType::Buy => (WSOL, sol, "TokenA", amount),
+            _ => ("TokenA", amount, WSOL, sol),
+        };
+        SwapInfo {
+            slot: 1,
+            block_time: 1_700_000_000,
+            signer: signer.to_string(),
+            signature: format!("sig_{}", idx),
+            error: false,
+            dex: DexType::Pumpfun,
+            swap_type,
+            amount_in,
+            token_in: token_in.to_string(),
+            amount_out,
+            token_out: token_out.to_string(),
+            routing_path: vec![],
+        }
+    }
+
+    #[test]
+    fn test_detect_sandwiches() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let swaps = [
+            swap("attacker", 0, SwapType::Buy, 1.0, 1000.0),
+            swap("victim", 1, SwapType::Buy, 2.0, 1500.0),
+            swap("attacker", 2, SwapType::Sell, 1.5, 1000.0),
+            // regular trader: no victim in between
+            swap("trader", 3, SwapType::Buy, 1.0, 500.0),
+            swap("trader", 4, SwapType::Sell, 1.0, 500.0),
+        ];
+        let swaps: Vec<&SwapInfo> = swaps.iter().collect();
+        db.insert_swaps_bulk(&swaps).unwrap();
+
+        let attacks = detect_sandwiches(&db).unwrap();
+        assert_eq!(attacks.len(), 1);
+        let attack = &attacks[0];
+        assert_eq!(attack.attacker, "attacker");
+        assert_eq!(attack.victim, "victim");
+        assert_eq!(attack.token, "TokenA");
+        assert_eq!(attack.front_run_sig, "sig_0");
+        assert_eq!(attack.victim_sig, "sig_1");
+        assert_eq!(attack.back_run_sig, "sig_2");
+        assert_eq!(attack.estimated_profit_sol, 0.5);
+
+        let attacks: Vec<&SandwichAttack> = attacks.iter().collect();
+        assert_eq!(db.insert_sandwich_attacks_bulk(&attacks).unwrap(), 1);
+    }
+}
@@ -1,15 +1,31 @@
 use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
+pub use sol_db::solana_db::S3Config;
+pub use sol_lib::client::{parse_commitment, CommitmentConfig};
+
+use crate::logger::LogFormat;
 
 #[derive(Debug, Deserialize)]
 pub struct RpcConfig {
     pub solana_rpc_url: String,
     pub solana_ws_url: String,
+    #[serde(default)]
+    pub solana_rpc_fallbacks: Vec<String>,
+    pub geyser_endpoint: Option<String>,
+    pub geyser_token: Option<String>,
+    /// processed | confirmed | finalized
+    pub commitment: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Settings {
+    /// human | json (default: human)
+    pub log_format: Option<String>,
+    /// OTLP collector for spans, only used with the `tracing` feature
+    pub tracing_endpoint: Option<String>,
     pub rpc: RpcConfig,
+    /// credentials for exports to S3
+    pub s3: Option<S3Config>,
 }
 
 impl Settings {
@@ -23,7 +39,29 @@ impl Settings {
             .build()?; // Build the config
 
         // Try to deserialize the settings into the Settings struct
-        settings.try_deserialize()
+        let settings: Settings = settings.try_deserialize()?;
+        settings.get_commitment()?;
+        settings.get_log_format()?;
+        Ok(settings)
+    }
+
+    /// the configured commitment, defaults to confirmed
+    pub fn get_commitment(&self) -> Result<CommitmentConfig, ConfigError> {
+        match &self.rpc.commitment {
+            Some(commitment) => {
+                parse_commitment(commitment).map_err(|e| ConfigError::Message(e.to_string()))
+            }
+            None => Ok(CommitmentConfig::confirmed()),
+        }
+    }
+
+    pub fn get_log_format(&self) -> Result<LogFormat, ConfigError> {
+        match &self.log_format {
+            Some(format) => format
+                .parse()
+                .map_err(|e: std::io::Error| ConfigError::Message(e.to_string())),
+            None => Ok(LogFormat::Human),
+        }
     }
 }
 
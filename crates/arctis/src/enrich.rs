@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use arctis_types::{NewToken, TokenMetadata};
+use serde_json::Value;
+use sol_db::solana_db::SolanaDatabase;
+use sol_lib::ipfs::{fetch_ipfs_metadata, get_best_gateway, get_cid_from_url};
+
+use crate::logger::log_message;
+
+async fn fetch_uri_metadata(uri: &str) -> Result<Value> {
+    // ipfs uris are resolved via our own gateway rotation, everything else (arweave, ..) is fetched directly
+    if let Some(cid) = get_cid_from_url(uri) {
+        return fetch_ipfs_metadata(&cid, get_best_gateway(&cid)).await;
+    }
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()?;
+    let response = client.get(uri).send().await?.error_for_status()?;
+    Ok(response.json::<Value>().await?)
+}
+
+fn get_string_field(metadata: &Value, key: &str) -> Option<String> {
+    metadata
+        .get(key)
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+}
+
+pub async fn enrich_token_metadata(token: &mut NewToken) -> Result<TokenMetadata> {
+    // some tokens are created with padded uris
+    token.uri = token.uri.trim_matches(char::from(0)).trim().to_string();
+
+    let metadata = fetch_uri_metadata(&token.uri).await?;
+    Ok(TokenMetadata {
+        mint: token.mint.clone(),
+        image_url: get_string_field(&metadata, "image"),
+        description: get_string_field(&metadata, "description"),
+        twitter: get_string_field(&metadata, "twitter"),
+        telegram: get_string_field(&metadata, "telegram"),
+    })
+}
+
+/// fetches metadata for all tokens and stores it in the metadata table
+/// tokens whose metadata can't be fetched are skipped
+pub async fn enrich_tokens(
+    tokens: &mut [NewToken],
+    solana_db: &mut SolanaDatabase,
+) -> Result<usize> {
+    let mut metadata = vec![];
+    for token in tokens.iter_mut() {
+        match enrich_token_metadata(token).await {
+            Ok(meta) => metadata.push(meta),
+            Err(e) => log_message(
+                &["Enrich"],
+                &format!("Failed to fetch metadata for {}: {:?}", token.mint, e),
+                None,
+            ),
+        }
+    }
+    let metadata: Vec<&TokenMetadata> = metadata.iter().collect();
+    let inserted = solana_db.insert_token_metadata_bulk(&metadata)?;
+    Ok(inserted)
+}
+
+/// fetches metadata for all tokens of the database, see `enrich_tokens`
+pub async fn enrich_new_tokens(solana_db: &mut SolanaDatabase) -> Result<usize> {
+    let mut tokens = solana_db.get_new_tokens(None, None)?;
+    enrich_tokens(&mut tokens, solana_db).await
+}
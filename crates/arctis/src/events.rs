@@ -0,0 +1,136 @@
+use std::fmt;
+use std::sync::Arc;
+
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+
+use crate::logger::log_message;
+
+/// Lifecycle events emitted by `parse_blocks` and `process_monitored_blocks`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum PipelineEvent {
+    BlockDownloaded {
+        slot: u64,
+        retry_count: u8,
+    },
+    BlockParsed {
+        slot: u64,
+        tx_count: u32,
+        swap_count: u32,
+    },
+    /// not emitted by arctis: for embedders that upload the parsed databases
+    BlockUploadStarted {
+        slot: u64,
+    },
+    /// not emitted by arctis: for embedders that upload the parsed databases
+    BlockUploadComplete {
+        slot: u64,
+    },
+    BlockFailed {
+        slot: u64,
+        error: String,
+    },
+    PipelineComplete {
+        total_blocks: u64,
+        elapsed_ms: u64,
+    },
+}
+
+impl fmt::Display for PipelineEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineEvent::BlockDownloaded { slot, retry_count } => {
+                write!(f, "Downloaded block {} ({} retries)", slot, retry_count)
+            }
+            PipelineEvent::BlockParsed {
+                slot,
+                tx_count,
+                swap_count,
+            } => write!(
+                f,
+                "Parsed block {}: {} txs, {} swaps",
+                slot, tx_count, swap_count
+            ),
+            PipelineEvent::BlockUploadStarted { slot } => write!(f, "Uploading block {}", slot),
+            PipelineEvent::BlockUploadComplete { slot } => write!(f, "Uploaded block {}", slot),
+            PipelineEvent::BlockFailed { slot, error } => {
+                write!(f, "Block {} failed: {}", slot, error)
+            }
+            PipelineEvent::PipelineComplete {
+                total_blocks,
+                elapsed_ms,
+            } => write!(f, "Processed {} blocks in {} ms", total_blocks, elapsed_ms),
+        }
+    }
+}
+
+pub type EventHandler = Arc<dyn Fn(PipelineEvent) + Send + Sync>;
+
+/// Wraps the handler so it can be cloned into the parse workers
+#[derive(Clone)]
+pub struct PipelineEvents {
+    handler: EventHandler,
+}
+
+impl PipelineEvents {
+    /// e.g. `PrintProgressEventHandler::handler(total_blocks)` or a custom handler for monitoring
+    pub fn new(handler: EventHandler) -> Self {
+        PipelineEvents { handler }
+    }
+
+    pub fn emit(&self, event: PipelineEvent) {
+        (self.handler)(event);
+    }
+}
+
+impl Default for PipelineEvents {
+    fn default() -> Self {
+        PipelineEvents::new(LoggingEventHandler::handler())
+    }
+}
+
+impl fmt::Debug for PipelineEvents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PipelineEvents")
+    }
+}
+
+/// Default handler: writes every event to the log
+pub struct LoggingEventHandler;
+
+impl LoggingEventHandler {
+    pub fn handler() -> EventHandler {
+        Arc::new(|event| Self::handle(&event))
+    }
+
+    pub fn handle(event: &PipelineEvent) {
+        // the event fields are added to json logs
+        log_message(
+            &["Pipeline"],
+            &event.to_string(),
+            serde_json::to_value(event).ok(),
+        );
+    }
+}
+
+/// Draws a progress bar that advances once per parsed or failed block
+pub struct PrintProgressEventHandler;
+
+impl PrintProgressEventHandler {
+    pub fn handler(total_blocks: u64) -> EventHandler {
+        let progress = ProgressBar::new(total_blocks);
+        progress.set_style(
+            ProgressStyle::with_template("{bar:40} {pos}/{len} blocks ({eta}) {msg}")
+                .unwrap_or(ProgressStyle::default_bar()),
+        );
+        Arc::new(move |event| match event {
+            PipelineEvent::BlockParsed { .. } => progress.inc(1),
+            PipelineEvent::BlockFailed { slot, .. } => {
+                progress.set_message(format!("failed: {}", slot));
+                progress.inc(1);
+            }
+            PipelineEvent::PipelineComplete { .. } => progress.finish(),
+            _ => {}
+        })
+    }
+}
@@ -1,3 +1,7 @@
+pub mod analytics;
 pub mod config;
+pub mod enrich;
+pub mod events;
+pub mod logger;
 pub mod parse;
 pub mod run;
@@ -1,9 +1,77 @@
-use chrono::Utc;
+use chrono::{SecondsFormat, Utc};
 use flexi_logger::{DeferredNow, Duplicate, LogSpecBuilder, Logger};
-use log::{info, LevelFilter, Record};
-use std::io::{Result, Write};
+use log::kv::{self, Key, VisitSource};
+use log::{LevelFilter, Record};
+use serde_json::{Map, Value};
+use std::io::{Error, ErrorKind, Result, Write};
+use std::str::FromStr;
+
+pub use sol_lib::utils::log_message;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// 2024-01-01 12:37:24:873 [target] message
+    Human,
+    /// newline-delimited json for ELK, Loki, ...
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "human" => Ok(LogFormat::Human),
+            "json" => Ok(LogFormat::Json),
+            other => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid log format '{}': expected human or json", other),
+            )),
+        }
+    }
+}
+
+/// Exports spans to an OTLP (grpc) collector, e.g. http://localhost:4317.
+/// Requires a tokio runtime.
+#[cfg(feature = "tracing")]
+pub fn init_tracing(endpoint: &str, service_name: &str) -> Result<()> {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use opentelemetry_sdk::{runtime, Resource};
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| Error::other(e.to_string()))?;
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            service_name.to_string(),
+        )]))
+        .build();
+    let tracer = provider.tracer(service_name.to_string());
+    opentelemetry::global::set_tracer_provider(provider);
+
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()
+        .map_err(|e| Error::other(e.to_string()))?;
+
+    eprintln!("Tracing initialized: {}", endpoint);
+    Ok(())
+}
 
 pub fn init_logger() -> Result<()> {
+    init_logger_with_format(LogFormat::Human)
+}
+
+pub fn init_logger_with_format(format: LogFormat) -> Result<()> {
     // Step 1: Configure log channels (WS, RPC, target) and their filtering rules
     let log_spec = LogSpecBuilder::new()
         .default(LevelFilter::Info) // Default log level
@@ -11,14 +79,17 @@ pub fn init_logger() -> Result<()> {
         .module("HTTP", LevelFilter::Info) // Enable logging for HTTP module
         .module("WS", LevelFilter::Info) // Enable logging for WS module
         .module("RPC", LevelFilter::Info) // Enable logging for RPC module
-        .module("PERF", LevelFilter::Info) 
+        .module("PERF", LevelFilter::Info)
         .module("target", LevelFilter::Info)
         .module("client", LevelFilter::Info)
         .build();
 
     // Step 2: Initialize the logger
     Logger::with(log_spec)
-        .format(custom_log_format) // Use custom log format
+        .format(match format {
+            LogFormat::Human => custom_log_format,
+            LogFormat::Json => json_log_format,
+        })
         .log_to_file(flexi_logger::FileSpec::default().directory("logs"))
         .use_utc()
         .duplicate_to_stderr(Duplicate::All) // Also print logs to stdout
@@ -32,7 +103,7 @@ pub fn init_logger() -> Result<()> {
 // Custom log format
 pub fn custom_log_format(
     writer: &mut dyn Write, // A dynamic writer (stdout, file, etc.)
-    _now: &mut DeferredNow,  // DeferredNow to handle time formatting
+    _now: &mut DeferredNow, // DeferredNow to handle time formatting
     record: &Record,        // The actual log record
 ) -> Result<()> {
     // Format the time using chrono - UTC required
@@ -47,8 +118,82 @@ pub fn custom_log_format(
     )
 }
 
-// This function accepts a flexible number of channels (up to 3)
-pub fn log_message(channels: &[&str], message: &str) {
-    let formatted_channels = channels.join("][");
-    info!("[{}] {}", formatted_channels, message);
+// Collects the key values of a record, the fields of `extra` are merged into the object
+struct JsonFields<'a>(&'a mut Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(
+        &mut self,
+        key: Key<'kvs>,
+        value: kv::Value<'kvs>,
+    ) -> std::result::Result<(), kv::Error> {
+        match serde_json::to_value(&value) {
+            Ok(Value::Object(fields)) if key.as_str() == "extra" => self.0.extend(fields),
+            Ok(value) => {
+                self.0.insert(key.to_string(), value);
+            }
+            Err(_) => {
+                self.0
+                    .insert(key.to_string(), Value::String(value.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+// {"ts":"...","level":"...","target":"...","message":"..."} per line
+pub fn json_log_format(
+    writer: &mut dyn Write,
+    _now: &mut DeferredNow,
+    record: &Record,
+) -> Result<()> {
+    let mut object = Map::new();
+    let time = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+    object.insert("ts".to_string(), Value::String(time));
+    object.insert(
+        "level".to_string(),
+        Value::String(record.level().to_string()),
+    );
+    object.insert(
+        "target".to_string(),
+        Value::String(record.target().to_string()),
+    );
+    object.insert(
+        "message".to_string(),
+        Value::String(record.args().to_string()),
+    );
+    let _ = record.key_values().visit(&mut JsonFields(&mut object));
+
+    writeln!(writer, "{}", Value::Object(object))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_log_format() {
+        let extra = serde_json::json!({ "slot": 42 });
+        let key_values = [("extra", kv::Value::from_serde(&extra))];
+        let mut output = vec![];
+        json_log_format(
+            &mut output,
+            &mut DeferredNow::new(),
+            &Record::builder()
+                .args(format_args!("[WS] Gap detected"))
+                .level(log::Level::Warn)
+                .target("sol_lib::blocks")
+                .key_values(&key_values)
+                .build(),
+        )
+        .unwrap();
+
+        let line: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["target"], "sol_lib::blocks");
+        assert_eq!(line["message"], "[WS] Gap detected");
+        assert_eq!(line["slot"], 42);
+        assert!(line["ts"].as_str().unwrap().ends_with('Z'));
+        assert!("yaml".parse::<LogFormat>().is_err());
+    }
 }
@@ -2,25 +2,73 @@ use std::collections::HashMap;
 use std::time::Instant;
 
 use anyhow::{anyhow, Result};
-use arctis_types::{ComputeBudgetInstruction, ParserResult, ParserResultData, UiConfirmedBlock};
+use arctis_types::{BlockInfo, ComputeBudgetInstruction, ParserResultData, UiConfirmedBlock};
+use serde::Serialize;
+use serde_json::Value;
 use sol_db::solana_db::{
-    ComputeBudgetProcessed, ProcessedBlock, ProcessedTransaction, ProgramParserData, SolanaDatabase,
+    AccountProcessed, ComputeBudgetProcessed, ProcessedBlock, ProcessedTransaction,
+    ProgramParserData, RewardProcessed, SolanaDatabase, TransactionParseStats,
 };
+use sol_lib::blocks::get_block_leader;
 
-use super::transaction::process_transaction;
+use super::metrics::ParserMetricsMap;
+use super::transaction::{process_transaction, TransactionProcessingOptions};
 
-pub fn process_block(block: &UiConfirmedBlock, solana_db: &mut SolanaDatabase) -> Result<()> {
+/// summary of a processed block, the counts are the rows written to the database
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct BlockParseStats {
+    pub slot: u64,
+    pub tx_count: u32,
+    pub discarded_tx_count: u32,
+    pub swap_count: u32,
+    pub new_token_count: u32,
+    pub sol_transfer_count: u32,
+    pub token_transfer_count: u32,
+    /// processing and inserting all transactions
+    pub parse_duration_ms: u64,
+    /// instructions whose parser returned an error
+    pub parse_errors: u32,
+}
+
+impl BlockParseStats {
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap()
+    }
+}
+
+pub fn process_block(
+    block: &UiConfirmedBlock,
+    solana_db: &mut SolanaDatabase,
+    metrics: Option<&ParserMetricsMap>,
+    options: &TransactionProcessingOptions,
+) -> Result<BlockParseStats> {
+    let ts_start = Instant::now();
     let transactions = block.transactions.as_ref().unwrap();
     let tx_count = transactions.len();
 
     let slot = block.parent_slot + 1;
     let block_time = block.block_time.unwrap();
 
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("arctis.process_block", slot).entered();
+
+    let (leader, rewards) = get_block_leader(block).unzip();
+    let block_info = BlockInfo {
+        slot,
+        block_time,
+        blockhash: block.blockhash.clone(),
+        leader,
+        rewards,
+    };
+
     let p_block = ProcessedBlock {
         slot,
         block_time,
         parent_slot: block.parent_slot,
         transaction_count: tx_count as u32,
+        blockhash: block_info.blockhash.clone(),
+        leader: block_info.leader.clone(),
+        rewards: block_info.rewards,
     };
 
     let res = solana_db.insert_block(&p_block);
@@ -31,7 +79,7 @@ pub fn process_block(block: &UiConfirmedBlock, solana_db: &mut SolanaDatabase) -
     let ts_start_process_tx = Instant::now();
     let mut processed_tx = vec![];
     for tx in transactions {
-        let ptx = process_transaction(tx, slot, block_time);
+        let ptx = process_transaction(tx, &block_info, metrics, options);
         match ptx {
             Ok(ptx) => processed_tx.push(ptx),
             Err(_err) => {
@@ -42,17 +90,73 @@ pub fn process_block(block: &UiConfirmedBlock, solana_db: &mut SolanaDatabase) -
     } // end tx loop
     let _elapsed = ts_start_process_tx.elapsed();
 
-    write_transactions_with_instructions_db(solana_db, slot, block_time, processed_tx)?;
+    let discarded_tx_count = processed_tx.iter().filter(|tx| tx.is_discarded).count() as u32;
+    let parse_errors = processed_tx
+        .iter()
+        .flat_map(|tx| tx.parsed_programs.iter())
+        .filter(|program| program.error)
+        .count() as u32;
+
+    // (signature, parse_us, ix_count) before the transactions are moved into the db
+    let parse_timings: Vec<(String, u64, u32)> = match options.with_parse_timing {
+        true => processed_tx
+            .iter()
+            .map(|tx| {
+                let ix_count = tx.top_level_ix_count as u32 + tx.inner_ix_count as u32;
+                (tx.signature.clone(), tx.parse_duration_us, ix_count)
+            })
+            .collect(),
+        false => vec![],
+    };
+
+    let ts_start_insert = Instant::now();
+    let mut stats =
+        write_transactions_with_instructions_db(solana_db, slot, block_time, processed_tx)?;
 
-    Ok(())
+    if options.with_parse_timing {
+        // inserts are done in bulk: the insert time is split evenly between the transactions
+        let insert_us = ts_start_insert.elapsed().as_micros() as u64 / tx_count.max(1) as u64;
+        let parse_stats = parse_timings
+            .into_iter()
+            .map(|(signature, parse_us, ix_count)| TransactionParseStats {
+                slot,
+                signature,
+                parse_us,
+                insert_us,
+                ix_count,
+            })
+            .collect();
+        let res = solana_db.insert_parse_stats_bulk(&parse_stats);
+        if res.is_err() {
+            return Err(anyhow!("Failed to insert parse stats"));
+        }
+    }
+
+    stats.tx_count = tx_count as u32;
+    stats.discarded_tx_count = discarded_tx_count;
+    stats.parse_errors = parse_errors;
+    stats.parse_duration_ms = ts_start.elapsed().as_millis() as u64;
+    Ok(stats)
 }
 
+/// all or nothing: a failed bulk insert must not leave a partially written block behind
 fn write_transactions_with_instructions_db(
     solana_db: &mut SolanaDatabase,
     slot: u64,
     block_time: i64,
     processed_tx: Vec<ProcessedTransaction>,
-) -> Result<()> {
+) -> Result<BlockParseStats> {
+    solana_db.with_transaction(|db| {
+        insert_transactions_with_instructions(db, slot, block_time, processed_tx)
+    })
+}
+
+fn insert_transactions_with_instructions(
+    solana_db: &mut SolanaDatabase,
+    slot: u64,
+    block_time: i64,
+    processed_tx: Vec<ProcessedTransaction>,
+) -> Result<BlockParseStats> {
     let ts_start = Instant::now();
     let res = solana_db.insert_transactions_bulk(&processed_tx);
     if res.is_err() {
@@ -75,12 +179,13 @@ fn write_transactions_with_instructions_db(
     }
 
     // handle parsed program instructions
-    let all_parsed_program_ix: Vec<(String, &ParserResult)> = processed_tx
+    let all_parsed_program_ix: Vec<(String, &ParserResultData)> = processed_tx
         .iter()
         .flat_map(|tx| {
             tx.parsed_ix
                 .iter()
-                .map(move |ix| (tx.signature.clone(), ix))
+                .flat_map(|ix| ix.data.flatten())
+                .map(move |data| (tx.signature.clone(), data))
         })
         .collect();
 
@@ -89,14 +194,16 @@ fn write_transactions_with_instructions_db(
     let mut token_transfers = vec![];
     let mut swaps = vec![];
     let mut tokens = vec![];
+    let mut metadata_updates = vec![];
     let mut supply_changes = vec![];
+    let mut accounts = vec![];
+    let mut completions = vec![];
+    let mut rewards = vec![];
 
     let mut fees: HashMap<String, ComputeBudgetProcessed> = HashMap::new();
 
-    for (signature, ppd) in all_parsed_program_ix {
-        let data = &ppd.data;
-        let _ix_type = &ppd.ix_type;
-        match &data {
+    for (signature, data) in all_parsed_program_ix {
+        match data {
             ParserResultData::SolTransfer(transfer) => {
                 sol_transfers.push(transfer);
             }
@@ -106,12 +213,43 @@ fn write_transactions_with_instructions_db(
             ParserResultData::Swap(swap) => {
                 swaps.push(swap);
             }
+            ParserResultData::MultiSwap(multi_swap) => {
+                swaps.extend(multi_swap.iter());
+            }
             ParserResultData::Token(token) => {
                 tokens.push(token);
             }
+            ParserResultData::MetadataUpdate(update) => {
+                metadata_updates.push(update);
+            }
             ParserResultData::Supply(supply_change) => {
                 supply_changes.push(supply_change);
             }
+            ParserResultData::BondingCurveComplete(completion) => {
+                completions.push(completion);
+            }
+            ParserResultData::Reward {
+                mint,
+                amount,
+                authority,
+            } => {
+                rewards.push(RewardProcessed {
+                    slot,
+                    block_time,
+                    signature: signature.clone(),
+                    mint: mint.clone(),
+                    amount: *amount,
+                    authority: authority.clone(),
+                });
+            }
+            ParserResultData::Account(account) => {
+                accounts.push(AccountProcessed {
+                    slot,
+                    block_time,
+                    signature: signature.clone(),
+                    account: account.clone(),
+                });
+            }
             // TODO collect in hashmap
             ParserResultData::ComputeBudget(budget) => {
                 match budget {
@@ -172,18 +310,48 @@ fn write_transactions_with_instructions_db(
         return Err(anyhow!("Failed to insert swaps"));
     }
 
+    // insert the routing paths of aggregator swaps
+    let res = solana_db.insert_swap_hops_bulk(&swaps);
+    if res.is_err() {
+        return Err(anyhow!("Failed to insert swap hops"));
+    }
+
     // insert tokens bulk
     let res = solana_db.insert_tokens_bulk(&tokens);
     if res.is_err() {
         return Err(anyhow!("Failed to insert tokens"));
     }
 
+    // apply metadata updates after the tokens of the same block were inserted
+    let res = solana_db.insert_token_metadata_updates_bulk(&metadata_updates);
+    if res.is_err() {
+        return Err(anyhow!("Failed to insert token metadata updates"));
+    }
+
     // insert supply changes bulk
     let res = solana_db.insert_supply_changes_bulk(&supply_changes);
     if res.is_err() {
         return Err(anyhow!("Failed to insert supply changes"));
     }
 
+    // insert account events bulk
+    let res = solana_db.insert_accounts_bulk(&accounts);
+    if res.is_err() {
+        return Err(anyhow!("Failed to insert accounts"));
+    }
+
+    // insert bonding curve completions bulk
+    let res = solana_db.insert_bonding_curve_completions_bulk(&completions);
+    if res.is_err() {
+        return Err(anyhow!("Failed to insert bonding curve completions"));
+    }
+
+    // insert staking rewards bulk
+    let res = solana_db.insert_rewards_bulk(&rewards);
+    if res.is_err() {
+        return Err(anyhow!("Failed to insert rewards"));
+    }
+
     // insert fees
     let fees: Vec<ComputeBudgetProcessed> = fees.into_values().collect();
     let res = solana_db.insert_compute_budget_bulk(&fees);
@@ -191,5 +359,359 @@ fn write_transactions_with_instructions_db(
         return Err(anyhow!("Failed to insert fees"));
     }
 
-    Ok(())
+    Ok(BlockParseStats {
+        slot,
+        swap_count: swaps.len() as u32,
+        new_token_count: tokens.len() as u32,
+        sol_transfer_count: sol_transfers.len() as u32,
+        token_transfer_count: token_transfers.len() as u32,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::transaction::BlockFilter;
+    use arctis_types::{
+        AccountInfo, BlockInfo, DexType, EncodedTransactionWithStatusMeta, ParserResult,
+        SplTokenTransfer, SwapInfo, SwapType,
+    };
+    use sol_lib::transaction::parsers::{Parser, ParserError, ParserRegistry};
+    use sol_lib::transaction::wrapper::TransactionWrapper;
+    use sol_lib::transaction::InstructionWrapper;
+    use sol_lib::utils::TestTransactionBuilder;
+    use std::sync::Arc;
+
+    fn swap(signer: &str, swap_type: SwapType) -> SwapInfo {
+        SwapInfo {
+            slot: 1,
+            block_time: 1_700_000_000,
+            signer: signer.to_string(),
+            signature: "sig".to_string(),
+            error: false,
+            dex: DexType::OpenBook,
+            swap_type,
+            amount_in: 1.5,
+            token_in: "TokenA".to_string(),
+            amount_out: 2.0,
+            token_out: "TokenB".to_string(),
+            routing_path: vec![],
+        }
+    }
+
+    fn processed_tx(parsed_ix: Vec<ParserResult>) -> ProcessedTransaction {
+        ProcessedTransaction {
+            slot: 1,
+            block_time: 1_700_000_000,
+            signer: "taker".to_string(),
+            signature: "sig".to_string(),
+            has_error: false,
+            top_level_ix_count: 1,
+            inner_ix_count: 0,
+            compute_units_consumed: 0,
+            fee: 5000,
+            version: 0,
+            is_discarded: true,
+            discard_reason: None,
+            parsed_programs: vec![],
+            parsed_ix,
+            data: None,
+            jito_tip_lamports: None,
+            parse_duration_us: 0,
+        }
+    }
+
+    #[test]
+    fn test_write_multi_swap() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let tx = processed_tx(vec![ParserResult {
+            parsed: true,
+            ix_type: "Fill".to_string(),
+            data: ParserResultData::MultiSwap(vec![
+                swap("taker", SwapType::Buy),
+                swap("maker", SwapType::Sell),
+            ]),
+        }]);
+        write_transactions_with_instructions_db(&mut db, 1, 1_700_000_000, vec![tx]).unwrap();
+
+        let swaps = db.get_swaps().unwrap();
+        assert_eq!(swaps.len(), 2);
+        assert!(swaps.contains(&swap("taker", SwapType::Buy)));
+        assert!(swaps.contains(&swap("maker", SwapType::Sell)));
+    }
+
+    #[test]
+    fn test_write_multi_result() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let account = AccountInfo {
+            account: "nested".to_string(),
+            owner: "owner_ata".to_string(),
+            open_tx: None,
+            init_tx: None,
+            close_tx: Some("sig".to_string()),
+            close_destination: Some("wallet".to_string()),
+            mint: Some("TokenA".to_string()),
+            decimals: Some(6),
+        };
+        let transfer = SplTokenTransfer {
+            slot: 1,
+            block_time: 1_700_000_000,
+            signature: "sig".to_string(),
+            from_acc: "nested".to_string(),
+            to_acc: "wallet_ata".to_string(),
+            amount: 1000.0,
+            raw_amount: 1000,
+            authority: Some("owner_ata".to_string()),
+            from: Some("owner_ata".to_string()),
+            to: Some("wallet".to_string()),
+            decimals: Some(6),
+            token: Some("TokenA".to_string()),
+        };
+        let tx = processed_tx(vec![ParserResult {
+            parsed: true,
+            ix_type: "recoverNested".to_string(),
+            data: ParserResultData::MultiResult(vec![
+                ParserResultData::Account(account),
+                ParserResultData::TokenTransfer(transfer),
+            ]),
+        }]);
+        write_transactions_with_instructions_db(&mut db, 1, 1_700_000_000, vec![tx]).unwrap();
+
+        assert_eq!(db.count_rows("token_transfers").unwrap(), 1);
+        assert_eq!(db.count_rows("accounts").unwrap(), 1);
+    }
+
+    // minimal json encoded tx with a single instruction
+    fn test_tx(signature: &str, program_id: &str) -> serde_json::Value {
+        TestTransactionBuilder::new(&["Signer1111111111111111111111111111111111111", program_id])
+            .signatures(&[signature])
+            .instruction(1, &[0], &[])
+            .sol_balance(0, 1_000_000, 995_000)
+            .sol_balance(1, 1, 1)
+            .to_json()
+    }
+
+    #[test]
+    fn test_process_block_filter_programs() {
+        let block: UiConfirmedBlock = serde_json::from_value(serde_json::json!({
+            "previousBlockhash": "11111111111111111111111111111111",
+            "blockhash": "11111111111111111111111111111111",
+            "parentSlot": 99,
+            "transactions": [
+                test_tx("sig_a", "ProgramA11111111111111111111111111111111111"),
+                test_tx("sig_b", "ProgramB11111111111111111111111111111111111"),
+            ],
+            "blockTime": 1_700_000_000,
+            "blockHeight": null
+        }))
+        .unwrap();
+
+        let mut db = SolanaDatabase::new().unwrap();
+        let options = TransactionProcessingOptions::for_programs(&[
+            "ProgramA11111111111111111111111111111111111",
+        ]);
+        process_block(&block, &mut db, None, &options).unwrap();
+
+        assert_eq!(db.count_rows("transactions").unwrap(), 2);
+        assert_eq!(
+            db.count_rows_where("transactions", "discard_reason = 'Filtered'")
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            db.count_rows_where("transactions", "signature = 'sig_b' AND is_discarded")
+                .unwrap(),
+            1
+        );
+        // only the matching tx was handed to the parsers
+        assert_eq!(db.count_rows("tx_programs").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_process_block_with_block_filter() {
+        let block: UiConfirmedBlock = serde_json::from_value(serde_json::json!({
+            "previousBlockhash": "11111111111111111111111111111111",
+            "blockhash": "11111111111111111111111111111111",
+            "parentSlot": 99,
+            "transactions": [
+                test_tx("sig_a", "ProgramA11111111111111111111111111111111111"),
+                test_tx("sig_b", "ProgramB11111111111111111111111111111111111"),
+            ],
+            "blockTime": 1_700_000_000,
+            "blockHeight": null
+        }))
+        .unwrap();
+        let filtered_count = |block_filter: BlockFilter| {
+            let mut db = SolanaDatabase::new().unwrap();
+            let options = TransactionProcessingOptions {
+                block_filter: Some(block_filter),
+                ..Default::default()
+            };
+            process_block(&block, &mut db, None, &options).unwrap();
+            assert_eq!(db.count_rows("transactions").unwrap(), 2);
+            db.count_rows_where("transactions", "discard_reason = 'Filtered'")
+                .unwrap()
+        };
+
+        assert_eq!(filtered_count(BlockFilter::default()), 0);
+        let block_filter = BlockFilter {
+            require_programs: Some(vec![
+                "ProgramB11111111111111111111111111111111111".to_string()
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(filtered_count(block_filter), 1);
+        let block_filter = BlockFilter {
+            exclude_signers: Some(vec![
+                "Signer1111111111111111111111111111111111111".to_string()
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(filtered_count(block_filter), 2);
+        let block_filter = BlockFilter {
+            min_instruction_count: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(filtered_count(block_filter), 2);
+    }
+
+    #[test]
+    fn test_process_block_parse_timing() {
+        let block: UiConfirmedBlock = serde_json::from_value(serde_json::json!({
+            "previousBlockhash": "11111111111111111111111111111111",
+            "blockhash": "11111111111111111111111111111111",
+            "parentSlot": 99,
+            "transactions": [
+                test_tx("sig_a", "ProgramA11111111111111111111111111111111111"),
+                test_tx("sig_b", "ProgramB11111111111111111111111111111111111"),
+            ],
+            "blockTime": 1_700_000_000,
+            "blockHeight": null
+        }))
+        .unwrap();
+
+        let mut db = SolanaDatabase::new().unwrap();
+        let stats = process_block(&block, &mut db, None, &Default::default()).unwrap();
+        assert_eq!(db.count_rows("parse_stats").unwrap(), 0);
+        assert_eq!(stats.slot, 100);
+        assert_eq!(stats.tx_count, 2);
+        assert_eq!(stats.swap_count, 0);
+        assert_eq!(stats.to_json()["tx_count"], 2);
+
+        let mut db = SolanaDatabase::new().unwrap();
+        let options = TransactionProcessingOptions {
+            with_parse_timing: true,
+            ..Default::default()
+        };
+        process_block(&block, &mut db, None, &options).unwrap();
+        let stats = db.get_slowest_transactions(10).unwrap();
+        assert_eq!(stats.len(), 2);
+        assert!(stats
+            .iter()
+            .all(|stat| stat.slot == 100 && stat.ix_count == 1));
+    }
+
+    #[test]
+    fn test_process_block_skip_noop_transactions() {
+        let block: UiConfirmedBlock = serde_json::from_value(serde_json::json!({
+            "previousBlockhash": "11111111111111111111111111111111",
+            "blockhash": "11111111111111111111111111111111",
+            "parentSlot": 99,
+            "transactions": [
+                test_tx("sig_a", "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"),
+                test_tx("sig_b", "ProgramB11111111111111111111111111111111111"),
+            ],
+            "blockTime": 1_700_000_000,
+            "blockHeight": null
+        }))
+        .unwrap();
+
+        let mut db = SolanaDatabase::new().unwrap();
+        process_block(&block, &mut db, None, &Default::default()).unwrap();
+        assert_eq!(db.count_rows("tx_programs").unwrap(), 2);
+
+        let mut db = SolanaDatabase::new().unwrap();
+        let options = TransactionProcessingOptions {
+            skip_noop_transactions: true,
+            ..Default::default()
+        };
+        process_block(&block, &mut db, None, &options).unwrap();
+        assert_eq!(db.count_rows("transactions").unwrap(), 2);
+        // the memo tx is stored without running the parsers
+        assert_eq!(
+            db.count_rows_where("transactions", "signature = 'sig_a' AND is_discarded")
+                .unwrap(),
+            1
+        );
+        assert_eq!(db.count_rows("tx_programs").unwrap(), 1);
+    }
+
+    struct FailingParser(fn() -> ParserError);
+    impl Parser for FailingParser {
+        fn parse(
+            &self,
+            _ix: &InstructionWrapper,
+            _tx: &TransactionWrapper,
+            _block: &BlockInfo,
+        ) -> Result<ParserResult, ParserError> {
+            Err((self.0)())
+        }
+    }
+
+    #[test]
+    fn test_process_transaction_parse_errors() {
+        let registry = ParserRegistry::new();
+        registry.register("NotMine111111111111111111111111111111111111", || {
+            Box::new(FailingParser(|| ParserError::NotMyInstruction))
+        });
+        registry.register("BadData111111111111111111111111111111111111", || {
+            Box::new(FailingParser(|| {
+                ParserError::MalformedData("bad".to_string())
+            }))
+        });
+        registry.register("RpcError11111111111111111111111111111111111", || {
+            Box::new(FailingParser(|| ParserError::RpcError(anyhow!("timeout"))))
+        });
+
+        let options = TransactionProcessingOptions {
+            registry: Some(Arc::new(registry)),
+            ..Default::default()
+        };
+        let metrics = ParserMetricsMap::default();
+        let process = |program_id: &str| {
+            let tx: EncodedTransactionWithStatusMeta =
+                serde_json::from_value(test_tx("sig", program_id)).unwrap();
+            process_transaction(
+                &tx,
+                &BlockInfo {
+                    slot: 100,
+                    block_time: 1_700_000_000,
+                    blockhash: "blockhash".to_string(),
+                    ..Default::default()
+                },
+                Some(&metrics),
+                &options,
+            )
+        };
+
+        let processed = process("NotMine111111111111111111111111111111111111").unwrap();
+        assert!(!processed.parsed_programs[0].parsed);
+        assert!(!processed.parsed_programs[0].error);
+
+        let processed = process("BadData111111111111111111111111111111111111").unwrap();
+        assert!(processed.parsed_programs[0].error);
+
+        assert!(process("RpcError11111111111111111111111111111111111").is_err());
+
+        let metrics = metrics.lock().unwrap();
+        assert_eq!(
+            metrics["NotMine111111111111111111111111111111111111"].error_count,
+            0
+        );
+        assert_eq!(
+            metrics["BadData111111111111111111111111111111111111"].error_count,
+            1
+        );
+    }
 }
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Shared parser metrics by program id
+pub type ParserMetricsMap = Arc<Mutex<HashMap<String, ParserMetrics>>>;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ParserMetrics {
+    pub program_id: String,
+    pub invocation_count: u64,
+    pub success_count: u64,
+    pub error_count: u64,
+    pub total_duration_us: u64,
+    pub max_duration_us: u64,
+}
+
+impl ParserMetrics {
+    pub fn new(program_id: &str) -> Self {
+        ParserMetrics {
+            program_id: program_id.to_string(),
+            invocation_count: 0,
+            success_count: 0,
+            error_count: 0,
+            total_duration_us: 0,
+            max_duration_us: 0,
+        }
+    }
+
+    pub fn avg_duration_us(&self) -> u64 {
+        if self.invocation_count == 0 {
+            return 0;
+        }
+        self.total_duration_us / self.invocation_count
+    }
+
+    pub fn success_rate(&self) -> f64 {
+        if self.invocation_count == 0 {
+            return 0.0;
+        }
+        self.success_count as f64 / self.invocation_count as f64
+    }
+}
+
+pub fn record_parser_metrics(
+    metrics: &ParserMetricsMap,
+    program_id: &str,
+    duration: Duration,
+    success: bool,
+) {
+    let duration_us = duration.as_micros() as u64;
+    let mut metrics = metrics.lock().unwrap();
+    let entry = metrics
+        .entry(program_id.to_string())
+        .or_insert_with(|| ParserMetrics::new(program_id));
+    entry.invocation_count += 1;
+    if success {
+        entry.success_count += 1;
+    } else {
+        entry.error_count += 1;
+    }
+    entry.total_duration_us += duration_us;
+    entry.max_duration_us = entry.max_duration_us.max(duration_us);
+}
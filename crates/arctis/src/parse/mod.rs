@@ -1,2 +1,3 @@
 pub mod block;
+pub mod metrics;
 pub mod transaction;
@@ -1,17 +1,33 @@
 use anyhow::Result;
-use arctis_types::{BlockInfo, EncodedTransactionWithStatusMeta, ParserResultData};
+use arctis_types::{BlockInfo, EncodedTransactionWithStatusMeta, ParserResult, ParserResultData};
 use sol_db::solana_db::{ProcessedTransaction, ProgramParserData};
+use sol_lib::transaction::parsers::{ParserError, ParserRegistry};
 use sol_lib::transaction::wrapper::TransactionWrapper;
 use sol_lib::transaction::InstructionWrapper;
 use sol_lib::{self as sol};
 use std::collections::HashMap;
 use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::metrics::{record_parser_metrics, ParserMetricsMap};
+
+const JITO_TIP_ACCOUNT: &str = "T1pyyaTNZsKv2WcRAB8oVnk93mLJw2XzjtVYqCsaHqt";
+
+// programs without any data we persist: tx that only invoke these don't need to be parsed
+const NOOP_PROGRAM_IDS: [&str; 4] = [
+    "Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo",
+    "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr",
+    "GDDMwNyyx8uB6zrqwBFHjLLG3TBYk2F8Az4yrQC5RzMp",
+    "ComputeBudget111111111111111111111111111111",
+];
 
 #[derive(Debug)]
 pub enum DiscardReason {
     Vote,
     Processed,
     Error,
+    Filtered,
     Unknown,
 }
 impl Display for DiscardReason {
@@ -20,18 +36,94 @@ impl Display for DiscardReason {
             DiscardReason::Vote => write!(f, "Vote"),
             DiscardReason::Processed => write!(f, "Processed"),
             DiscardReason::Error => write!(f, "Error"),
+            DiscardReason::Filtered => write!(f, "Filtered"),
             DiscardReason::Unknown => write!(f, "Unknown"),
         }
     }
 }
 
+/// transactions that don't pass the filter are stored as discarded (`Filtered`) without running any parser
+#[derive(Debug, Clone, Default)]
+pub struct BlockFilter {
+    /// only keep transactions that invoke at least one of these programs (top level or cpi)
+    pub require_programs: Option<Vec<String>>,
+    /// skip transactions signed by any of these wallets (e.g. known bots)
+    pub exclude_signers: Option<Vec<String>>,
+    /// skip transactions with fewer top level instructions
+    pub min_instruction_count: Option<u8>,
+}
+
+impl BlockFilter {
+    pub fn matches(&self, tx: &TransactionWrapper) -> bool {
+        if let Some(min_instruction_count) = self.min_instruction_count {
+            if tx.get_instructions().len() < min_instruction_count as usize {
+                return false;
+            }
+        }
+        if let Some(exclude_signers) = &self.exclude_signers {
+            if tx
+                .get_signers()
+                .iter()
+                .any(|signer| exclude_signers.contains(signer))
+            {
+                return false;
+            }
+        }
+        if let Some(require_programs) = &self.require_programs {
+            let program_ids = tx.get_all_program_ids_with_inner();
+            if !require_programs
+                .iter()
+                .any(|program_id| program_ids.contains(program_id))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TransactionProcessingOptions {
+    /// also parse instructions of failed transactions (e.g. to study failed bot / arbitrage tx)
+    /// failed transactions are still marked as discarded
+    pub parse_failed: bool,
+    /// only parse transactions that pass the filter (programs, signers, instruction count)
+    /// other transactions are marked as discarded without running any parser
+    pub block_filter: Option<BlockFilter>,
+    /// write parse and insert durations of every transaction to the `parse_stats` table
+    /// for profiling only: adds one insert per block
+    pub with_parse_timing: bool,
+    /// parsers to use instead of the global registry, e.g. to run custom parsers in isolation
+    pub registry: Option<Arc<ParserRegistry>>,
+    /// don't run the parsers for transactions that only invoke memo and compute budget programs
+    /// their priority fees and `tx_programs` rows are not written
+    pub skip_noop_transactions: bool,
+}
+
+impl TransactionProcessingOptions {
+    pub fn for_programs(programs: &[&str]) -> Self {
+        TransactionProcessingOptions {
+            block_filter: Some(BlockFilter {
+                require_programs: Some(programs.iter().map(|p| p.to_string()).collect()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+}
+
 pub fn process_transaction(
     tx: &EncodedTransactionWithStatusMeta,
-    slot: u64,
-    block_time: i64,
+    block: &BlockInfo,
+    metrics: Option<&ParserMetricsMap>,
+    options: &TransactionProcessingOptions,
 ) -> Result<ProcessedTransaction> {
+    let slot = block.slot;
+    let block_time = block.block_time;
     let tx = TransactionWrapper::new(tx.clone());
     let signature = tx.get_signature().clone();
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("arctis.process_transaction", signature = %signature).entered();
     let signer = tx.get_signer();
     let has_error = tx.is_error();
     let compute_units_consumed = tx.get_compute_units_consumed();
@@ -46,8 +138,7 @@ pub fn process_transaction(
 
     let mut discard_reason = None;
 
-    if tx.is_error() {
-        // TODO we might not want to discard failed tx
+    if has_error && !options.parse_failed {
         let processed_tx = ProcessedTransaction {
             slot,
             block_time,
@@ -64,10 +155,39 @@ pub fn process_transaction(
             is_discarded: true,
             discard_reason: Some(DiscardReason::Error.to_string()),
             data: None,
+            jito_tip_lamports: None,
+            parse_duration_us: 0,
         };
         return Ok(processed_tx);
     }
 
+    // fast path: skip transactions that don't pass the filter, e.g. don't touch any of the programs we are interested in
+    if let Some(block_filter) = &options.block_filter {
+        if !block_filter.matches(&tx) {
+            return Ok(discarded_transaction(
+                &tx,
+                slot,
+                block_time,
+                DiscardReason::Filtered,
+            ));
+        }
+    }
+
+    // fast path: skip the parsers if the tx only contains known noop programs
+    let program_ids = tx.get_all_program_ids();
+    if options.skip_noop_transactions
+        && !program_ids.is_empty()
+        && program_ids
+            .iter()
+            .all(|program_id| NOOP_PROGRAM_IDS.contains(&program_id.as_str()))
+    {
+        let discard_reason = match has_error {
+            true => DiscardReason::Error,
+            false => DiscardReason::Processed,
+        };
+        return Ok(discarded_transaction(&tx, slot, block_time, discard_reason));
+    }
+
     // a flag if we have extracted all necessary information and can discard the tx
     // discarded transactions will be removed from the block before persisting it
     // e.g. we want to remove all vote tx to reduce ~30% data
@@ -77,8 +197,6 @@ pub fn process_transaction(
     let ix_len = top_level_instructions.len();
     let inner_ix_count = tx.get_inner_ix_count();
 
-    let block_info = BlockInfo { slot, block_time };
-
     /* can happen: see https://solscan.io/tx/X571pNgdt4ny636Gtefyhibg2ezqZ7WQHpoUachTrrRYE12hC4f1UT1hMBbbR9QXJYHB35qYjv4LHatHsdQ6gQa
     if ix_len == 0 {
       return Err(anyhow!("No instructions found for tx: {}", signature));
@@ -89,6 +207,7 @@ pub fn process_transaction(
     let mut program_indexes = HashMap::new();
 
     let accounts = tx.get_accounts();
+    let mut parse_duration = Duration::ZERO;
 
     for (ix_idx, ix) in top_level_instructions.iter().enumerate() {
         let program_id = accounts[ix.program_id_index as usize].clone();
@@ -108,7 +227,10 @@ pub fn process_transaction(
             .or_insert(0);
 
         // get parser for program based on id
-        let parser = sol::transaction::parsers::get_parser(&program_id);
+        let parser = match &options.registry {
+            Some(registry) => registry.get(&program_id),
+            None => sol::transaction::parsers::get_parser(&program_id),
+        };
         if parser.is_none() {
             parsed_programs.push(ProgramParserData {
                 signature: signature.clone(),
@@ -125,21 +247,47 @@ pub fn process_transaction(
         // parse program instruction
         let parser = parser.unwrap();
         let ix_wrapped = InstructionWrapper::new(ix, ix_idx as usize, *program_ix_index);
-        let result = parser.parse(&ix_wrapped, &tx, &block_info);
-        if result.is_err() {
-            // TODO log errors println!("Failed to parse: program {}  sig {} ix: {} err {:?}", program_id, signature, ix_idx, result.err().unwrap());
-            parsed_programs.push(ProgramParserData {
-                signature: signature.clone(),
-                ix_idx,
-                program_id: program_id.clone(),
-                ix_type: "unknown".to_string(),
-                parsed: false,
-                error: true,
-            });
-            can_discard = false;
-            continue;
+        let ts_start_parse = Instant::now();
+        #[cfg(feature = "tracing")]
+        let parse_span =
+            tracing::info_span!("arctis.parse_instruction", program_id = %program_id).entered();
+        let result = parser.parse(&ix_wrapped, &tx, block);
+        #[cfg(feature = "tracing")]
+        drop(parse_span);
+        let elapsed_parse = ts_start_parse.elapsed();
+        parse_duration += elapsed_parse;
+        if let Some(metrics) = metrics {
+            record_parser_metrics(
+                metrics,
+                &program_id,
+                elapsed_parse,
+                matches!(result, Ok(_) | Err(ParserError::NotMyInstruction)),
+            );
         }
-        let result = result.unwrap();
+        let result = match result {
+            Ok(result) => result,
+            // handled like an instruction the parser does not know
+            Err(ParserError::NotMyInstruction) => ParserResult {
+                parsed: false,
+                ix_type: "unknown".to_string(),
+                data: ParserResultData::NoData,
+            },
+            // the block has to be retried
+            Err(ParserError::RpcError(err)) => return Err(err),
+            Err(_err) => {
+                // TODO log errors println!("Failed to parse: program {}  sig {} ix: {} err {}", program_id, signature, ix_idx, _err);
+                parsed_programs.push(ProgramParserData {
+                    signature: signature.clone(),
+                    ix_idx,
+                    program_id: program_id.clone(),
+                    ix_type: "unknown".to_string(),
+                    parsed: false,
+                    error: true,
+                });
+                can_discard = false;
+                continue;
+            }
+        };
         let parsed = result.parsed;
         parsed_programs.push(ProgramParserData {
             signature: signature.clone(),
@@ -166,6 +314,23 @@ pub fn process_transaction(
         can_discard &= _can_discard;
     }
 
+    // tips paid to jito are regular sol transfers
+    let jito_tips: Vec<u64> = parsed_ix
+        .iter()
+        .flat_map(|result| result.data.flatten())
+        .filter_map(|data| match data {
+            ParserResultData::SolTransfer(transfer) if transfer.to == JITO_TIP_ACCOUNT => {
+                Some(transfer.lamports)
+            }
+            _ => None,
+        })
+        .collect();
+    let jito_tip_lamports = if jito_tips.is_empty() {
+        None
+    } else {
+        Some(jito_tips.iter().sum())
+    };
+
     if discard_reason.is_none() {
         if can_discard {
             discard_reason = Some(DiscardReason::Processed);
@@ -190,6 +355,8 @@ pub fn process_transaction(
         is_discarded: true,
         discard_reason: Some(discard_reason.unwrap().to_string()),
         data: None,
+        jito_tip_lamports,
+        parse_duration_us: parse_duration.as_micros() as u64,
     };
 
     if !can_discard {
@@ -198,5 +365,39 @@ pub fn process_transaction(
         // TODO make setting
         processed_tx.data = None; // Some(tx.tx); // don't write all the data during testing
     }
+
+    // parsed failed tx: keep the parsed data but still mark them as errors
+    if has_error {
+        processed_tx.is_discarded = true;
+        processed_tx.discard_reason = Some(DiscardReason::Error.to_string());
+    }
     Ok(processed_tx)
 }
+
+/// a transaction that is discarded before any parser runs
+fn discarded_transaction(
+    tx: &TransactionWrapper,
+    slot: u64,
+    block_time: i64,
+    discard_reason: DiscardReason,
+) -> ProcessedTransaction {
+    ProcessedTransaction {
+        slot,
+        block_time,
+        signature: tx.get_signature(),
+        signer: tx.get_signer(),
+        has_error: tx.is_error(),
+        top_level_ix_count: tx.get_instructions().len() as u8,
+        inner_ix_count: tx.get_inner_ix_count(),
+        compute_units_consumed: tx.get_compute_units_consumed(),
+        fee: tx.get_fee(),
+        version: tx.get_version(),
+        parsed_programs: vec![],
+        parsed_ix: vec![],
+        is_discarded: true,
+        discard_reason: Some(discard_reason.to_string()),
+        data: None,
+        jito_tip_lamports: None,
+        parse_duration_us: 0,
+    }
+}
@@ -1,50 +1,458 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
 use anyhow::{anyhow, Result};
+use arctis_types::{BlockInfo, ParserResult, UiConfirmedBlock};
+use futures::stream::{self, Stream, StreamExt};
+use serde::Serialize;
 use sol_db::solana_db::{ProcessedTransaction, SolanaDatabase};
-use sol_lib::blocks::get_block_with_retries;
-use sol_lib::client::get_client;
-use sol_lib::transaction::tx::get_transaction;
+use sol_lib::blocks::{get_block_with_fallback, monitor_blocks_with_progress, MonitorConfig};
+use sol_lib::client::{get_client_with_fallbacks, CommitmentConfig, FallbackRpcClient};
+use sol_lib::transaction::tx::{
+    decode_transaction, get_simulated_transaction, get_transaction, simulate_transaction,
+};
+use tokio::sync::{mpsc, watch, Semaphore};
 
+use crate::enrich::enrich_new_tokens;
+use crate::events::{PipelineEvent, PipelineEvents};
 use crate::parse::block::process_block;
+pub use crate::parse::block::BlockParseStats;
+use crate::parse::metrics::{ParserMetrics, ParserMetricsMap};
+use crate::parse::transaction::TransactionProcessingOptions;
 use crate::parse::{self};
 
+pub use sol_lib::blocks::SlotProgress;
+
 pub struct ExecutionContext {
     pub rpc_url: String,
+    /// tried in order if a request to `rpc_url` fails
+    pub rpc_fallbacks: Vec<String>,
+    /// used for all block and transaction requests
+    pub commitment: CommitmentConfig,
     pub ws_url: String,
+    pub geyser_endpoint: Option<String>,
+    pub geyser_token: Option<String>,
+    pub metrics: ParserMetricsMap,
+    pub processing_options: TransactionProcessingOptions,
+    /// fetch off-chain metadata (image, description, socials) of the new tokens of parsed blocks
+    /// opt-in: this performs one http request per token
+    pub with_metadata_enrichment: bool,
+    /// notified at each block lifecycle event of `parse_blocks` and `process_monitored_blocks`
+    pub events: PipelineEvents,
 }
 
-pub async fn parse_block(block_number: u64, ctx: &ExecutionContext) -> Result<SolanaDatabase> {
-    let rpc_client = get_client(&ctx.rpc_url);
-    let block = get_block_with_retries(&rpc_client, block_number, 200, None).await?;
+impl ExecutionContext {
+    pub fn get_metrics(&self) -> HashMap<String, ParserMetrics> {
+        self.metrics.lock().unwrap().clone()
+    }
+
+    pub fn reset_metrics(&self) {
+        self.metrics.lock().unwrap().clear();
+    }
+
+    pub fn get_rpc_client(&self) -> Arc<FallbackRpcClient> {
+        let fallbacks = self
+            .rpc_fallbacks
+            .iter()
+            .map(|url| url.as_str())
+            .collect::<Vec<_>>();
+        get_client_with_fallbacks(&self.rpc_url, &fallbacks)
+    }
+
+    pub fn get_monitor_config(&self) -> MonitorConfig {
+        MonitorConfig {
+            geyser_endpoint: self.geyser_endpoint.clone(),
+            geyser_token: self.geyser_token.clone(),
+            commitment: self.commitment,
+            ..Default::default()
+        }
+    }
+}
+
+pub struct BlockParseResult {
+    pub db: SolanaDatabase,
+    pub stats: BlockParseStats,
+}
+
+pub async fn parse_block(block_number: u64, ctx: &ExecutionContext) -> Result<BlockParseResult> {
+    let rpc_client = ctx.get_rpc_client();
+    let block =
+        get_block_with_fallback(&rpc_client, block_number, 200, None, ctx.commitment).await?;
     match block {
         Some((block, _)) => {
             let mut sol_db = SolanaDatabase::new()?;
-            let _ = process_block(&block, &mut sol_db);
-            Ok(sol_db)
-        }
-        None => {
-            Err(anyhow!("Block not found"))
+            let metrics = Some(&ctx.metrics);
+            let stats = process_block(&block, &mut sol_db, metrics, &ctx.processing_options)?;
+            if ctx.with_metadata_enrichment {
+                enrich_new_tokens(&mut sol_db).await?;
+            }
+            Ok(BlockParseResult { db: sol_db, stats })
         }
+        None => Err(anyhow!("Block not found")),
+    }
+}
+
+/// Parses all blocks in `start..=end` into one database.
+/// At most `download_concurrency` blocks are fetched in parallel and up to `parse_concurrency`
+/// downloaded blocks are processed in parallel, each worker writes to its own database.
+/// The worker databases are merged once all blocks are processed.
+/// High download concurrency against a public RPC will trigger rate limiting.
+/// `on_block` is called for every slot, including skipped ones, in the order they finish.
+/// Lifecycle events of every block are sent to `ctx.events`.
+pub async fn parse_blocks<F>(
+    start: u64,
+    end: u64,
+    download_concurrency: usize,
+    parse_concurrency: usize,
+    ctx: &ExecutionContext,
+    on_block: F,
+) -> Result<SolanaDatabase>
+where
+    F: Fn(u64),
+{
+    let rpc_client = ctx.get_rpc_client();
+    let rpc_semaphore = Arc::new(Semaphore::new(download_concurrency.max(1)));
+    let parse_concurrency = parse_concurrency.max(1);
+
+    let blocks = stream::iter(start..=end)
+        .map(|slot| {
+            let rpc_client = rpc_client.clone();
+            let rpc_semaphore = rpc_semaphore.clone();
+            async move {
+                let permit = rpc_semaphore.acquire_owned().await.unwrap();
+                let block =
+                    get_block_with_fallback(&rpc_client, slot, 200, None, ctx.commitment).await;
+                drop(permit);
+                (slot, block)
+            }
+        })
+        .buffered(download_concurrency.max(1) + parse_concurrency);
+
+    parse_downloaded_blocks(blocks, parse_concurrency, ctx, on_block).await
+}
+
+/// Result of `get_block_with_fallback` for a slot: None if the slot was skipped
+type DownloadedBlock = (u64, Result<Option<(UiConfirmedBlock, u8)>>);
+
+/// Parse stage of `parse_blocks`: processes up to `parse_concurrency` downloaded blocks in parallel
+async fn parse_downloaded_blocks<S, F>(
+    blocks: S,
+    parse_concurrency: usize,
+    ctx: &ExecutionContext,
+    on_block: F,
+) -> Result<SolanaDatabase>
+where
+    S: Stream<Item = DownloadedBlock>,
+    F: Fn(u64),
+{
+    let started_at = Instant::now();
+    // databases of idle workers: at most `parse_concurrency` are created
+    let databases: Arc<Mutex<Vec<SolanaDatabase>>> = Arc::new(Mutex::new(vec![]));
+
+    let mut parsed = blocks
+        .map(|(slot, block)| {
+            let databases = databases.clone();
+            let metrics = ctx.metrics.clone();
+            let options = ctx.processing_options.clone();
+            let events = ctx.events.clone();
+            async move {
+                let result =
+                    parse_downloaded_block(slot, block, databases, metrics, options, &events).await;
+                match &result {
+                    Ok(Some(stats)) => events.emit(PipelineEvent::BlockParsed {
+                        slot,
+                        tx_count: stats.tx_count,
+                        swap_count: stats.swap_count,
+                    }),
+                    Ok(None) => {}
+                    Err(err) => events.emit(PipelineEvent::BlockFailed {
+                        slot,
+                        error: err.to_string(),
+                    }),
+                }
+                result.map(|_| slot)
+            }
+        })
+        .buffer_unordered(parse_concurrency);
+
+    let mut total_blocks = 0;
+    while let Some(slot) = parsed.next().await {
+        on_block(slot?);
+        total_blocks += 1;
+    }
+    drop(parsed);
+
+    let mut databases = std::mem::take(&mut *databases.lock().unwrap());
+    let mut sol_db = match databases.pop() {
+        Some(sol_db) => sol_db,
+        None => SolanaDatabase::new()?,
+    };
+    for other in &databases {
+        sol_db.merge_from(other)?;
     }
+    if ctx.with_metadata_enrichment {
+        enrich_new_tokens(&mut sol_db).await?;
+    }
+    ctx.events.emit(PipelineEvent::PipelineComplete {
+        total_blocks,
+        elapsed_ms: started_at.elapsed().as_millis() as u64,
+    });
+    Ok(sol_db)
+}
+
+/// Processes one downloaded block into an idle worker database. None if the slot was skipped
+async fn parse_downloaded_block(
+    slot: u64,
+    block: Result<Option<(UiConfirmedBlock, u8)>>,
+    databases: Arc<Mutex<Vec<SolanaDatabase>>>,
+    metrics: ParserMetricsMap,
+    options: TransactionProcessingOptions,
+    events: &PipelineEvents,
+) -> Result<Option<BlockParseStats>> {
+    // None: slot was skipped by the leader
+    let Some((block, retry_count)) = block? else {
+        return Ok(None);
+    };
+    events.emit(PipelineEvent::BlockDownloaded { slot, retry_count });
+    let stats = tokio::task::spawn_blocking(move || -> Result<BlockParseStats> {
+        let sol_db = databases.lock().unwrap().pop();
+        let mut sol_db = match sol_db {
+            Some(sol_db) => sol_db,
+            None => SolanaDatabase::new()?,
+        };
+        let result = process_block(&block, &mut sol_db, Some(&metrics), &options);
+        databases.lock().unwrap().push(sol_db);
+        result
+    })
+    .await??;
+    Ok(Some(stats))
 }
 
 pub async fn parse_transaction(
     tx_id: &str,
     ctx: &ExecutionContext,
 ) -> Result<ProcessedTransaction> {
-    let rpc_client = get_client(&ctx.rpc_url);
-    let tx = get_transaction(&rpc_client, tx_id).await?;
-    let block_time = tx.block_time.unwrap();
-    let slot = tx.slot;
+    let rpc_client = ctx.get_rpc_client();
+    let tx = rpc_client
+        .with_fallback(
+            |client| async move { get_transaction(&client, tx_id, ctx.commitment).await },
+        )
+        .await?;
+    let block_info = BlockInfo {
+        slot: tx.slot,
+        block_time: tx.block_time.unwrap(),
+        // getTransaction doesn't return the hash or leader of the block
+        ..Default::default()
+    };
     let transaction = tx.transaction;
 
-    let result = parse::transaction::process_transaction(&transaction, slot, block_time)?;
+    let result = parse::transaction::process_transaction(
+        &transaction,
+        &block_info,
+        Some(&ctx.metrics),
+        &ctx.processing_options,
+    )?;
     Ok(result)
 }
 
-pub async fn monitor_blocks(ctx: &ExecutionContext) -> Result<()> {
-    println!("Monitoring blocks...");
-    let rpc_client = get_client(&ctx.rpc_url);
-    let slot = rpc_client.get_slot().await?;
-    println!("Current slot: {}", slot);
-    Err(anyhow!("Not implemented"))
+#[derive(Serialize, Debug)]
+pub struct SimulationResult {
+    pub success: bool,
+    pub error: Option<String>,
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+    /// parsed top level and inner instructions, as for a landed transaction
+    pub parsed_results: Vec<ParserResult>,
+}
+
+/// Simulates a serialized transaction or message (raw or base64) and parses the result.
+/// Signatures are not verified and the blockhash is replaced with the latest one
+pub async fn simulate_transaction_from_message(
+    message: &[u8],
+    ctx: &ExecutionContext,
+) -> Result<SimulationResult> {
+    let tx = decode_transaction(message)?;
+    let rpc_client = ctx.get_rpc_client();
+    let tx_ref = &tx;
+    let response = rpc_client
+        .with_fallback(|client| async move {
+            simulate_transaction(&client, tx_ref, ctx.commitment).await
+        })
+        .await?;
+    let simulation = response.value;
+    let simulated = get_simulated_transaction(&tx, &simulation)?;
+    let block_info = BlockInfo {
+        slot: response.context.slot,
+        block_time: sol_lib::utils::get_ts_now() as i64,
+        ..Default::default()
+    };
+    let processed = parse::transaction::process_transaction(
+        &simulated,
+        &block_info,
+        Some(&ctx.metrics),
+        &ctx.processing_options,
+    )?;
+
+    Ok(SimulationResult {
+        success: simulation.err.is_none(),
+        error: simulation.err.map(|err| err.to_string()),
+        units_consumed: simulation.units_consumed,
+        logs: simulation.logs.unwrap_or_default(),
+        parsed_results: processed.parsed_ix,
+    })
+}
+
+/// Blocks received by the monitor: (block, received at ms, slot). `None` once the subscription gives up
+pub type MonitoredBlocks = mpsc::Receiver<Option<(UiConfirmedBlock, i64, u64)>>;
+
+/// Starts a BlocksWS subscription in the background.
+/// The watch receiver holds the latest subscription progress and can be read at any time.
+pub fn monitor_blocks(
+    ctx: &ExecutionContext,
+) -> Result<(MonitoredBlocks, watch::Receiver<SlotProgress>)> {
+    let rpc_client = ctx.get_rpc_client();
+    monitor_blocks_with_progress(&rpc_client, &ctx.ws_url, &ctx.get_monitor_config())
+}
+
+/// Processes monitored blocks into one database until the subscription gives up.
+/// `on_block` is called after each processed block, lifecycle events are sent to `ctx.events`.
+pub async fn process_monitored_blocks<F>(
+    mut blocks: MonitoredBlocks,
+    ctx: &ExecutionContext,
+    on_block: F,
+) -> Result<SolanaDatabase>
+where
+    F: Fn(u64),
+{
+    let started_at = Instant::now();
+    let mut total_blocks = 0;
+    let mut sol_db = SolanaDatabase::new()?;
+    while let Some(Some((block, _received_at, slot))) = blocks.recv().await {
+        let stats = process_block(
+            &block,
+            &mut sol_db,
+            Some(&ctx.metrics),
+            &ctx.processing_options,
+        );
+        let stats = match stats {
+            Ok(stats) => stats,
+            Err(err) => {
+                ctx.events.emit(PipelineEvent::BlockFailed {
+                    slot,
+                    error: err.to_string(),
+                });
+                return Err(err);
+            }
+        };
+        ctx.events.emit(PipelineEvent::BlockParsed {
+            slot,
+            tx_count: stats.tx_count,
+            swap_count: stats.swap_count,
+        });
+        on_block(slot);
+        total_blocks += 1;
+    }
+    ctx.events.emit(PipelineEvent::PipelineComplete {
+        total_blocks,
+        elapsed_ms: started_at.elapsed().as_millis() as u64,
+    });
+    Ok(sol_db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sol_lib::utils::TestTransactionBuilder;
+
+    fn test_block() -> UiConfirmedBlock {
+        let tx = |signature: &str| {
+            TestTransactionBuilder::new(&[
+                "Signer1111111111111111111111111111111111111",
+                "ProgramA11111111111111111111111111111111111",
+            ])
+            .signatures(&[signature])
+            .instruction(1, &[0], &[])
+            .to_json()
+        };
+        serde_json::from_value(serde_json::json!({
+            "previousBlockhash": "11111111111111111111111111111111",
+            "blockhash": "11111111111111111111111111111111",
+            "parentSlot": 99,
+            "transactions": [tx("sig_a"), tx("sig_b")],
+            "blockTime": 1_700_000_000,
+            "blockHeight": null
+        }))
+        .unwrap()
+    }
+
+    fn test_ctx(events: Arc<Mutex<Vec<PipelineEvent>>>) -> ExecutionContext {
+        ExecutionContext {
+            rpc_url: "http://localhost:8899".to_string(),
+            rpc_fallbacks: vec![],
+            commitment: CommitmentConfig::confirmed(),
+            ws_url: "ws://localhost:8900".to_string(),
+            geyser_endpoint: None,
+            geyser_token: None,
+            metrics: Default::default(),
+            processing_options: Default::default(),
+            with_metadata_enrichment: false,
+            events: PipelineEvents::new(Arc::new(move |event| events.lock().unwrap().push(event))),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_blocks_events() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let ctx = test_ctx(events.clone());
+        let blocks: Vec<DownloadedBlock> =
+            vec![(100, Ok(Some((test_block(), 2)))), (101, Ok(None))];
+
+        let sol_db = parse_downloaded_blocks(stream::iter(blocks), 2, &ctx, |_slot| {})
+            .await
+            .unwrap();
+        assert_eq!(sol_db.count_rows("transactions").unwrap(), 2);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[0],
+            PipelineEvent::BlockDownloaded {
+                slot: 100,
+                retry_count: 2
+            }
+        );
+        assert_eq!(
+            events[1],
+            PipelineEvent::BlockParsed {
+                slot: 100,
+                tx_count: 2,
+                swap_count: 0
+            }
+        );
+        assert!(matches!(
+            events[2],
+            PipelineEvent::PipelineComplete {
+                total_blocks: 2,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_parse_blocks_failed_event() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let ctx = test_ctx(events.clone());
+        let blocks: Vec<DownloadedBlock> = vec![(102, Err(anyhow!("timeout")))];
+
+        let result = parse_downloaded_blocks(stream::iter(blocks), 2, &ctx, |_slot| {}).await;
+        assert!(result.is_err());
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![PipelineEvent::BlockFailed {
+                slot: 102,
+                error: "timeout".to_string()
+            }]
+        );
+    }
 }
@@ -1,7 +1,19 @@
-use anyhow::Result;
-use arctis::config::get_settings;
-use arctis::run::{parse_block, parse_transaction, ExecutionContext};
-use clap::{Parser, Subcommand};
+use anyhow::{anyhow, Context, Result};
+use arctis::config::{get_settings, parse_commitment, S3Config};
+use arctis::logger::init_logger_with_format;
+use arctis::parse::transaction::{BlockFilter, TransactionProcessingOptions};
+use arctis::run::{
+    monitor_blocks, parse_block, parse_blocks, parse_transaction, process_monitored_blocks,
+    BlockParseResult, ExecutionContext, SlotProgress,
+};
+use arctis_types::DexType;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use serde_json::Value;
+use sol_db::solana_db::{default_migrations, ExportFormat, SolanaDatabase, SwapFilter, TABLES};
+use sol_db::utils::print_json_objects_as_table;
+
+const WSOL: &str = "So11111111111111111111111111111111111111112";
 
 #[derive(Parser)]
 #[command(author, version, about = "AlphaArc Arctis CLI", long_about = None)]
@@ -9,6 +21,79 @@ struct Cli {
     /// A command to run
     #[command(subcommand)]
     command: Commands,
+
+    /// Commitment of RPC requests [default: config value or confirmed]
+    #[arg(long, global = true, value_enum)]
+    commitment: Option<Commitment>,
+
+    /// Fetch off-chain metadata (image, description, socials) of new tokens in parsed blocks.
+    /// Performs one http request per token
+    #[arg(long, global = true)]
+    with_metadata_enrichment: bool,
+
+    #[command(flatten)]
+    db_settings: DbSettings,
+
+    #[command(flatten)]
+    filter_settings: FilterSettings,
+}
+
+/// Transactions of parsed blocks that are stored as discarded without running any parser
+#[derive(Args, Clone, Default)]
+struct FilterSettings {
+    /// Only parse transactions that invoke at least one of these comma separated programs
+    #[arg(long, global = true, value_delimiter = ',', value_name = "PROGRAM_IDS")]
+    filter_programs: Vec<String>,
+
+    /// Skip transactions signed by any of these comma separated wallets
+    #[arg(long, global = true, value_delimiter = ',', value_name = "WALLETS")]
+    exclude_signers: Vec<String>,
+
+    /// Skip transactions with fewer top level instructions
+    #[arg(long, global = true, value_name = "N")]
+    min_instruction_count: Option<u8>,
+}
+
+impl FilterSettings {
+    fn get_processing_options(&self) -> TransactionProcessingOptions {
+        let non_empty = |values: &Vec<String>| Some(values.clone()).filter(|v| !v.is_empty());
+        let block_filter = BlockFilter {
+            require_programs: non_empty(&self.filter_programs),
+            exclude_signers: non_empty(&self.exclude_signers),
+            min_instruction_count: self.min_instruction_count,
+        };
+        let is_empty = block_filter.require_programs.is_none()
+            && block_filter.exclude_signers.is_none()
+            && block_filter.min_instruction_count.is_none();
+        TransactionProcessingOptions {
+            block_filter: (!is_empty).then_some(block_filter),
+            ..Default::default()
+        }
+    }
+}
+
+/// DuckDB settings of the databases that are queried by a command
+#[derive(Args, Clone, Copy, Default)]
+struct DbSettings {
+    /// Memory limit of DuckDB in GB [default: 80% of RAM]
+    #[arg(long = "db-memory-limit", global = true, value_name = "GB")]
+    memory_limit_gb: Option<f64>,
+
+    /// Threads of DuckDB queries [default: number of cores]
+    #[arg(long = "db-threads", global = true, value_name = "N")]
+    threads: Option<u32>,
+}
+
+impl DbSettings {
+    fn apply(&self, sol_db: &mut SolanaDatabase) -> Result<()> {
+        if let Some(limit_gb) = self.memory_limit_gb {
+            sol_db.set_memory_limit(limit_gb)?;
+        }
+        if let Some(threads) = self.threads {
+            sol_db.set_threads(threads)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Subcommand)]
@@ -19,18 +104,39 @@ enum Commands {
         #[command(subcommand)]
         subcommand: Parse,
     },
+    /// Analyze the trading activity of a wallet
+    Wallet {
+        #[command(subcommand)]
+        subcommand: Wallet,
+    },
+    /// Export tables of an existing database
+    Export {
+        #[command(subcommand)]
+        subcommand: Export,
+    },
+    /// Analyze the parser coverage of an existing database
+    Analyze {
+        #[command(subcommand)]
+        subcommand: Analyze,
+    },
+    /// Maintain an existing database
+    Db {
+        #[command(subcommand)]
+        subcommand: Db,
+    },
+    /// Parse a block and print parser timing and success-rate metrics
+    Metrics {
+        /// Block number to parse
+        block_number: u64,
+    },
+    /// Process new blocks as they are produced and show a live dashboard
+    Monitor,
     /*
 
     /// Fetch information about a token
     Token {
       /// Token address
       address: String,
-    },
-    /// Monitor blocks or programs
-    Monitor {
-      /// Monitor blocks
-      #[arg(default_value = "BlocksWS")]
-      strategy: String,
     },
      */
 }
@@ -43,20 +149,39 @@ enum Parse {
         #[arg(long, value_name = "DATASET", default_value = "swaps")]
         dataset: String,
 
-        /// Filter to apply
+        /// Only print swaps of this dex
         #[arg(long, value_name = "FILTER", default_value = "pumpfun")]
-        filter: String,
+        filter: DexType,
 
         /// Block number to parse
         block_number: u64,
     },
-    /*
     /// Parse a range of blocks
     Blocks {
-      /// Range of blocks to parse, in the format start:end
-      block_range: String,
+        /// Range of blocks to parse: start:end (inclusive) or start:+count
+        block_range: String,
+
+        /// Number of blocks fetched in parallel.
+        /// High values against a public RPC will be rate limited
+        #[arg(long, alias = "concurrency", default_value_t = 4)]
+        download_concurrency: usize,
+
+        /// Number of blocks parsed in parallel
+        #[arg(long, default_value_t = 4)]
+        parse_concurrency: usize,
+
+        /// Output file (json) or directory (csv, parquet). Defaults to stdout
+        #[arg(long, value_name = "PATH")]
+        output: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+        format: OutputFormat,
+
+        /// Also write a DuckDB snapshot (schema and data) of the parsed blocks to this directory
+        #[arg(long, value_name = "PATH")]
+        backup: Option<String>,
     },
-     */
     /// Parse a specific transaction
     Tx {
         /// Transaction ID to parse
@@ -64,16 +189,269 @@ enum Parse {
     },
 }
 
-/*
+#[derive(Subcommand)]
+enum Wallet {
+    /// Realized PnL of a wallet
+    Pnl {
+        /// Wallet address
+        address: String,
+
+        #[command(flatten)]
+        source: DataSource,
+    },
+    /// Trade count, volume and unique tokens of a wallet
+    Activity {
+        /// Wallet address
+        address: String,
+
+        /// Only include swaps of the last N days
+        #[arg(long)]
+        days: Option<u64>,
+
+        #[command(flatten)]
+        source: DataSource,
+    },
+    /// Tokens traded by a wallet, sorted by volume
+    Tokens {
+        /// Wallet address
+        address: String,
+
+        #[command(flatten)]
+        source: DataSource,
+    },
+}
+
+#[derive(Subcommand)]
+enum Export {
+    /// Export a table to a local file
+    Table {
+        /// Database file to export from
+        #[arg(long, value_name = "PATH")]
+        db: String,
+
+        /// Table to export
+        #[arg(long)]
+        table: String,
+
+        /// Output format
+        #[arg(long, value_enum)]
+        format: ExportFileFormat,
+
+        /// Output file
+        #[arg(long, value_name = "OUTPUT_PATH")]
+        output: String,
+
+        /// Only export rows matching the SQL condition, e.g. "dex = 'Pumpfun'"
+        #[arg(long = "where", value_name = "CLAUSE")]
+        where_clause: Option<String>,
+
+        /// Apply pending schema migrations to the database before exporting
+        #[arg(long)]
+        migrate: bool,
+    },
+    /// Upload tables to S3 using the credentials of the [s3] config section
+    S3 {
+        /// Database file to export from
+        #[arg(long, value_name = "PATH")]
+        db: String,
+
+        /// Destination bucket, must exist
+        #[arg(long)]
+        bucket: String,
+
+        /// Key prefix of the uploaded files: <prefix>/<table>.<extension>
+        #[arg(long, default_value = "")]
+        prefix: String,
+
+        /// Output format (csv, parquet, parquet_zstd)
+        #[arg(long, value_enum, default_value_t = ExportFileFormat::ParquetZstd)]
+        format: ExportFileFormat,
+
+        /// Comma separated tables to upload. Defaults to all non-empty tables
+        #[arg(long, value_delimiter = ',')]
+        tables: Vec<String>,
+
+        /// Apply pending schema migrations to the database before exporting
+        #[arg(long)]
+        migrate: bool,
+    },
+    /// Copy all tables into a single DuckDB file, e.g. for duckdb.connect() in Python
+    Duckdb {
+        /// Database file to export from
+        #[arg(long, value_name = "PATH")]
+        db: String,
+
+        /// Output DuckDB file, must not exist yet
+        #[arg(long, value_name = "OUTPUT_PATH")]
+        output: String,
+
+        /// Apply pending schema migrations to the database before exporting
+        #[arg(long)]
+        migrate: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum Analyze {
+    /// Programs that kept transactions from being discarded, most frequent first
+    CantDiscard {
+        /// Database file to analyze
+        #[arg(long, value_name = "PATH")]
+        db: String,
+    },
+    /// Number of transactions per discard reason, many Unknown transactions indicate missing parsers
+    DiscardReasons {
+        /// Database file to analyze
+        #[arg(long, value_name = "PATH")]
+        db: String,
+    },
+    /// Most invoked programs and the frequent programs without a parser
+    Programs {
+        /// Database file to analyze
+        #[arg(long, value_name = "PATH")]
+        db: String,
+
+        /// Number of programs to list
+        #[arg(long, value_name = "N", default_value_t = 20)]
+        top_programs: usize,
+
+        /// Only list programs without a parser with more calls
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        min_calls: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum Db {
+    /// Delete the data of old slots
+    Prune {
+        /// Database file to prune
+        #[arg(long, value_name = "PATH")]
+        db: String,
+
+        /// Delete all rows of slots before SLOT
+        #[arg(
+            long,
+            value_name = "SLOT",
+            required_unless_present = "prune_keep_last",
+            conflicts_with = "prune_keep_last"
+        )]
+        prune_before_slot: Option<u64>,
+
+        /// Only keep the last N slots of the blocks table
+        #[arg(long, value_name = "N")]
+        prune_keep_last: Option<u64>,
+    },
+    /// Report missing slots and the slot coverage of the blocks table
+    Verify {
+        /// Database file to verify
+        #[arg(long, value_name = "PATH")]
+        db: String,
+
+        /// First expected slot, defaults to the first slot in the database
+        #[arg(long, value_name = "SLOT")]
+        start: Option<u64>,
+
+        /// Last expected slot, defaults to the last slot in the database
+        #[arg(long, value_name = "SLOT")]
+        end: Option<u64>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFileFormat {
+    Csv,
+    Json,
+    Parquet,
+    #[value(name = "parquet_zstd")]
+    ParquetZstd,
+}
+
+/// where wallet data is loaded from: an existing database or live blocks
+#[derive(Args)]
+struct DataSource {
+    /// Existing database file to query
+    #[arg(long, value_name = "PATH")]
+    db_path: Option<String>,
+
+    /// Apply pending schema migrations to --db-path before querying
+    #[arg(long)]
+    migrate: bool,
+
+    /// Directory of a database snapshot created with --backup, instead of --db-path
+    #[arg(long, value_name = "PATH", conflicts_with = "db_path")]
+    restore: Option<String>,
+
+    /// First slot to fetch if no database is given
+    #[arg(long)]
+    slot_start: Option<u64>,
+
+    /// Last slot to fetch if no database is given
+    #[arg(long)]
+    slot_end: Option<u64>,
+
+    /// Number of blocks fetched in parallel if no database is given
+    #[arg(long, default_value_t = 4)]
+    download_concurrency: usize,
+
+    /// Number of blocks parsed in parallel
+    #[arg(long, default_value_t = 4)]
+    parse_concurrency: usize,
+
+    /// Token that volumes and PnL are denominated in
+    #[arg(long, default_value = WSOL)]
+    quote_token: String,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Commitment::Processed => "processed",
+            Commitment::Confirmed => "confirmed",
+            Commitment::Finalized => "finalized",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+    Parquet,
+}
+
+/// parses start:end (inclusive) or start:+count into (start, end)
 fn parse_block_range(range: &str) -> Result<(u64, u64)> {
     let (start, end) = range
         .split_once(':')
-        .ok_or_else(|| anyhow!("Invalid block range format. Expected start:end"))?;
-    let start = start.parse().context("Failed to parse start of range")?;
-    let end = end.parse().context("Failed to parse end of range")?;
+        .ok_or_else(|| anyhow!("Invalid block range format. Expected start:end or start:+count"))?;
+    let start: u64 = start.parse().context("Failed to parse start of range")?;
+    let end = match end.strip_prefix('+') {
+        Some(count) => {
+            let count: u64 = count.parse().context("Failed to parse block count")?;
+            if count == 0 {
+                return Err(anyhow!("Block count must be greater than 0"));
+            }
+            count
+                .checked_sub(1)
+                .and_then(|offset| start.checked_add(offset))
+                .ok_or_else(|| anyhow!("Block range exceeds the maximum slot"))?
+        }
+        None => end.parse().context("Failed to parse end of range")?,
+    };
+    if end < start {
+        return Err(anyhow!("End of range must not be before start"));
+    }
     Ok((start, end))
 }
-*/
 
 fn print_banner() {
     println!("\n");
@@ -83,20 +461,458 @@ fn print_banner() {
     println!("\n\n");
 }
 
-async fn handle_parse_block(block_number: u64, ctx: &ExecutionContext) -> Result<()> {
+async fn handle_parse_block(
+    block_number: u64,
+    filter: DexType,
+    ctx: &ExecutionContext,
+) -> Result<()> {
     println!("Parse block: {}", block_number);
-    let sol_db = parse_block(block_number, ctx).await?;
-    sol_db.print_table("swaps")?;
+    let BlockParseResult { db: sol_db, stats } = parse_block(block_number, ctx).await?;
+    println!("{}", serde_json::to_string_pretty(&stats.to_json())?);
+    let swaps = sol_db.get_swaps_filtered(&SwapFilter::builder().with_dex(filter).build())?;
+    print_as_table(&swaps)?;
     Ok(())
 }
 
-/*
-async fn handle_parse_blocks(block_range: &str, _ctx: &ExecutionContext) -> Result<()> {
-  let (start, end) = parse_block_range(block_range)?;
-  println!("Parse blocks: {} to {}", start, end);
-  Ok(())
+fn get_non_empty_tables(sol_db: &SolanaDatabase) -> Result<Vec<&'static str>> {
+    let mut tables = vec![];
+    for table in TABLES {
+        if sol_db.count_rows(table)? > 0 {
+            tables.push(table);
+        }
+    }
+    Ok(tables)
+}
+
+fn write_output(sol_db: &SolanaDatabase, output: Option<&str>, format: OutputFormat) -> Result<()> {
+    let tables = get_non_empty_tables(sol_db)?;
+    match format {
+        OutputFormat::Table => {
+            for table in tables {
+                println!("{}:", table);
+                sol_db.print_table(table)?;
+            }
+        }
+        OutputFormat::Json => {
+            let mut result = serde_json::Map::new();
+            for table in tables {
+                let rows = sol_db.query_to_json_file(&format!("SELECT * FROM {}", table))?;
+                result.insert(table.to_string(), Value::Array(rows));
+            }
+            let json = serde_json::to_string_pretty(&Value::Object(result))?;
+            match output {
+                Some(path) => std::fs::write(path, json)?,
+                None => println!("{}", json),
+            }
+        }
+        OutputFormat::Csv | OutputFormat::Parquet => {
+            let dir = output.ok_or(anyhow!(
+                "--output directory is required for csv and parquet"
+            ))?;
+            let export_format = match format {
+                OutputFormat::Csv => ExportFormat::CSV,
+                _ => ExportFormat::PARQUET_ZSTD,
+            };
+            let exported = sol_db.export_all_tables(dir, export_format.clone())?;
+            for table in tables {
+                let path = format!("{}/{}.{}", dir, table, export_format.extension());
+                println!("Exported {} rows of {} to {}", exported[table], table, path);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn handle_parse_blocks(
+    block_range: &str,
+    download_concurrency: usize,
+    parse_concurrency: usize,
+    output: Option<&str>,
+    format: OutputFormat,
+    backup: Option<&str>,
+    ctx: &ExecutionContext,
+) -> Result<()> {
+    let (start, end) = parse_block_range(block_range)?;
+    println!("Parse blocks: {} to {}", start, end);
+
+    let progress = ProgressBar::new(end - start + 1);
+    progress.set_style(ProgressStyle::with_template(
+        "{bar:40} {pos}/{len} blocks ({eta})",
+    )?);
+    let sol_db = parse_blocks(
+        start,
+        end,
+        download_concurrency,
+        parse_concurrency,
+        ctx,
+        |_slot| progress.inc(1),
+    )
+    .await?;
+    progress.finish();
+
+    if let Some(backup) = backup {
+        sol_db
+            .backup(backup)
+            .with_context(|| format!("Failed to back up database to {}", backup))?;
+        println!("Database backup written to {}", backup);
+    }
+    write_output(&sol_db, output, format)
+}
+
+/// opens a database created before, databases of older versions need `migrate`
+fn open_database(db_path: &str, migrate: bool, db_settings: &DbSettings) -> Result<SolanaDatabase> {
+    let mut sol_db = SolanaDatabase::open_existing(db_path)?;
+    db_settings.apply(&mut sol_db)?;
+    if migrate {
+        let executed = sol_db.migrate_schema(&default_migrations())?;
+        for name in &executed {
+            println!("Applied migration {}", name);
+        }
+    }
+    Ok(sol_db)
+}
+
+async fn load_database(
+    source: &DataSource,
+    db_settings: &DbSettings,
+    ctx: &ExecutionContext,
+) -> Result<SolanaDatabase> {
+    if let Some(db_path) = &source.db_path {
+        return open_database(db_path, source.migrate, db_settings);
+    }
+    if let Some(restore) = &source.restore {
+        let mut sol_db = SolanaDatabase::restore(restore)
+            .with_context(|| format!("Failed to restore database from {}", restore))?;
+        db_settings.apply(&mut sol_db)?;
+        return Ok(sol_db);
+    }
+    let (Some(start), Some(end)) = (source.slot_start, source.slot_end) else {
+        return Err(anyhow!(
+            "Either --db-path, --restore or --slot-start and --slot-end are required"
+        ));
+    };
+    let progress = ProgressBar::new(end.saturating_sub(start) + 1);
+    let mut sol_db = parse_blocks(
+        start,
+        end,
+        source.download_concurrency,
+        source.parse_concurrency,
+        ctx,
+        |_slot| progress.inc(1),
+    )
+    .await?;
+    progress.finish();
+    db_settings.apply(&mut sol_db)?;
+    Ok(sol_db)
+}
+
+fn print_as_table<T: serde::Serialize>(rows: &[T]) -> Result<()> {
+    let rows = rows
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<Value>, _>>()?;
+    print_json_objects_as_table(&rows);
+    Ok(())
+}
+
+async fn handle_wallet(
+    subcommand: Wallet,
+    db_settings: &DbSettings,
+    ctx: &ExecutionContext,
+) -> Result<()> {
+    match subcommand {
+        Wallet::Pnl { address, source } => {
+            println!("Wallet PnL: {}", address);
+            let sol_db = load_database(&source, db_settings, ctx).await?;
+            let pnl = sol_db.get_wallet_pnl(&address, &source.quote_token)?;
+            print_as_table(&[pnl])?;
+        }
+        Wallet::Activity {
+            address,
+            days,
+            source,
+        } => {
+            println!("Wallet activity: {}", address);
+            let sol_db = load_database(&source, db_settings, ctx).await?;
+            let since_block_time = days.map(|days| {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+                now.saturating_sub(days * 24 * 60 * 60) as i64
+            });
+            let activity =
+                sol_db.get_wallet_activity(&address, &source.quote_token, since_block_time)?;
+            print_as_table(&[activity])?;
+        }
+        Wallet::Tokens { address, source } => {
+            println!("Wallet tokens: {}", address);
+            let sol_db = load_database(&source, db_settings, ctx).await?;
+            let tokens = sol_db.get_wallet_tokens(&address, &source.quote_token)?;
+            print_as_table(&tokens)?;
+        }
+    }
+    Ok(())
+}
+
+fn handle_export(
+    db: &str,
+    table: &str,
+    format: ExportFileFormat,
+    output: &str,
+    where_clause: Option<&str>,
+    migrate: bool,
+    db_settings: &DbSettings,
+) -> Result<()> {
+    if !TABLES.contains(&table) {
+        return Err(anyhow!(
+            "Unknown table '{}'. Available tables: {}",
+            table,
+            TABLES.join(", ")
+        ));
+    }
+    let sol_db = open_database(db, migrate, db_settings)?;
+    let query = match where_clause {
+        Some(where_clause) => format!("SELECT * FROM {} WHERE {}", table, where_clause),
+        None => format!("SELECT * FROM {}", table),
+    };
+
+    match format {
+        ExportFileFormat::Json => {
+            // newline-delimited json
+            let rows = sol_db.query_to_json_file(&query)?;
+            let mut ndjson = String::new();
+            for row in &rows {
+                ndjson.push_str(&serde_json::to_string(row)?);
+                ndjson.push('\n');
+            }
+            std::fs::write(output, ndjson)?;
+        }
+        ExportFileFormat::Csv => sol_db.export_query(&query, output, ExportFormat::CSV)?,
+        ExportFileFormat::Parquet => sol_db.export_query(&query, output, ExportFormat::PARQUET)?,
+        ExportFileFormat::ParquetZstd => {
+            sol_db.export_query(&query, output, ExportFormat::PARQUET_ZSTD)?
+        }
+    }
+    println!("Exported {} to {}", table, output);
+    Ok(())
+}
+
+fn handle_export_duckdb(
+    db: &str,
+    output: &str,
+    migrate: bool,
+    db_settings: &DbSettings,
+) -> Result<()> {
+    if std::path::Path::new(output).exists() {
+        return Err(anyhow!("Output file '{}' already exists", output));
+    }
+    let sol_db = open_database(db, migrate, db_settings)?;
+    sol_db.export_to_duckdb_file(output)?;
+    println!("Exported {} to {}", db, output);
+    Ok(())
+}
+
+/// s3://<bucket>/<prefix>/<file>, the prefix is optional
+fn get_s3_path(bucket: &str, prefix: &str, file: &str) -> String {
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        format!("s3://{}/{}", bucket, file)
+    } else {
+        format!("s3://{}/{}/{}", bucket, prefix, file)
+    }
+}
+
+fn handle_export_s3(
+    db: &str,
+    bucket: &str,
+    prefix: &str,
+    format: ExportFileFormat,
+    tables: &[String],
+    migrate: bool,
+    s3_config: Option<&S3Config>,
+    db_settings: &DbSettings,
+) -> Result<()> {
+    let Some(s3_config) = s3_config else {
+        return Err(anyhow!(
+            "Missing S3 credentials: add an [s3] section to config.toml"
+        ));
+    };
+    let export_format = match format {
+        ExportFileFormat::Csv => ExportFormat::CSV,
+        ExportFileFormat::Parquet => ExportFormat::PARQUET,
+        ExportFileFormat::ParquetZstd => ExportFormat::PARQUET_ZSTD,
+        ExportFileFormat::Json => return Err(anyhow!("S3 exports don't support json")),
+    };
+    if let Some(table) = tables
+        .iter()
+        .find(|table| !TABLES.contains(&table.as_str()))
+    {
+        return Err(anyhow!(
+            "Unknown table '{}'. Available tables: {}",
+            table,
+            TABLES.join(", ")
+        ));
+    }
+
+    let mut sol_db = open_database(db, migrate, db_settings)?;
+    let tables = if tables.is_empty() {
+        let mut non_empty = vec![];
+        for table in sol_db.list_tables()? {
+            if TABLES.contains(&table.as_str()) && sol_db.count_rows(&table)? > 0 {
+                non_empty.push(table);
+            }
+        }
+        non_empty
+    } else {
+        tables.to_vec()
+    };
+
+    sol_db.enable_s3(s3_config)?;
+    sol_db
+        .check_s3_bucket(bucket)
+        .with_context(|| format!("Bucket '{}' is not accessible", bucket))?;
+
+    let progress = ProgressBar::new(tables.len() as u64);
+    progress.set_style(ProgressStyle::with_template(
+        "{bar:40} {pos}/{len} tables {msg}",
+    )?);
+    for table in &tables {
+        progress.set_message(table.clone());
+        let file = format!("{}.{}", table, export_format.extension());
+        let path = get_s3_path(bucket, prefix, &file);
+        sol_db.export_table(table, &path, export_format.clone())?;
+        let size = sol_db.get_file_size(&path)?;
+        progress.println(format!(
+            "Uploaded {} to {} ({})",
+            table,
+            path,
+            HumanBytes(size as u64)
+        ));
+        progress.inc(1);
+    }
+    progress.finish_with_message("done");
+    Ok(())
+}
+
+fn handle_analyze_cant_discard(db: &str, db_settings: &DbSettings) -> Result<()> {
+    let sol_db = open_database(db, false, db_settings)?;
+    let stats = sol_db.get_cant_discard_programs()?;
+    if stats.is_empty() {
+        println!("No transactions in cant_discard");
+        return Ok(());
+    }
+    print_as_table(&stats)?;
+
+    let unrecognized = sol_db.get_unrecognized_program_ids()?;
+    println!("Programs without a parser: {}", unrecognized.len());
+    for program_id in &unrecognized {
+        println!("{}", program_id);
+    }
+    Ok(())
+}
+
+fn handle_analyze_discard_reasons(db: &str, db_settings: &DbSettings) -> Result<()> {
+    let sol_db = open_database(db, false, db_settings)?;
+    let mut stats: Vec<_> = sol_db.get_discard_reason_stats()?.into_iter().collect();
+    stats.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    for (reason, count) in &stats {
+        println!("{:<12} {}", reason, count);
+    }
+    println!(
+        "Kept transactions: {:.2}%",
+        sol_db.get_non_discarded_ratio()? * 100.0
+    );
+    Ok(())
+}
+
+fn handle_analyze_programs(
+    db: &str,
+    top_programs: usize,
+    min_calls: u64,
+    db_settings: &DbSettings,
+) -> Result<()> {
+    let sol_db = open_database(db, false, db_settings)?;
+    let programs = sol_db.get_program_invocation_frequency()?;
+    if programs.is_empty() {
+        println!("No programs in tx_programs");
+        return Ok(());
+    }
+    let top: Vec<_> = programs.into_iter().take(top_programs).collect();
+    print_as_table(&top)?;
+
+    let unparsed: Vec<_> = sol_db
+        .get_unparsed_high_frequency_programs(min_calls)?
+        .into_iter()
+        .take(top_programs)
+        .collect();
+    println!("Programs without a parser: {}", unparsed.len());
+    if !unparsed.is_empty() {
+        print_as_table(&unparsed)?;
+    }
+    Ok(())
+}
+
+fn handle_db_prune(
+    db: &str,
+    prune_before_slot: Option<u64>,
+    prune_keep_last: Option<u64>,
+    db_settings: &DbSettings,
+) -> Result<()> {
+    let mut sol_db = open_database(db, false, db_settings)?;
+    let deleted = match (prune_before_slot, prune_keep_last) {
+        (Some(slot), _) => sol_db.delete_blocks_before_slot(slot)?,
+        (None, Some(n)) => sol_db.prune_to_last_n_slots(n)?,
+        (None, None) => return Err(anyhow!("--prune-before-slot or --prune-keep-last required")),
+    };
+    let mut deleted: Vec<_> = deleted
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    deleted.sort();
+    for (table, count) in &deleted {
+        println!("Deleted {} rows from {}", count, table);
+    }
+    println!(
+        "Deleted {} rows",
+        deleted.iter().map(|(_, count)| count).sum::<u64>()
+    );
+    Ok(())
+}
+
+fn handle_db_verify(
+    db: &str,
+    start: Option<u64>,
+    end: Option<u64>,
+    db_settings: &DbSettings,
+) -> Result<()> {
+    const MAX_PRINTED_SLOTS: usize = 20;
+    let sol_db = open_database(db, false, db_settings)?;
+    let Some((min_slot, max_slot)) = sol_db.get_slot_range()? else {
+        println!("No blocks in database");
+        return Ok(());
+    };
+    println!("Slot range: {} - {}", min_slot, max_slot);
+
+    let missing = sol_db.get_missing_slots()?;
+    println!("Missing slots: {}", missing.len());
+    for slot in missing.iter().take(MAX_PRINTED_SLOTS) {
+        println!("{}", slot);
+    }
+    if missing.len() > MAX_PRINTED_SLOTS {
+        println!("... and {} more", missing.len() - MAX_PRINTED_SLOTS);
+    }
+
+    let start = start.unwrap_or(min_slot);
+    let end = end.unwrap_or(max_slot);
+    println!(
+        "Coverage of {} - {}: {:.2}%",
+        start,
+        end,
+        sol_db.get_slot_coverage_pct(start, end)?
+    );
+    Ok(())
 }
-*/
 
 async fn handle_parse_transaction(tx_id: &str, ctx: &ExecutionContext) -> Result<()> {
     println!("Parse Transaction: {}", tx_id);
@@ -106,44 +922,510 @@ async fn handle_parse_transaction(tx_id: &str, ctx: &ExecutionContext) -> Result
     Ok(())
 }
 
+async fn handle_metrics(block_number: u64, ctx: &ExecutionContext) -> Result<()> {
+    println!("Parser metrics for block: {}", block_number);
+    ctx.reset_metrics();
+    parse_block(block_number, ctx).await?;
+
+    let mut metrics: Vec<_> = ctx.get_metrics().into_values().collect();
+    metrics.sort_by(|a, b| b.total_duration_us.cmp(&a.total_duration_us));
+    let rows = metrics
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<Value>, _>>()?;
+    print_json_objects_as_table(&rows);
+    Ok(())
+}
+
 /*
 async fn handle_token(address: &str) -> Result<()> {
   println!("Token: {}", address);
   Ok(())
 }
+*/
 
-async fn handle_monitor(strategy: &str, ctx: &ExecutionContext) -> Result<()> {
-  println!("Monitoring blocks with strategy: {}", strategy);
-  monitor_blocks(&ctx).await?;
-  Ok(())
+fn format_slot_progress(progress: &SlotProgress, processed_blocks: u64) -> String {
+    format!(
+        "received: {} | processed: {} ({} blocks) | lag: {} ms | backlog: {}",
+        progress.last_received_slot,
+        progress.last_processed_slot,
+        processed_blocks,
+        progress.receive_lag_ms,
+        progress.processing_backlog
+    )
+}
+
+async fn handle_monitor(ctx: &ExecutionContext) -> Result<()> {
+    println!("Monitoring blocks: {}", ctx.ws_url);
+    let (blocks, mut progress) = monitor_blocks(ctx)?;
+
+    let dashboard = ProgressBar::new_spinner();
+    dashboard.set_style(
+        ProgressStyle::with_template("{spinner} {elapsed_precise} {msg}")
+            .unwrap_or(ProgressStyle::default_spinner()),
+    );
+    dashboard.enable_steady_tick(std::time::Duration::from_millis(250));
+    dashboard.set_message("waiting for blocks");
+
+    // redraw whenever the subscription reports progress
+    let progress_bar = dashboard.clone();
+    let render = tokio::spawn(async move {
+        while progress.changed().await.is_ok() {
+            let current = progress.borrow_and_update().clone();
+            progress_bar.set_message(format_slot_progress(&current, progress_bar.position()));
+        }
+    });
+
+    let result = process_monitored_blocks(blocks, ctx, |_slot| dashboard.inc(1)).await;
+    render.abort();
+    dashboard.finish_with_message(format!(
+        "Subscription closed after {} blocks",
+        dashboard.position()
+    ));
+    result?;
+    Ok(())
 }
-*/
 
 #[tokio::main]
 async fn main() -> Result<()> {
     print_banner();
 
+    let cli = Cli::parse();
+
     let settings = get_settings()?;
+    init_logger_with_format(settings.get_log_format()?)?;
+    #[cfg(feature = "tracing")]
+    if let Some(endpoint) = &settings.tracing_endpoint {
+        arctis::logger::init_tracing(endpoint, "arctis")?;
+    }
+    let commitment = match cli.commitment {
+        Some(commitment) => parse_commitment(commitment.as_str())?,
+        None => settings.get_commitment()?,
+    };
+    let db_settings = cli.db_settings;
     let ctx = ExecutionContext {
         rpc_url: settings.rpc.solana_rpc_url,
+        rpc_fallbacks: settings.rpc.solana_rpc_fallbacks,
         ws_url: settings.rpc.solana_ws_url,
+        geyser_endpoint: settings.rpc.geyser_endpoint,
+        geyser_token: settings.rpc.geyser_token,
+        commitment,
+        metrics: Default::default(),
+        processing_options: cli.filter_settings.get_processing_options(),
+        with_metadata_enrichment: cli.with_metadata_enrichment,
+        events: Default::default(),
     };
 
-    let cli = Cli::parse();
-
     match cli.command {
         Commands::Parse { subcommand } => match subcommand {
             Parse::Block {
                 block_number,
                 dataset: _,
-                filter: _,
-            } => handle_parse_block(block_number, &ctx).await?,
-            // Parse::Blocks { block_range } => handle_parse_blocks(&block_range, &ctx).await?,
+                filter,
+            } => handle_parse_block(block_number, filter, &ctx).await?,
+            Parse::Blocks {
+                block_range,
+                download_concurrency,
+                parse_concurrency,
+                output,
+                format,
+                backup,
+            } => {
+                handle_parse_blocks(
+                    &block_range,
+                    download_concurrency,
+                    parse_concurrency,
+                    output.as_deref(),
+                    format,
+                    backup.as_deref(),
+                    &ctx,
+                )
+                .await?
+            }
             Parse::Tx { tx_id } => handle_parse_transaction(&tx_id, &ctx).await?,
         },
+        Commands::Wallet { subcommand } => handle_wallet(subcommand, &db_settings, &ctx).await?,
+        Commands::Export { subcommand } => match subcommand {
+            Export::Table {
+                db,
+                table,
+                format,
+                output,
+                where_clause,
+                migrate,
+            } => handle_export(
+                &db,
+                &table,
+                format,
+                &output,
+                where_clause.as_deref(),
+                migrate,
+                &db_settings,
+            )?,
+            Export::S3 {
+                db,
+                bucket,
+                prefix,
+                format,
+                tables,
+                migrate,
+            } => handle_export_s3(
+                &db,
+                &bucket,
+                &prefix,
+                format,
+                &tables,
+                migrate,
+                settings.s3.as_ref(),
+                &db_settings,
+            )?,
+            Export::Duckdb {
+                db,
+                output,
+                migrate,
+            } => handle_export_duckdb(&db, &output, migrate, &db_settings)?,
+        },
+        Commands::Analyze { subcommand } => match subcommand {
+            Analyze::CantDiscard { db } => handle_analyze_cant_discard(&db, &db_settings)?,
+            Analyze::DiscardReasons { db } => handle_analyze_discard_reasons(&db, &db_settings)?,
+            Analyze::Programs {
+                db,
+                top_programs,
+                min_calls,
+            } => handle_analyze_programs(&db, top_programs, min_calls, &db_settings)?,
+        },
+        Commands::Db { subcommand } => match subcommand {
+            Db::Prune {
+                db,
+                prune_before_slot,
+                prune_keep_last,
+            } => handle_db_prune(&db, prune_before_slot, prune_keep_last, &db_settings)?,
+            Db::Verify { db, start, end } => handle_db_verify(&db, start, end, &db_settings)?,
+        },
+        Commands::Metrics { block_number } => handle_metrics(block_number, &ctx).await?,
+        Commands::Monitor => handle_monitor(&ctx).await?,
         // Commands::Token { address } => handle_token(&address).await?,
-        // Commands::Monitor { strategy } => handle_monitor(&strategy, &ctx).await?,
     };
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arctis_types::{ParserResult, ParserResultData, SwapInfo, SwapType};
+
+    fn test_ctx() -> ExecutionContext {
+        ExecutionContext {
+            rpc_url: "http://localhost:8899".to_string(),
+            rpc_fallbacks: vec![],
+            ws_url: "ws://localhost:8900".to_string(),
+            geyser_endpoint: None,
+            geyser_token: None,
+            commitment: parse_commitment("confirmed").unwrap(),
+            metrics: Default::default(),
+            processing_options: Default::default(),
+            with_metadata_enrichment: false,
+            events: Default::default(),
+        }
+    }
+
+    fn swap(signer: &str, slot: u64, sol: f64) -> SwapInfo {
+        SwapInfo {
+            slot,
+            block_time: 1_700_000_000 + slot as i64,
+            signer: signer.to_string(),
+            signature: format!("sig_{}_{}", signer, slot),
+            error: false,
+            dex: DexType::Pumpfun,
+            swap_type: SwapType::Buy,
+            amount_in: sol,
+            token_in: WSOL.to_string(),
+            amount_out: 1000.0,
+            token_out: format!("Token{}", slot),
+            routing_path: vec![],
+        }
+    }
+
+    #[test]
+    fn test_format_slot_progress() {
+        let progress = SlotProgress {
+            last_received_slot: 101,
+            last_processed_slot: 100,
+            receive_lag_ms: 450,
+            processing_backlog: 3,
+        };
+        assert_eq!(
+            format_slot_progress(&progress, 12),
+            "received: 101 | processed: 100 (12 blocks) | lag: 450 ms | backlog: 3"
+        );
+    }
+
+    #[test]
+    fn test_parse_block_range() {
+        assert_eq!(parse_block_range("100:200").unwrap(), (100, 200));
+        assert_eq!(
+            parse_block_range("300000000:+100").unwrap(),
+            (300000000, 300000099)
+        );
+        assert!(parse_block_range("100").is_err());
+        assert!(parse_block_range("200:100").is_err());
+        assert!(parse_block_range("100:+0").is_err());
+        assert!(parse_block_range(&format!("{}:+2", u64::MAX)).is_err());
+        assert_eq!(
+            parse_block_range(&format!("{}:+1", u64::MAX)).unwrap(),
+            (u64::MAX, u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_parse_block_filter() {
+        let cli = Cli::try_parse_from(["arctis", "parse", "block", "--filter", "raydiumamm", "1"])
+            .unwrap();
+        let Commands::Parse {
+            subcommand: Parse::Block { filter, .. },
+        } = cli.command
+        else {
+            panic!("expected parse block");
+        };
+        assert_eq!(filter, DexType::RaydiumAmm);
+        assert_eq!(filter.to_string(), "RaydiumAmm");
+
+        assert!(Cli::try_parse_from(["arctis", "parse", "block", "--filter", "foo", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_db_prune() {
+        let cli = Cli::try_parse_from([
+            "arctis",
+            "db",
+            "prune",
+            "--db",
+            "db.duckdb",
+            "--prune-keep-last",
+            "100",
+        ])
+        .unwrap();
+        let Commands::Db {
+            subcommand:
+                Db::Prune {
+                    prune_before_slot,
+                    prune_keep_last,
+                    ..
+                },
+        } = cli.command
+        else {
+            panic!("expected db prune");
+        };
+        assert_eq!(prune_before_slot, None);
+        assert_eq!(prune_keep_last, Some(100));
+
+        assert!(Cli::try_parse_from(["arctis", "db", "prune", "--db", "db.duckdb"]).is_err());
+        assert!(Cli::try_parse_from([
+            "arctis",
+            "db",
+            "prune",
+            "--db",
+            "db.duckdb",
+            "--prune-before-slot",
+            "1",
+            "--prune-keep-last",
+            "100",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_db_settings() {
+        let cli = Cli::try_parse_from([
+            "arctis",
+            "db",
+            "verify",
+            "--db",
+            "db.duckdb",
+            "--db-memory-limit",
+            "1.5",
+            "--db-threads",
+            "4",
+        ])
+        .unwrap();
+        assert_eq!(cli.db_settings.memory_limit_gb, Some(1.5));
+        assert_eq!(cli.db_settings.threads, Some(4));
+
+        let cli = Cli::try_parse_from(["arctis", "db", "verify", "--db", "db.duckdb"]).unwrap();
+        assert_eq!(cli.db_settings.memory_limit_gb, None);
+    }
+
+    #[test]
+    fn test_parse_db_verify() {
+        let cli = Cli::try_parse_from([
+            "arctis",
+            "db",
+            "verify",
+            "--db",
+            "db.duckdb",
+            "--start",
+            "100",
+        ])
+        .unwrap();
+        let Commands::Db {
+            subcommand: Db::Verify { start, end, .. },
+        } = cli.command
+        else {
+            panic!("expected db verify");
+        };
+        assert_eq!(start, Some(100));
+        assert_eq!(end, None);
+    }
+
+    #[test]
+    fn test_parse_filter_programs() {
+        let cli = Cli::try_parse_from(["arctis", "parse", "block", "1"]).unwrap();
+        let options = cli.filter_settings.get_processing_options();
+        assert!(options.block_filter.is_none());
+
+        let cli = Cli::try_parse_from([
+            "arctis",
+            "parse",
+            "block",
+            "1",
+            "--filter-programs",
+            "ProgramA,ProgramB",
+            "--min-instruction-count",
+            "2",
+        ])
+        .unwrap();
+        let block_filter = cli
+            .filter_settings
+            .get_processing_options()
+            .block_filter
+            .unwrap();
+        assert_eq!(
+            block_filter.require_programs,
+            Some(vec!["ProgramA".to_string(), "ProgramB".to_string()])
+        );
+        assert_eq!(block_filter.exclude_signers, None);
+        assert_eq!(block_filter.min_instruction_count, Some(2));
+    }
+
+    #[test]
+    fn test_parse_export_s3() {
+        let cli = Cli::try_parse_from([
+            "arctis",
+            "export",
+            "s3",
+            "--db",
+            "db.duckdb",
+            "--bucket",
+            "bucket",
+            "--tables",
+            "swaps,blocks",
+        ])
+        .unwrap();
+        let Commands::Export {
+            subcommand: Export::S3 { tables, prefix, .. },
+        } = cli.command
+        else {
+            panic!("expected export s3");
+        };
+        assert_eq!(tables, vec!["swaps", "blocks"]);
+        assert_eq!(prefix, "");
+
+        assert_eq!(
+            get_s3_path("bucket", "", "swaps.parquet"),
+            "s3://bucket/swaps.parquet"
+        );
+        assert_eq!(
+            get_s3_path("bucket", "/2025/01/", "swaps.parquet"),
+            "s3://bucket/2025/01/swaps.parquet"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wallet_from_db_file() {
+        let db_path = std::env::temp_dir()
+            .join(format!("arctis_wallet_test_{}.db", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        {
+            let mut sol_db = SolanaDatabase::new_from_file(&db_path).unwrap();
+            let swaps = [
+                swap("wallet1", 1, 1.0),
+                swap("wallet1", 2, 2.0),
+                swap("wallet2", 3, 4.0),
+            ];
+            let swaps: Vec<&SwapInfo> = swaps.iter().collect();
+            sol_db.insert_swaps_bulk(&swaps).unwrap();
+        }
+
+        let source = DataSource {
+            db_path: Some(db_path.clone()),
+            migrate: false,
+            restore: None,
+            slot_start: None,
+            slot_end: None,
+            download_concurrency: 4,
+            parse_concurrency: 4,
+            quote_token: WSOL.to_string(),
+        };
+        let sol_db = load_database(&source, &DbSettings::default(), &test_ctx())
+            .await
+            .unwrap();
+        let activity = sol_db.get_wallet_activity("wallet1", WSOL, None).unwrap();
+        assert_eq!(activity.trade_count, 2);
+        assert_eq!(activity.total_volume_quote, 3.0);
+
+        let tokens = sol_db.get_wallet_tokens("wallet1", WSOL).unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].token, "Token2");
+
+        drop(sol_db);
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_export_unknown_table() {
+        let result = handle_export(
+            "does_not_exist.db",
+            "not_a_table",
+            ExportFileFormat::Csv,
+            "out.csv",
+            None,
+            false,
+            &DbSettings::default(),
+        );
+        assert!(result.unwrap_err().to_string().contains("Unknown table"));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_requires_source() {
+        let source = DataSource {
+            db_path: None,
+            migrate: false,
+            restore: None,
+            slot_start: Some(1),
+            slot_end: None,
+            download_concurrency: 4,
+            parse_concurrency: 4,
+            quote_token: WSOL.to_string(),
+        };
+        assert!(load_database(&source, &DbSettings::default(), &test_ctx())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_multi_swap_json() {
+        let result = ParserResult {
+            parsed: true,
+            ix_type: "Fill".to_string(),
+            data: ParserResultData::MultiSwap(vec![swap("taker", 1, 1.0), swap("maker", 1, 1.0)]),
+        };
+        let json = serde_json::to_value(&result).unwrap();
+        let swaps = json["data"]["MultiSwap"].as_array().unwrap();
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(swaps[0]["signer"], "taker");
+        assert_eq!(swaps[1]["signer"], "maker");
+    }
+}
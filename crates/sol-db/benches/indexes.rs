@@ -0,0 +1,68 @@
+use arctis_types::{DexType, SwapInfo, SwapType};
+use criterion::{criterion_group, criterion_main, Criterion};
+use sol_db::solana_db::SolanaDatabase;
+
+const ROWS: u64 = 100_000;
+const WSOL: &str = "So11111111111111111111111111111111111111112";
+
+fn create_db(with_indexes: bool) -> SolanaDatabase {
+    let mut db = SolanaDatabase::new().unwrap();
+    let swaps: Vec<SwapInfo> = (0..ROWS)
+        .map(|i| SwapInfo {
+            slot: i / 100,
+            block_time: 1_700_000_000 + (i / 100) as i64,
+            signer: format!("wallet_{}", i % 5_000),
+            signature: format!("sig_{}", i),
+            error: false,
+            dex: DexType::Pumpfun,
+            swap_type: SwapType::Buy,
+            amount_in: 1.0,
+            token_in: WSOL.to_string(),
+            amount_out: 1000.0,
+            token_out: format!("token_{}", i % 1_000),
+            routing_path: vec![],
+        })
+        .collect();
+    let swaps: Vec<&SwapInfo> = swaps.iter().collect();
+    db.insert_swaps_bulk(&swaps).unwrap();
+    if with_indexes {
+        db.create_default_indexes().unwrap();
+    }
+    db
+}
+
+fn bench_indexes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("swaps_by_signer");
+    for with_indexes in [false, true] {
+        let db = create_db(with_indexes);
+        let name = if with_indexes {
+            "with_indexes"
+        } else {
+            "without_indexes"
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                db.count_rows_where("swaps", "signer = 'wallet_42'")
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("swaps_by_token");
+    for with_indexes in [false, true] {
+        let db = create_db(with_indexes);
+        let name = if with_indexes {
+            "with_indexes"
+        } else {
+            "without_indexes"
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| db.count_rows_where("swaps", "token = 'token_42'").unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_indexes);
+criterion_main!(benches);
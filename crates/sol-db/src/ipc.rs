@@ -0,0 +1,88 @@
+//! Arrow IPC export for consumers like Polars or PyArrow.
+//! Record batches are streamed from DuckDB without the json round-trip of `query_to_json_parsed`.
+
+use std::io::Write;
+
+use anyhow::Result;
+use arrow::ipc::writer::StreamWriter;
+use bytes::Bytes;
+
+use crate::solana_db::SolanaDatabase;
+
+/// counts the bytes passed through to the inner writer
+struct CountingWriter<'a> {
+    inner: &'a mut dyn Write,
+    bytes_written: u64,
+}
+
+impl Write for CountingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes_written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl SolanaDatabase {
+    /// Writes the query result in the Arrow IPC streaming format to `output`.
+    /// Returns the number of bytes written.
+    pub fn query_to_arrow_ipc(&self, query: &str, output: &mut dyn Write) -> Result<u64> {
+        let mut stmt = self.conn.prepare(query)?;
+        let batches = stmt.query_arrow([])?;
+        let schema = batches.get_schema();
+
+        let mut counter = CountingWriter {
+            inner: output,
+            bytes_written: 0,
+        };
+        let mut writer = StreamWriter::try_new(&mut counter, &schema)?;
+        for batch in batches {
+            writer.write(&batch)?;
+        }
+        writer.finish()?;
+        drop(writer);
+
+        Ok(counter.bytes_written)
+    }
+
+    /// Same as `query_to_arrow_ipc` but buffers the stream in memory
+    pub fn query_to_arrow_bytes(&self, query: &str) -> Result<Bytes> {
+        let mut buffer = vec![];
+        self.query_to_arrow_ipc(query, &mut buffer)?;
+        Ok(Bytes::from(buffer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::ipc::reader::StreamReader;
+
+    #[test]
+    fn test_query_to_arrow_ipc() {
+        let db = SolanaDatabase::new().unwrap();
+        let query = "SELECT range AS slot, 'sig' || range AS signature FROM range(5000)";
+
+        let bytes = db.query_to_arrow_bytes(query).unwrap();
+        let mut buffer = vec![];
+        let bytes_written = db.query_to_arrow_ipc(query, &mut buffer).unwrap();
+        assert_eq!(bytes_written, buffer.len() as u64);
+        assert_eq!(bytes.len(), buffer.len());
+
+        let reader = StreamReader::try_new(bytes.as_ref(), None).unwrap();
+        let schema = reader.schema();
+        assert_eq!(schema.field(0).name(), "slot");
+        assert_eq!(schema.field(1).name(), "signature");
+        let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+        assert_eq!(rows, 5000);
+
+        // empty results still carry the schema
+        let bytes = db.query_to_arrow_bytes("SELECT * FROM swaps").unwrap();
+        let reader = StreamReader::try_new(bytes.as_ref(), None).unwrap();
+        assert!(reader.schema().field_with_name("signer").is_ok());
+    }
+}
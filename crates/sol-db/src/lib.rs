@@ -1,2 +1,4 @@
+pub mod ipc;
+pub mod raw;
 pub mod solana_db;
 pub mod utils;
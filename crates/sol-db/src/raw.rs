@@ -0,0 +1,214 @@
+//! Escape hatch for ad-hoc SQL against the parsed data.
+//! Prefer the typed methods on `SolanaDatabase`: queries here are not validated
+//! and can break when the schema changes.
+
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use arctis_types::SwapInfo;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::solana_db::SolanaDatabase;
+
+impl SolanaDatabase {
+    /// Runs a SELECT statement and deserializes every row into `T`.
+    /// Columns are matched by name, columns without a field in `T` are ignored.
+    pub fn execute_raw_query<T: DeserializeOwned>(&self, select_sql: &str) -> Result<Vec<T>> {
+        self.execute_raw_query_json(select_sql)?
+            .into_iter()
+            .map(|row| Ok(serde_json::from_value(row)?))
+            .collect()
+    }
+
+    /// Runs a SELECT statement and returns every row as a json object
+    pub fn execute_raw_query_json(&self, select_sql: &str) -> Result<Vec<Value>> {
+        Ok(self.query_to_json_parsed(select_sql)?)
+    }
+
+    /// Runs statements with side effects e.g. CREATE, INSERT, UPDATE
+    pub fn execute_raw_ddl(&mut self, sql: &str) -> Result<()> {
+        self.conn.execute_batch(sql)?;
+        Ok(())
+    }
+
+    /// Attaches another database file (read only) for the duration of `query`.
+    /// The query can reference its tables with the alias from `get_attach_alias`,
+    /// e.g. `swaps_2024_01.db` -> `swaps_2024_01.swaps`, and the tables of this
+    /// database with its name: `SELECT * FROM db1.swaps a JOIN db2.swaps b ON a.token = b.token`
+    pub fn attach_and_query(&self, other_db_path: &str, query: &str) -> Result<Vec<Value>> {
+        if !Path::new(other_db_path).is_file() {
+            return Err(anyhow!("Database file not found: {}", other_db_path));
+        }
+        let alias = get_attach_alias(other_db_path);
+        let current_database: String =
+            self.conn
+                .query_row("SELECT current_database()", [], |row| row.get(0))?;
+        if alias == current_database {
+            return Err(anyhow!(
+                "Cannot attach {} as '{}': the name is used by this database",
+                other_db_path,
+                alias
+            ));
+        }
+
+        self.conn.execute_batch(&format!(
+            "ATTACH '{}' AS {} (READ_ONLY);",
+            other_db_path.replace('\'', "''"),
+            alias
+        ))?;
+        let result = self.execute_raw_query_json(query);
+        // detach even if the query failed
+        self.conn.execute_batch(&format!("DETACH {};", alias))?;
+        result
+    }
+
+    /// Swaps of the other database that trade a token which is also traded in this database
+    pub fn cross_join_swaps(&self, other_db_path: &str) -> Result<Vec<SwapInfo>> {
+        let alias = get_attach_alias(other_db_path);
+        let query = format!(
+            "
+      SELECT other.*
+      FROM {}.swaps other
+      WHERE other.token IN (SELECT DISTINCT token FROM main.swaps)
+      ORDER BY other.slot, other.signature
+      ",
+            alias
+        );
+        self.attach_and_query(other_db_path, &query)?
+            .into_iter()
+            .map(|row| Ok(serde_json::from_value(row)?))
+            .collect()
+    }
+}
+
+/// The file name without extension, lowercased. Characters that aren't valid in an
+/// unquoted identifier are replaced with `_`, names starting with a digit get a `db_` prefix
+pub fn get_attach_alias(db_path: &str) -> String {
+    let stem = Path::new(db_path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("other");
+    let alias: String = stem
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '_',
+        })
+        .collect();
+    match alias.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => alias,
+        _ => format!("db_{}", alias),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arctis_types::{DexType, SwapInfo, SwapType};
+
+    #[test]
+    fn test_execute_raw_query() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let swap = SwapInfo {
+            slot: 1,
+            block_time: 1_700_000_000,
+            signer: "wallet1".to_string(),
+            signature: "sig".to_string(),
+            error: false,
+            dex: DexType::Pumpfun,
+            swap_type: SwapType::Buy,
+            amount_in: 1.5,
+            token_in: "So11111111111111111111111111111111111111112".to_string(),
+            amount_out: 1000.0,
+            token_out: "TokenA".to_string(),
+            routing_path: vec![],
+        };
+        db.insert_swaps_bulk(&vec![&swap]).unwrap();
+
+        let swaps = db
+            .execute_raw_query::<SwapInfo>("SELECT * FROM swaps")
+            .unwrap();
+        assert_eq!(swaps, vec![swap]);
+
+        db.execute_raw_ddl("DELETE FROM swaps WHERE signer = 'wallet1'")
+            .unwrap();
+        let rows = db
+            .execute_raw_query_json("SELECT COUNT(*) AS count FROM swaps")
+            .unwrap();
+        assert_eq!(rows[0]["count"], 0);
+
+        assert!(db
+            .execute_raw_query::<SwapInfo>("SELECT 1 AS slot")
+            .is_err());
+    }
+
+    #[test]
+    fn test_get_attach_alias() {
+        assert_eq!(get_attach_alias("/data/Swaps-2024.01.db"), "swaps_2024_01");
+        assert_eq!(get_attach_alias("2024.db"), "db_2024");
+        assert_eq!(get_attach_alias("_tmp.db"), "db__tmp");
+    }
+
+    #[test]
+    fn test_attach_and_query() {
+        let dir = std::env::temp_dir().join(format!("arctis_attach_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let swap = |slot: u64, token: &str| SwapInfo {
+            slot,
+            block_time: 1_700_000_000 + slot as i64,
+            signer: "wallet1".to_string(),
+            signature: format!("sig_{}", slot),
+            error: false,
+            dex: DexType::Pumpfun,
+            swap_type: SwapType::Buy,
+            amount_in: 1.0,
+            token_in: "So11111111111111111111111111111111111111112".to_string(),
+            amount_out: 1000.0,
+            token_out: token.to_string(),
+            routing_path: vec![],
+        };
+
+        let mut db1 = SolanaDatabase::new_from_file(&path("db1.db")).unwrap();
+        db1.insert_swaps_bulk(&vec![&swap(1, "TokenA"), &swap(2, "TokenB")])
+            .unwrap();
+
+        let mut db2 = SolanaDatabase::new_from_file(&path("db2.db")).unwrap();
+        let later_swaps = [swap(10, "TokenA"), swap(11, "TokenC"), swap(12, "TokenA")];
+        db2.insert_swaps_bulk(&later_swaps.iter().collect())
+            .unwrap();
+        drop(db2);
+
+        let rows = db1
+            .attach_and_query(
+                &path("db2.db"),
+                "
+      SELECT a.token, a.slot AS first_slot, b.slot AS second_slot
+      FROM db1.swaps a JOIN db2.swaps b ON a.token = b.token
+      ORDER BY b.slot
+      ",
+            )
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["token"], "TokenA");
+        assert_eq!(rows[0]["first_slot"], 1);
+        assert_eq!(rows[1]["second_slot"], 12);
+
+        let swaps = db1.cross_join_swaps(&path("db2.db")).unwrap();
+        assert_eq!(swaps, vec![later_swaps[0].clone(), later_swaps[2].clone()]);
+
+        // detached after each query
+        assert!(db1
+            .execute_raw_query_json("SELECT * FROM db2.swaps")
+            .is_err());
+
+        let err = db1
+            .attach_and_query(&path("missing.db"), "SELECT 1")
+            .unwrap_err();
+        assert!(err.to_string().contains("Database file not found"));
+
+        drop(db1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,13 +1,16 @@
 use arctis_types::{
-    DexType, EncodedTransactionWithStatusMeta, NewToken, ParserResult, SolTransfer,
-    SplTokenTransfer, SupplyChange, SwapInfo, SwapType,
+    AccountInfo, BlockInfo, BondingCurveCompletion, DexType, EncodedTransactionWithStatusMeta,
+    MetadataUpdate, NewToken, ParserResult, SandwichAttack, SolTransfer, SplTokenTransfer,
+    SupplyChange, SwapInfo, SwapType, TokenMetadata,
 };
-use duckdb::arrow::array::Array;
+use duckdb::arrow::array::{Array, ArrayRef};
 use duckdb::arrow::datatypes::DataType;
 use duckdb::types::{EnumType, ListType};
-use duckdb::{params, Connection, Result};
-use serde::Serialize;
+use duckdb::{params, Connection, Result, Row, Statement};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::broadcast;
 
 use crate::utils::print_json_objects_as_table;
 
@@ -53,13 +56,36 @@ pub struct ProcessedTransaction {
     pub parsed_programs: Vec<ProgramParserData>,
     pub parsed_ix: Vec<ParserResult>,
     pub data: Option<EncodedTransactionWithStatusMeta>,
+    pub jito_tip_lamports: Option<u64>,
+    /// time spent in `Parser::parse` for all instructions
+    pub parse_duration_us: u64,
 }
 
+/// Row of the `transactions` table, without the parsed instructions and raw data
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StoredTransaction {
+    pub slot: u64,
+    pub block_time: i64,
+    pub signature: String,
+    pub signer: String,
+    pub has_error: bool,
+    pub compute_units: u64,
+    pub fee: u64,
+    pub version: i8,
+    pub is_discarded: bool,
+    pub discard_reason: Option<String>,
+}
+
+#[derive(Debug, Clone)]
 pub struct ProcessedBlock {
     pub slot: u64,
     pub block_time: i64,
     pub parent_slot: u64,
     pub transaction_count: u32,
+    pub blockhash: String,
+    pub leader: Option<String>,
+    /// fee reward of the leader in lamports
+    pub rewards: Option<u64>,
 }
 
 pub struct ComputeBudgetProcessed {
@@ -70,6 +96,544 @@ pub struct ComputeBudgetProcessed {
     pub fee: u64,
 }
 
+/// a row of the `parse_stats` table
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TransactionParseStats {
+    pub slot: u64,
+    pub signature: String,
+    pub parse_us: u64,
+    pub insert_us: u64,
+    pub ix_count: u32,
+}
+
+/// a claimed staking reward, amount in token units
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RewardProcessed {
+    pub slot: u64,
+    pub block_time: i64,
+    pub signature: String,
+    pub mint: String,
+    pub amount: f64,
+    pub authority: String,
+}
+
+/// a Metaplex metadata update of a token, old values are None if the token is not in `tokens`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TokenMetadataUpdate {
+    pub slot: u64,
+    pub signature: String,
+    pub mint: String,
+    pub old_name: Option<String>,
+    pub new_name: Option<String>,
+    pub old_symbol: Option<String>,
+    pub new_symbol: Option<String>,
+}
+
+/// an open, init or close event of a token account
+pub struct AccountProcessed {
+    pub slot: u64,
+    pub block_time: i64,
+    pub signature: String,
+    pub account: AccountInfo,
+}
+
+/// current state of a token account, aggregated from all its events
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AccountLifecycle {
+    pub account: String,
+    pub mint: Option<String>,
+    pub owner: Option<String>,
+    /// latest create or initialize tx
+    pub open_tx: Option<String>,
+    /// latest close tx
+    pub close_tx: Option<String>,
+    /// closed after it was opened the last time
+    pub is_closed: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WalletPnl {
+    pub realized_pnl: f64,
+    pub total_bought_quote: f64,
+    pub total_sold_quote: f64,
+    pub trade_count: i64,
+    pub tokens_traded: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TraderStats {
+    pub signer: String,
+    pub total_volume_quote: f64,
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+    pub trade_count: i64,
+    pub unique_tokens: i64,
+}
+
+/// buys and sells of a wallet against SOL, slots are None if the wallet has no swaps
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WalletSwapSummary {
+    pub signer: String,
+    pub buy_count: i64,
+    pub sell_count: i64,
+    pub total_sol_spent: f64,
+    pub total_sol_received: f64,
+    pub unique_tokens_bought: i64,
+    pub unique_tokens_sold: i64,
+    pub first_trade_slot: Option<u64>,
+    pub last_trade_slot: Option<u64>,
+}
+
+/// how much of the requested compute unit limits transactions consumed
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ComputeEfficiencyStats {
+    pub avg_efficiency_pct: f64,
+    pub median_efficiency_pct: f64,
+    pub total_wasted_cu: i64,
+    /// signatures with the lowest efficiency, lowest first
+    pub worst_offenders: Vec<String>,
+}
+
+/// a program instruction that kept transactions from being discarded
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CantDiscardStats {
+    pub program_id: String,
+    pub fn_name: String,
+    pub occurrence_count: i64,
+    pub example_signature: String,
+}
+
+/// invocations of a program in `tx_programs`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ProgramFrequency {
+    pub program_id: String,
+    pub total_calls: i64,
+    /// calls a parser handled
+    pub parsed_calls: i64,
+    /// calls in failed transactions
+    pub error_calls: i64,
+    pub parse_rate_pct: f64,
+}
+
+/// creation of a token and its first swap, the first trade fields are None if it was never traded
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TokenLaunchStats {
+    pub mint: String,
+    pub create_block_time: i64,
+    pub first_trade_block_time: Option<i64>,
+    pub first_trade_slot: Option<u64>,
+    /// signer of the first Buy, can be later than the first trade
+    pub first_buyer: Option<String>,
+    pub slots_to_first_trade: Option<i64>,
+    pub seconds_to_first_trade: Option<i64>,
+}
+
+/// Balance of a holder reconstructed from `token_transfers`.
+/// Amounts are raw token units as stored by the token program parser, not scaled by decimals
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TokenHolderBalance {
+    pub holder: String,
+    pub net_amount: f64,
+    /// total amount received
+    pub buy_amount: f64,
+    /// total amount sent
+    pub sell_amount: f64,
+}
+
+/// `SplTokenTransfer` of `token_transfers` with the amount divided by the token decimals
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DecodedTokenTransfer {
+    pub slot: u64,
+    pub block_time: i64,
+    pub signature: String,
+    pub from_acc: String,
+    pub to_acc: String,
+    pub amount: f64,
+    pub raw_amount: u64,
+    pub authority: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// of `tokens`, the decimals stored with the transfer if the token is unknown
+    pub decimals: Option<u8>,
+    pub token: Option<String>,
+}
+
+/// Activity of a UTC day
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DailySummary {
+    /// YYYY-MM-DD
+    pub date: String,
+    pub slot_count: i64,
+    pub tx_count: i64,
+    pub swap_count: i64,
+    pub new_token_count: i64,
+    pub unique_traders: i64,
+    pub total_sol_volume: f64,
+    /// SOL spent on buys
+    pub total_buy_volume: f64,
+    /// SOL received from sells
+    pub total_sell_volume: f64,
+}
+
+/// Supply of a token after a supply change
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SupplySnapshot {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: i64,
+    pub delta: i128,
+    /// `tokens.initial_supply` plus all changes up to and including this one
+    pub cumulative_supply: i128,
+}
+
+/// a wallet that traded the same tokens in the same slots as another wallet
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WalletInteraction {
+    pub other_wallet: String,
+    pub shared_tokens: Vec<String>,
+    pub shared_token_count: u32,
+    pub first_shared_trade_slot: u64,
+}
+
+/// directed graph of swapped tokens, an edge per (token_in, token_out) pair
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SwapGraph {
+    /// tokens of the edges, sorted
+    pub nodes: Vec<String>,
+    pub edges: Vec<SwapEdge>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SwapEdge {
+    pub from_token: String,
+    pub to_token: String,
+    /// SOL side of buys and sells, token to token swaps have no SOL volume
+    pub volume: f64,
+    pub trade_count: i64,
+}
+
+/// wallets connected to a root wallet by SOL transfers, an edge per (from, to) pair
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SolTransferNetwork {
+    /// wallets of the edges and the root wallet, sorted
+    pub nodes: Vec<String>,
+    pub edges: Vec<SolEdge>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SolEdge {
+    pub from: String,
+    pub to: String,
+    pub total_sol: f64,
+    pub transfer_count: i64,
+}
+
+/// first buy of a wallet of a token
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FirstBuyerStats {
+    pub signer: String,
+    pub buy_slot: u64,
+    pub buy_block_time: i64,
+    pub amount_sol_spent: f64,
+    pub amount_token_received: f64,
+    /// 1 for the first buyer, by slot and signature
+    pub rank: u32,
+}
+
+/// a wallet that repeatedly bought and quickly sold the same token
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WashTradingCandidate {
+    pub wallet: String,
+    pub token: String,
+    pub round_trips: u32,
+    /// SOL volume of the swaps that are part of a round trip
+    pub total_volume: f64,
+    /// fees (SOL) of the transactions of these swaps
+    pub total_fees_paid: f64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WalletTokenVolume {
+    pub token: String,
+    pub volume_quote: f64,
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+    pub trade_count: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SwapFilter {
+    pub dex: Option<DexType>,
+    pub swap_type: Option<SwapType>,
+    pub signer: Option<String>,
+    pub token: Option<String>,
+    pub slot_start: Option<u64>,
+    pub slot_end: Option<u64>,
+    pub min_amount_in: Option<f64>,
+    pub limit: Option<u32>,
+}
+
+impl SwapFilter {
+    pub fn builder() -> SwapFilterBuilder {
+        SwapFilterBuilder {
+            filter: SwapFilter::default(),
+        }
+    }
+}
+
+pub struct SwapFilterBuilder {
+    filter: SwapFilter,
+}
+
+impl SwapFilterBuilder {
+    pub fn with_dex(mut self, dex: DexType) -> Self {
+        self.filter.dex = Some(dex);
+        self
+    }
+
+    pub fn with_swap_type(mut self, swap_type: SwapType) -> Self {
+        self.filter.swap_type = Some(swap_type);
+        self
+    }
+
+    pub fn with_signer(mut self, signer: &str) -> Self {
+        self.filter.signer = Some(signer.to_string());
+        self
+    }
+
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.filter.token = Some(token.to_string());
+        self
+    }
+
+    /// inclusive slot range
+    pub fn with_slot_range(mut self, slot_start: u64, slot_end: u64) -> Self {
+        self.filter.slot_start = Some(slot_start);
+        self.filter.slot_end = Some(slot_end);
+        self
+    }
+
+    pub fn with_min_amount_in(mut self, min_amount_in: f64) -> Self {
+        self.filter.min_amount_in = Some(min_amount_in);
+        self
+    }
+
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.filter.limit = Some(limit);
+        self
+    }
+
+    pub fn build(self) -> SwapFilter {
+        self.filter
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PagedResult {
+    pub rows: Vec<Value>,
+    pub page: u64,
+    pub total_pages: u64,
+    pub total_rows: u64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct JitoTipStats {
+    pub total_tip_lamports: u64,
+    pub avg_tip_lamports: f64,
+    pub tx_count: i64,
+}
+
+/// Fees paid by a signer over all its transactions in the database
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WalletFeeStats {
+    pub total_base_fee_lamports: i64,
+    pub total_priority_fee_lamports: i64,
+    pub total_fee_lamports: i64,
+    pub transaction_count: i64,
+    /// over all transactions, 0 for transactions without compute unit price
+    pub average_priority_fee_lamports: f64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+pub struct LoadStats {
+    pub files_loaded: u32,
+    pub rows_loaded: HashMap<String, u64>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TableStats {
+    pub table_name: String,
+    pub row_count: i64,
+    /// uncompressed estimate: rows * fixed column widths (strings count as 16 bytes)
+    pub estimated_size_bytes: i64,
+}
+
+/// a schema change for databases that were created by an older version
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    /// recorded as version in `schema_migrations`
+    pub name: String,
+    pub up_sql: String,
+    /// returns 1 if the change is already part of the schema
+    pub check_sql: String,
+}
+
+fn add_column_migration(table: &str, column: &str, column_type: &str) -> Migration {
+    Migration {
+        name: format!("add_{}_{}", table, column),
+        up_sql: format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, column_type),
+        check_sql: format!(
+            "SELECT COUNT(*) FROM information_schema.columns WHERE table_catalog = current_database() AND table_schema = 'main' AND table_name = '{}' AND column_name = '{}'",
+            table, column
+        ),
+    }
+}
+
+fn create_table_migration(table: &str, columns: &str) -> Migration {
+    Migration {
+        name: format!("create_{}", table),
+        up_sql: format!("CREATE TABLE IF NOT EXISTS {} ({});", table, columns),
+        check_sql: format!(
+            "SELECT COUNT(*) FROM information_schema.tables WHERE table_catalog = current_database() AND table_schema = 'main' AND table_name = '{}'",
+            table
+        ),
+    }
+}
+
+fn alter_column_type_migration(
+    table: &str,
+    column: &str,
+    column_type: &str,
+    using: &str,
+) -> Migration {
+    Migration {
+        name: format!("alter_{}_{}_{}", table, column, column_type.to_lowercase()),
+        up_sql: format!(
+            "ALTER TABLE {} ALTER {} TYPE {} USING {};",
+            table, column, column_type, using
+        ),
+        // also applied if the table doesn't exist
+        check_sql: format!(
+            "SELECT (COUNT(*) = 0)::BIGINT FROM information_schema.columns WHERE table_catalog = current_database() AND table_schema = 'main' AND table_name = '{}' AND column_name = '{}' AND data_type != '{}'",
+            table, column, column_type
+        ),
+    }
+}
+
+const SWAP_TYPE_VALUES: [&str; 4] = ["Buy", "Sell", "Token", "Arbitrage"];
+const DEX_TYPE_VALUES: [&str; 7] = [
+    "Jupiterv6",
+    "Pumpfun",
+    "RaydiumAmm",
+    "RaydiumStableAmm",
+    "OpenBook",
+    "Helium",
+    "Unknown",
+];
+
+/// 'a', 'b', .. as used in CREATE TYPE and list literals
+fn enum_values_sql(values: &[&str]) -> String {
+    values
+        .iter()
+        .map(|value| format!("'{}'", value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// DuckDB can't add values to an enum: the columns are converted to text, the type is recreated
+/// with all `values` and the columns are converted back.
+/// Indexes on the columns have to be dropped before, DuckDB can't alter indexed columns
+fn recreate_enum_migration(
+    type_name: &str,
+    values: &[&str],
+    columns: &[(&str, &str)],
+) -> Migration {
+    let values_sql = enum_values_sql(values);
+    let alter_columns = |column_type: &str| {
+        columns
+            .iter()
+            .map(|(table, column)| {
+                format!(
+                    "ALTER TABLE {} ALTER {} TYPE {};\n",
+                    table, column, column_type
+                )
+            })
+            .collect::<String>()
+    };
+    Migration {
+        // the number of values is part of the name: adding a value needs a new migration
+        name: format!("recreate_{}_{}", type_name.to_lowercase(), values.len()),
+        up_sql: format!(
+            "{}DROP TYPE {};\nCREATE TYPE {} AS ENUM ({});\n{}",
+            alter_columns("VARCHAR"),
+            type_name,
+            type_name,
+            values_sql,
+            alter_columns(type_name)
+        ),
+        // also applied if the type doesn't exist
+        check_sql: format!(
+            "SELECT (COUNT(*) = 0)::BIGINT FROM duckdb_types() WHERE database_name = current_database() AND type_name = '{}' AND labels != [{}]",
+            type_name, values_sql
+        ),
+    }
+}
+
+/// All schema changes since the first release, in the order they were made.
+/// Columns are only appended: the appenders insert by position.
+pub fn default_migrations() -> Vec<Migration> {
+    vec![
+        add_column_migration("transactions", "jito_tip_lamports", "BIGINT DEFAULT NULL"),
+        // inserted by column name
+        add_column_migration("blocks", "blockhash", "TEXT"),
+        add_column_migration("blocks", "leader", "TEXT DEFAULT NULL"),
+        add_column_migration("blocks", "rewards", "BIGINT DEFAULT NULL"),
+        create_table_migration(
+            "metadata",
+            "mint TEXT PRIMARY KEY, image_url TEXT, description TEXT, twitter TEXT, telegram TEXT",
+        ),
+        create_table_migration(
+            "sandwich_attacks",
+            "slot BIGINT, attacker TEXT, victim TEXT, token TEXT, front_run_sig TEXT, back_run_sig TEXT, victim_sig TEXT, estimated_profit_sol DOUBLE",
+        ),
+        create_table_migration(
+            "accounts",
+            "slot BIGINT, block_time BIGINT, signature TEXT, account TEXT, owner TEXT, mint TEXT DEFAULT NULL, decimals INTEGER DEFAULT NULL, open_tx TEXT DEFAULT NULL, init_tx TEXT DEFAULT NULL, close_tx TEXT DEFAULT NULL, close_destination TEXT DEFAULT NULL",
+        ),
+        create_table_migration(
+            "bonding_curve_completions",
+            "slot BIGINT, block_time BIGINT, signature TEXT, mint TEXT PRIMARY KEY, bonding_curve TEXT, signer TEXT",
+        ),
+        create_table_migration(
+            "parse_stats",
+            "slot BIGINT, signature TEXT, parse_us BIGINT, insert_us BIGINT, ix_count INTEGER",
+        ),
+        create_table_migration(
+            "rewards",
+            "slot BIGINT, block_time BIGINT, signature TEXT, mint TEXT, amount FLOAT, authority TEXT",
+        ),
+        create_table_migration(
+            "swap_hops",
+            "signature TEXT, hop_index INTEGER, amm TEXT, input_mint TEXT, output_mint TEXT, input_amount FLOAT, output_amount FLOAT",
+        ),
+        create_table_migration(
+            "token_metadata_updates",
+            "slot BIGINT, signature TEXT, mint TEXT, old_name TEXT, new_name TEXT, old_symbol TEXT, new_symbol TEXT",
+        ),
+        // FLOAT loses precision above 2^24, amounts stored before can't be recovered
+        alter_column_type_migration(
+            "token_transfers",
+            "amount",
+            "VARCHAR",
+            "round(amount)::HUGEINT::VARCHAR",
+        ),
+        // databases of older versions reject swaps of the dexes and swap types added since
+        recreate_enum_migration("SwapType", &SWAP_TYPE_VALUES, &[("swaps", "swap_type")]),
+        recreate_enum_migration("DexType", &DEX_TYPE_VALUES, &[("swaps", "dex")]),
+    ]
+}
+
 fn create_connection(file_path: Option<&str>, use_primary_keys: bool) -> Result<Connection> {
     let conn = match file_path {
         Some(path) => {
@@ -90,14 +654,17 @@ fn create_connection(file_path: Option<&str>, use_primary_keys: bool) -> Result<
             "
       BEGIN;
 
-      CREATE TYPE SwapType AS ENUM ('Buy', 'Sell', 'Token');
-      CREATE TYPE DexType AS ENUM ('Jupiterv6', 'Pumpfun', 'RaydiumAmm', 'Unknown');
+      CREATE TYPE SwapType AS ENUM ({swap_types});
+      CREATE TYPE DexType AS ENUM ({dex_types});
 
       CREATE table blocks (
         slot BIGINT {},
         block_time BIGINT,
         parent_slot BIGINT,
         transaction_count INTEGER,
+        blockhash TEXT,
+        leader TEXT DEFAULT NULL,
+        rewards BIGINT DEFAULT NULL
       );
       CREATE TABLE transactions (
         slot BIGINT,
@@ -112,7 +679,8 @@ fn create_connection(file_path: Option<&str>, use_primary_keys: bool) -> Result<
         version INTEGER,
         is_discarded BOOLEAN,
         discard_reason TEXT,
-        data JSON
+        data JSON,
+        jito_tip_lamports BIGINT DEFAULT NULL
       );
       CREATE TABLE swaps (
         slot BIGINT,
@@ -167,7 +735,7 @@ fn create_connection(file_path: Option<&str>, use_primary_keys: bool) -> Result<
         dst TEXT DEFAULT NULL,
         from_acc TEXT,
         to_acc TEXT,
-        amount FLOAT,
+        amount TEXT, -- raw u64
         token TEXT DEFAULT NULL,
         decimals INTEGER DEFAULT 0,
         authority TEXT DEFAULT NULL
@@ -194,6 +762,107 @@ fn create_connection(file_path: Option<&str>, use_primary_keys: bool) -> Result<
         has_error BOOLEAN 
         {}
       );
+      CREATE TABLE metadata (
+        mint TEXT {},
+        image_url TEXT,
+        description TEXT,
+        twitter TEXT,
+        telegram TEXT
+      );
+      CREATE TABLE sandwich_attacks (
+        slot BIGINT,
+        attacker TEXT,
+        victim TEXT,
+        token TEXT,
+        front_run_sig TEXT,
+        back_run_sig TEXT,
+        victim_sig TEXT,
+        estimated_profit_sol DOUBLE
+      );
+      CREATE TABLE accounts (
+        slot BIGINT,
+        block_time BIGINT,
+        signature TEXT,
+        account TEXT,
+        owner TEXT,
+        mint TEXT DEFAULT NULL,
+        decimals INTEGER DEFAULT NULL,
+        open_tx TEXT DEFAULT NULL,
+        init_tx TEXT DEFAULT NULL,
+        close_tx TEXT DEFAULT NULL,
+        close_destination TEXT DEFAULT NULL
+      );
+      CREATE TABLE bonding_curve_completions (
+        slot BIGINT,
+        block_time BIGINT,
+        signature TEXT,
+        mint TEXT {},
+        bonding_curve TEXT,
+        signer TEXT
+      );
+      CREATE TABLE parse_stats (
+        slot BIGINT,
+        signature TEXT,
+        parse_us BIGINT,
+        insert_us BIGINT,
+        ix_count INTEGER
+      );
+      CREATE TABLE rewards (
+        slot BIGINT,
+        block_time BIGINT,
+        signature TEXT,
+        mint TEXT,
+        amount FLOAT,
+        authority TEXT
+      );
+      CREATE TABLE swap_hops (
+        signature TEXT,
+        hop_index INTEGER,
+        amm TEXT,
+        input_mint TEXT,
+        output_mint TEXT,
+        input_amount FLOAT,
+        output_amount FLOAT
+      );
+      CREATE TABLE token_metadata_updates (
+        slot BIGINT,
+        signature TEXT,
+        mint TEXT,
+        old_name TEXT,
+        new_name TEXT,
+        old_symbol TEXT,
+        new_symbol TEXT
+      );
+      -- priority_fee is in micro-lamports per CU: / 10^6 -> lamports, / 10^9 -> SOL
+      CREATE VIEW fee_stats AS
+      SELECT
+        f.slot,
+        f.block_time,
+        f.signature,
+        t.signer,
+        f.compute_unit_limit,
+        f.priority_fee,
+        t.compute_units,
+        t.fee AS tx_fee_lamports,
+        (f.compute_unit_limit::DOUBLE * f.priority_fee) / 1000000000000000.0 AS effective_priority_fee_sol
+      FROM fees f
+      LEFT JOIN transactions t ON f.signature = t.signature;
+      -- events are ordered by slot and insert order: an account can be opened and closed in the same tx
+      CREATE VIEW account_states AS
+      WITH events AS (
+        SELECT *, row_number() OVER (ORDER BY slot, rowid) AS event_idx
+        FROM accounts
+      )
+      SELECT
+        account,
+        arg_max(mint, event_idx) FILTER (WHERE mint IS NOT NULL) AS mint,
+        arg_max(owner, event_idx) FILTER (WHERE owner IS NOT NULL) AS owner,
+        arg_max(COALESCE(open_tx, init_tx), event_idx) FILTER (WHERE COALESCE(open_tx, init_tx) IS NOT NULL) AS open_tx,
+        arg_max(close_tx, event_idx) FILTER (WHERE close_tx IS NOT NULL) AS close_tx,
+        COALESCE(max(event_idx) FILTER (WHERE close_tx IS NOT NULL), 0)
+          > COALESCE(max(event_idx) FILTER (WHERE close_tx IS NULL), 0) AS is_closed
+      FROM events
+      GROUP BY account;
       COMMIT;
       ",
             if use_primary_keys { "PRIMARY KEY" } else { "" }, // blocks
@@ -211,6 +880,10 @@ fn create_connection(file_path: Option<&str>, use_primary_keys: bool) -> Result<
             } else {
                 ""
             }, // tx_programs
+            if use_primary_keys { "PRIMARY KEY" } else { "" }, // metadata
+            if use_primary_keys { "PRIMARY KEY" } else { "" }, // bonding_curve_completions
+            swap_types = enum_values_sql(&SWAP_TYPE_VALUES),
+            dex_types = enum_values_sql(&DEX_TYPE_VALUES),
         )
         .as_str(),
     )?;
@@ -218,12 +891,314 @@ fn create_connection(file_path: Option<&str>, use_primary_keys: bool) -> Result<
     Ok(conn)
 }
 
+/// all tables created by `create_connection`
+pub const TABLES: [&str; 18] = [
+    "blocks",
+    "transactions",
+    "swaps",
+    "sol_transfers",
+    "tokens",
+    "supply_changes",
+    "token_transfers",
+    "fees",
+    "cant_discard",
+    "tx_programs",
+    "metadata",
+    "sandwich_attacks",
+    "accounts",
+    "bonding_curve_completions",
+    "parse_stats",
+    "rewards",
+    "swap_hops",
+    "token_metadata_updates",
+];
+
+/// tables that get a primary key when `use_primary_keys` is set
+fn has_primary_key(table: &str) -> bool {
+    matches!(
+        table,
+        "blocks"
+            | "transactions"
+            | "tokens"
+            | "supply_changes"
+            | "fees"
+            | "cant_discard"
+            | "tx_programs"
+            | "metadata"
+            | "bonding_curve_completions"
+    )
+}
+
+/// columns that identify a row when removing duplicates.
+/// for tables without a natural key this is the full set of parsed values:
+/// e.g. two identical sol transfers in the same tx are merged into one row
+pub fn get_dedup_key_columns(table: &str) -> &'static [&'static str] {
+    match table {
+        "blocks" => &["slot"],
+        "transactions" => &["signature"],
+        "swaps" => &[
+            "signature",
+            "signer",
+            "swap_type",
+            "token_in",
+            "amount_in",
+            "token_out",
+            "amount_out",
+        ],
+        "sol_transfers" => &["signature", "src", "dst", "lamports"],
+        "tokens" => &["mint"],
+        "supply_changes" => &["signature", "ix_index"],
+        "token_transfers" => &["signature", "from_acc", "to_acc", "amount"],
+        "fees" => &["signature"],
+        "cant_discard" => &["signature"],
+        "tx_programs" => &["signature", "ix_index"],
+        "metadata" => &["mint"],
+        "sandwich_attacks" => &["front_run_sig", "victim_sig", "back_run_sig"],
+        "accounts" => &["signature", "account", "open_tx", "init_tx", "close_tx"],
+        "bonding_curve_completions" => &["mint"],
+        "parse_stats" => &["signature"],
+        "rewards" => &["signature", "mint", "authority", "amount"],
+        "swap_hops" => &[
+            "signature",
+            "hop_index",
+            "amm",
+            "input_mint",
+            "output_mint",
+            "input_amount",
+            "output_amount",
+        ],
+        "token_metadata_updates" => &["signature", "mint"],
+        _ => &[],
+    }
+}
+
+/// the traded (non quote) token of a swap
+fn get_swap_token(swap: &SwapInfo) -> String {
+    match swap.swap_type {
+        SwapType::Buy => swap.token_out.clone(),
+        SwapType::Sell => swap.token_in.clone(),
+        SwapType::Token => "".to_string(),
+        // round trip: token_in == token_out
+        SwapType::Arbitrage => swap.token_in.clone(),
+    }
+}
+
+fn swap_from_row(row: &Row) -> Result<SwapInfo> {
+    let dex_type_str: String = row.get(5)?;
+    let swap_type_str: String = row.get(6)?;
+    Ok(SwapInfo {
+        slot: row.get(0)?,
+        block_time: row.get(1)?,
+        signer: row.get(2)?,
+        signature: row.get(3)?,
+        error: row.get(4)?,
+        dex: DexType::from_db(&dex_type_str).unwrap(),
+        swap_type: SwapType::from_db(&swap_type_str).unwrap(),
+        amount_in: row.get(7)?,
+        token_in: row.get(8)?,
+        amount_out: row.get(9)?,
+        token_out: row.get(10)?,
+        routing_path: vec![],
+    })
+}
+
+const WALLET_SWAP_SUMMARY_COLUMNS: &str = "
+        COUNT(*) FILTER (WHERE swap_type = 'Buy'),
+        COUNT(*) FILTER (WHERE swap_type = 'Sell'),
+        COALESCE(SUM(amount_in) FILTER (WHERE swap_type = 'Buy'), 0)::DOUBLE,
+        COALESCE(SUM(amount_out) FILTER (WHERE swap_type = 'Sell'), 0)::DOUBLE,
+        COUNT(DISTINCT token_out) FILTER (WHERE swap_type = 'Buy'),
+        COUNT(DISTINCT token_in) FILTER (WHERE swap_type = 'Sell'),
+        MIN(slot),
+        MAX(slot)";
+
+/// maps the columns of WALLET_SWAP_SUMMARY_COLUMNS starting at index 1
+fn wallet_swap_summary_from_row(signer: String, row: &Row) -> Result<WalletSwapSummary> {
+    Ok(WalletSwapSummary {
+        signer,
+        buy_count: row.get(1)?,
+        sell_count: row.get(2)?,
+        total_sol_spent: row.get(3)?,
+        total_sol_received: row.get(4)?,
+        unique_tokens_bought: row.get(5)?,
+        unique_tokens_sold: row.get(6)?,
+        first_trade_slot: row.get(7)?,
+        last_trade_slot: row.get(8)?,
+    })
+}
+
+const TOKEN_LAUNCH_STATS_QUERY: &str = "
+      SELECT
+        t.mint,
+        t.create_block_time,
+        s.first_trade_block_time,
+        s.first_trade_slot,
+        s.first_buyer,
+        s.first_trade_slot - t.create_slot AS slots_to_first_trade,
+        s.first_trade_block_time - t.create_block_time AS seconds_to_first_trade
+      FROM tokens t
+      LEFT JOIN (
+        SELECT
+          token,
+          MIN(block_time)::BIGINT AS first_trade_block_time,
+          MIN(slot) AS first_trade_slot,
+          arg_min(signer, slot) FILTER (WHERE swap_type = 'Buy') AS first_buyer
+        FROM swaps
+        GROUP BY token
+      ) s ON t.mint = s.token";
+
+fn token_launch_stats_from_row(row: &Row) -> Result<TokenLaunchStats> {
+    Ok(TokenLaunchStats {
+        mint: row.get(0)?,
+        create_block_time: row.get(1)?,
+        first_trade_block_time: row.get(2)?,
+        first_trade_slot: row.get(3)?,
+        first_buyer: row.get(4)?,
+        slots_to_first_trade: row.get(5)?,
+        seconds_to_first_trade: row.get(6)?,
+    })
+}
+
+/// slot and price of `?1` in `?2` of every successful swap of the pair
+const PAIR_PRICES_QUERY: &str = "
+      SELECT
+        slot,
+        CASE WHEN token_in = ?1 THEN amount_out / amount_in ELSE amount_in / amount_out END::DOUBLE AS price
+      FROM swaps
+      WHERE ((token_in = ?1 AND token_out = ?2) OR (token_in = ?2 AND token_out = ?1))
+        AND NOT error AND amount_in > 0 AND amount_out > 0
+";
+
+/// activity per UTC day, oldest first
+/// tables are aggregated separately: a day with swaps but without blocks is still reported
+const DAILY_SUMMARY_QUERY: &str = "
+      WITH block_days AS (
+        SELECT strftime(epoch_ms(block_time * 1000), '%Y-%m-%d') AS date, COUNT(DISTINCT slot) AS slot_count
+        FROM blocks
+        GROUP BY date
+      ),
+      tx_days AS (
+        SELECT strftime(epoch_ms(block_time * 1000), '%Y-%m-%d') AS date, COUNT(*) AS tx_count
+        FROM transactions
+        GROUP BY date
+      ),
+      swap_days AS (
+        SELECT
+          strftime(epoch_ms(block_time::BIGINT * 1000), '%Y-%m-%d') AS date,
+          COUNT(*) AS swap_count,
+          COUNT(DISTINCT signer) AS unique_traders,
+          SUM(CASE WHEN swap_type = 'Buy' THEN amount_in ELSE 0 END) AS buy_volume,
+          SUM(CASE WHEN swap_type = 'Sell' THEN amount_out ELSE 0 END) AS sell_volume
+        FROM swaps
+        WHERE NOT error
+        GROUP BY date
+      ),
+      token_days AS (
+        SELECT strftime(epoch_ms(create_block_time * 1000), '%Y-%m-%d') AS date, COUNT(*) AS new_token_count
+        FROM tokens
+        GROUP BY date
+      ),
+      days AS (
+        SELECT date FROM block_days
+        UNION SELECT date FROM tx_days
+        UNION SELECT date FROM swap_days
+        UNION SELECT date FROM token_days
+      )
+      SELECT
+        d.date,
+        COALESCE(b.slot_count, 0)::BIGINT AS slot_count,
+        COALESCE(t.tx_count, 0)::BIGINT AS tx_count,
+        COALESCE(s.swap_count, 0)::BIGINT AS swap_count,
+        COALESCE(tk.new_token_count, 0)::BIGINT AS new_token_count,
+        COALESCE(s.unique_traders, 0)::BIGINT AS unique_traders,
+        COALESCE(s.buy_volume + s.sell_volume, 0)::DOUBLE AS total_sol_volume,
+        COALESCE(s.buy_volume, 0)::DOUBLE AS total_buy_volume,
+        COALESCE(s.sell_volume, 0)::DOUBLE AS total_sell_volume
+      FROM days d
+      LEFT JOIN block_days b ON b.date = d.date
+      LEFT JOIN tx_days t ON t.date = d.date
+      LEFT JOIN swap_days s ON s.date = d.date
+      LEFT JOIN token_days tk ON tk.date = d.date
+      ORDER BY d.date";
+
+/// in- and outflows per owner, falls back to the token account or authority if the owner is unknown
+const TOKEN_HOLDER_BALANCES_QUERY: &str = "
+      WITH flows AS (
+        SELECT COALESCE(dst, to_acc) AS holder, amount::DOUBLE AS amount_in, 0 AS amount_out
+        FROM token_transfers
+        WHERE token = ?1
+        UNION ALL
+        SELECT COALESCE(src, authority, from_acc) AS holder, 0 AS amount_in, amount::DOUBLE AS amount_out
+        FROM token_transfers
+        WHERE token = ?1
+      )
+      SELECT
+        holder,
+        (SUM(amount_in) - SUM(amount_out))::DOUBLE AS net_amount,
+        SUM(amount_in)::DOUBLE AS buy_amount,
+        SUM(amount_out)::DOUBLE AS sell_amount
+      FROM flows
+      GROUP BY holder
+      HAVING SUM(amount_in) - SUM(amount_out) > 0";
+
+const TOKEN_COLUMNS: &str = "create_block_time, create_slot, create_tx, signer, factory, mint, decimals, name, symbol, uri, initial_supply, supply";
+
+/// expects the columns in TOKEN_COLUMNS order
+fn token_from_row(row: &Row) -> Result<NewToken> {
+    Ok(NewToken {
+        block_time: row.get(0)?,
+        slot: row.get(1)?,
+        signature: row.get(2)?,
+        signer: row.get(3)?,
+        factory: row.get(4)?,
+        mint: row.get(5)?,
+        decimals: row.get::<_, Option<u8>>(6)?.unwrap_or(0),
+        // metadata can be missing if the token was not created with metaplex
+        name: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+        symbol: row.get::<_, Option<String>>(8)?.unwrap_or_default(),
+        uri: row.get::<_, Option<String>>(9)?.unwrap_or_default(),
+        initial_supply: row.get(10)?,
+        supply: row.get(11)?,
+        seller_fee_basis_points: None,
+        creators: None,
+    })
+}
+
+fn get_enum_label(values: &ArrayRef, key: Option<usize>) -> Value {
+    // https://github.com/duckdb/duckdb-rs/issues/365#issuecomment-2263195641
+    // it definitely doesn't live up to the headline of "an ergonomic wrapper". lmao
+    let labels = values
+        .as_any()
+        .downcast_ref::<duckdb::arrow::array::StringArray>();
+    match (labels, key) {
+        (Some(labels), Some(key)) => json!(labels.value(key)),
+        _ => Value::Null,
+    }
+}
+
+/// buffer of the insert notification channel, slow receivers skip older notifications
+const INSERT_NOTIFICATION_BUFFER: usize = 1024;
+
+/// A row that was inserted with one of the `insert_*` methods
+#[derive(Debug, Clone)]
+pub enum InsertNotification {
+    NewBlock(ProcessedBlock),
+    NewSwap(SwapInfo),
+    NewToken(NewToken),
+    NewSolTransfer(SolTransfer),
+    NewTokenTransfer(SplTokenTransfer),
+    NewTokenMetadata(TokenMetadata),
+    NewSandwichAttack(SandwichAttack),
+    NewSupplyChange(SupplyChange),
+}
+
 pub struct SolanaDatabase {
     pub conn: Connection,
     #[allow(dead_code)]
     use_primary_keys: bool,
     no_op: bool,
     path: Option<String>,
+    insert_notifications: Option<broadcast::Sender<InsertNotification>>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -234,16 +1209,53 @@ pub enum ExportFormat {
     CSV,
 }
 
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::PARQUET | ExportFormat::PARQUET_ZSTD => "parquet",
+            ExportFormat::CSV => "csv",
+        }
+    }
+
+    fn copy_options(&self) -> &'static str {
+        match self {
+            ExportFormat::PARQUET => "FORMAT PARQUET",
+            ExportFormat::PARQUET_ZSTD => "FORMAT PARQUET, COMPRESSION ZSTD",
+            ExportFormat::CSV => "FORMAT CSV, HEADER",
+        }
+    }
+}
+
 pub enum DatabaseMode {
     InMemory,
     File,
 }
 
+/// credentials of DuckDB's httpfs extension for s3:// paths
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct S3Config {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub region: String,
+    /// S3 compatible storage, e.g. "localhost:9000"
+    pub endpoint: Option<String>,
+}
+
 pub struct DatabaseConfig {
     pub path: Option<String>,
     pub mode: DatabaseMode,
     pub with_primary_keys: bool,
-    pub enable_s3: bool,
+    /// enables s3:// paths in exports and imports
+    pub s3: Option<S3Config>,
+    /// bulk loads are faster without indexes: skip and call `create_default_indexes` after loading
+    pub create_indexes_on_init: bool,
+    /// creates the views of `create_analytics_views`
+    pub with_analytics_views: bool,
+    /// see `set_wal_mode`
+    pub wal_mode: bool,
+    pub memory_limit_gb: Option<f64>,
+    /// DuckDB uses all cores by default
+    pub threads: Option<u32>,
 }
 
 impl SolanaDatabase {
@@ -255,6 +1267,7 @@ impl SolanaDatabase {
             no_op: false,
             path: None,
             use_primary_keys: true,
+            insert_notifications: None,
         })
     }
 
@@ -270,9 +1283,23 @@ impl SolanaDatabase {
             no_op: false,
             path: config.path,
             use_primary_keys: config.with_primary_keys,
+            insert_notifications: None,
         };
-        if config.enable_s3 {
-            db.enable_s3();
+        if let Some(s3_config) = &config.s3 {
+            db.enable_s3(s3_config)?;
+        }
+        if config.create_indexes_on_init {
+            db.create_default_indexes()?;
+        }
+        if config.with_analytics_views {
+            db.create_analytics_views()?;
+        }
+        db.set_wal_mode(config.wal_mode)?;
+        if let Some(limit_gb) = config.memory_limit_gb {
+            db.set_memory_limit(limit_gb)?;
+        }
+        if let Some(threads) = config.threads {
+            db.set_threads(threads)?;
         }
         Ok(db)
     }
@@ -284,6 +1311,7 @@ impl SolanaDatabase {
             no_op: false,
             path: None,
             use_primary_keys: with_primary_keys,
+            insert_notifications: None,
         })
     }
 
@@ -294,6 +1322,7 @@ impl SolanaDatabase {
             no_op: false,
             path: Some(file_path.to_string()),
             use_primary_keys: true,
+            insert_notifications: None,
         })
     }
 
@@ -307,6 +1336,19 @@ impl SolanaDatabase {
             no_op: false,
             path: Some(file_path.to_string()),
             use_primary_keys: with_primary_keys,
+            insert_notifications: None,
+        })
+    }
+
+    /// opens a database file that was created before, without creating the schema
+    pub fn open_existing(file_path: &str) -> Result<SolanaDatabase> {
+        let conn = Connection::open(file_path)?;
+        Ok(SolanaDatabase {
+            conn,
+            no_op: false,
+            path: Some(file_path.to_string()),
+            use_primary_keys: true,
+            insert_notifications: None,
         })
     }
 
@@ -317,12 +1359,40 @@ impl SolanaDatabase {
             no_op: false,
             path: None,
             use_primary_keys: true,
+            insert_notifications: None,
+        }
+    }
+
+    /// Sends every row inserted with the `insert_*` methods to a broadcast channel.
+    /// Each row is cloned into the channel, which adds latency to bulk inserts proportional to the channel load.
+    /// Upserts don't send notifications
+    pub fn enable_insert_notifications(&mut self) {
+        if self.insert_notifications.is_none() {
+            let (sender, _) = broadcast::channel(INSERT_NOTIFICATION_BUFFER);
+            self.insert_notifications = Some(sender);
+        }
+    }
+
+    /// None if `enable_insert_notifications` wasn't called
+    pub fn subscribe_inserts(&self) -> Option<broadcast::Receiver<InsertNotification>> {
+        self.insert_notifications
+            .as_ref()
+            .map(|sender| sender.subscribe())
+    }
+
+    fn notify_inserts<T: Clone>(&self, rows: &[&T], notification: fn(T) -> InsertNotification) {
+        let Some(sender) = &self.insert_notifications else {
+            return;
+        };
+        for row in rows {
+            // fails if nobody is subscribed
+            let _ = sender.send(notification((*row).clone()));
         }
     }
 
-    pub fn enable_s3(&mut self) {
+    pub fn enable_s3(&mut self, config: &S3Config) -> Result<()> {
         let conn = &self.conn;
-        conn.execute_batch("INSTALL httpfs; LOAD httpfs;").unwrap();
+        conn.execute_batch("INSTALL httpfs; LOAD httpfs;")?;
 
         conn.execute_batch(
             format!(
@@ -331,13 +1401,30 @@ impl SolanaDatabase {
       SET s3_secret_access_key='{}';
       SET s3_region='{}';
     ",
-                "",
-                "",
-                "" // FIXME get from config
+                config.access_key_id, config.secret_access_key, config.region
             )
             .as_str(),
-        )
-        .unwrap();
+        )?;
+        if let Some(endpoint) = &config.endpoint {
+            conn.execute_batch(&format!("SET s3_endpoint='{}';", endpoint))?;
+        }
+        Ok(())
+    }
+
+    /// Fails if the bucket doesn't exist or the credentials of `enable_s3` can't list it
+    pub fn check_s3_bucket(&self, bucket: &str) -> Result<()> {
+        let query = format!("SELECT COUNT(*) FROM glob('s3://{}/*')", bucket);
+        self.conn
+            .query_row(&query, [], |row| row.get::<_, i64>(0))?;
+        Ok(())
+    }
+
+    /// Size in bytes of a local or remote (s3://, https://) file
+    pub fn get_file_size(&self, path: &str) -> Result<i64> {
+        self.conn
+            .query_row("SELECT size FROM read_blob(?1)", params![path], |row| {
+                row.get(0)
+            })
     }
 
     pub fn set_no_op(&mut self, no_op: bool) {
@@ -378,6 +1465,27 @@ impl SolanaDatabase {
         }
     }
 
+    /// DuckDB always writes commits to a write-ahead log, there is no `PRAGMA wal_mode`.
+    /// Enabled, the log is merged into the database file after 16MB (DuckDB's default).
+    /// Disabled, every commit is checkpointed so the database file is always complete.
+    /// Connections of the same database (`Connection::try_clone`) read while another one writes,
+    /// other processes can't open the file while it's opened for writing
+    pub fn set_wal_mode(&mut self, enabled: bool) -> Result<()> {
+        let threshold = if enabled { "16MB" } else { "0KB" };
+        self.conn
+            .execute_batch(&format!("SET checkpoint_threshold = '{}';", threshold))
+    }
+
+    pub fn set_memory_limit(&mut self, limit_gb: f64) -> Result<()> {
+        self.conn
+            .execute_batch(&format!("SET memory_limit = '{}GB';", limit_gb))
+    }
+
+    /// threads of DuckDB's parallel query execution
+    pub fn set_threads(&mut self, n: u32) -> Result<()> {
+        self.conn.execute_batch(&format!("SET threads = {};", n))
+    }
+
     pub fn get_setting<T>(&self, setting: &str) -> Result<T>
     where
         T: std::str::FromStr,
@@ -464,6 +1572,8 @@ impl SolanaDatabase {
                     duckdb::types::ValueRef::TinyInt(v) => json!(v),
                     duckdb::types::ValueRef::SmallInt(v) => json!(v),
                     duckdb::types::ValueRef::BigInt(v) => json!(v),
+                    // i128 doesn't fit into json numbers without precision loss
+                    duckdb::types::ValueRef::HugeInt(v) => json!(v.to_string()),
                     duckdb::types::ValueRef::Float(v) => json!(v),
                     duckdb::types::ValueRef::Double(v) => json!(v),
                     duckdb::types::ValueRef::Text(v) => {
@@ -533,16 +1643,11 @@ impl SolanaDatabase {
                         }
                     }
                     duckdb::types::ValueRef::Enum(v, idx) => {
+                        // duckdb picks the smallest key type that fits the number of enum values
                         match v {
-                            EnumType::UInt8(dict) => {
-                                let values = dict.values();
-                                let key = dict.key(idx).unwrap();
-                                // https://github.com/duckdb/duckdb-rs/issues/365#issuecomment-2263195641
-                                // it definitely doesn't live up to the headline of "an ergonomic wrapper". lmao
-                                let val = values.as_any().downcast_ref::<duckdb::arrow::array::StringArray>().unwrap().value(key);
-                                json!(val)
-                            }
-                            _ => json!("unsupported dictionary type"),
+                            EnumType::UInt8(dict) => get_enum_label(dict.values(), dict.key(idx)),
+                            EnumType::UInt16(dict) => get_enum_label(dict.values(), dict.key(idx)),
+                            EnumType::UInt32(dict) => get_enum_label(dict.values(), dict.key(idx)),
                         }
                     }
                     _ => {
@@ -562,11 +1667,389 @@ impl SolanaDatabase {
         Ok(results)
     }
 
-    pub fn get_block_time(&self, slot: u64) -> Result<i64> {
-        let query = format!("SELECT block_time FROM blocks WHERE slot = {}", slot);
-        let mut stmt = self.conn.prepare(&query)?;
-        let block_time: i64 = stmt.query_row([], |row| row.get(0))?;
-        Ok(block_time)
+    /// Returns page `page` (0-based) of the query results.
+    /// The query should have a stable ORDER BY, otherwise rows can shift between pages.
+    pub fn query_paginated(&self, query: &str, page: u64, page_size: u64) -> Result<PagedResult> {
+        let page_size = page_size.max(1);
+
+        let count_query = format!("SELECT COUNT(*) FROM ({})", query);
+        let total_rows: u64 = self.conn.query_row(&count_query, [], |row| row.get(0))?;
+        let total_pages = total_rows.div_ceil(page_size);
+
+        let page_query = format!(
+            "SELECT * FROM ({}) LIMIT {} OFFSET {}",
+            query,
+            page_size,
+            page * page_size
+        );
+        let rows = self.query_to_json_parsed(&page_query)?;
+
+        Ok(PagedResult {
+            rows,
+            page,
+            total_pages,
+            total_rows,
+        })
+    }
+
+    pub fn create_index(&self, table: &str, columns: &[&str], unique: bool) -> Result<()> {
+        let index_name = format!("idx_{}_{}", table, columns.join("_"));
+        let query = format!(
+            "CREATE {}INDEX IF NOT EXISTS {} ON {} ({})",
+            if unique { "UNIQUE " } else { "" },
+            index_name,
+            table,
+            columns.join(", ")
+        );
+        self.conn.execute_batch(&query)
+    }
+
+    /// indexes on columns that are used in most filters
+    pub fn create_default_indexes(&self) -> Result<()> {
+        self.create_index("swaps", &["signer"], false)?;
+        self.create_index("swaps", &["token"], false)?;
+        self.create_index("swaps", &["block_time"], false)?;
+        self.create_index("swaps", &["dex"], false)?;
+        self.create_index("token_transfers", &["token"], false)?;
+        self.create_index("sol_transfers", &["src"], false)?;
+        self.create_index("sol_transfers", &["dst"], false)?;
+        self.create_index("transactions", &["signer"], false)?;
+        Ok(())
+    }
+
+    /// views with common aggregations on swaps, tokens and fees.
+    /// SOL volume only includes Buy and Sell swaps.
+    pub fn create_analytics_views(&self) -> Result<()> {
+        self.conn.execute_batch(
+            "
+      CREATE OR REPLACE VIEW token_volume AS
+      SELECT
+        token,
+        date_trunc('hour', to_timestamp(block_time)) AS hour,
+        COUNT(*) AS trade_count,
+        SUM(CASE WHEN swap_type = 'Buy' THEN amount_in ELSE amount_out END)::DOUBLE AS volume_sol,
+        SUM(CASE WHEN swap_type = 'Buy' THEN amount_out ELSE amount_in END)::DOUBLE AS volume_token
+      FROM swaps
+      WHERE swap_type IN ('Buy', 'Sell')
+      GROUP BY token, hour;
+
+      CREATE OR REPLACE VIEW wallet_activity AS
+      SELECT
+        signer,
+        date_trunc('day', to_timestamp(block_time)) AS day,
+        COUNT(*) AS trade_count,
+        SUM(CASE
+          WHEN swap_type = 'Buy' THEN amount_in
+          WHEN swap_type = 'Sell' THEN amount_out
+          ELSE 0
+        END)::DOUBLE AS volume_sol
+      FROM swaps
+      GROUP BY signer, day;
+
+      CREATE OR REPLACE VIEW dex_share AS
+      SELECT
+        dex,
+        day,
+        trade_count,
+        volume_sol,
+        trade_count / SUM(trade_count) OVER (PARTITION BY day) AS trade_share
+      FROM (
+        SELECT
+          dex,
+          date_trunc('day', to_timestamp(block_time)) AS day,
+          COUNT(*)::DOUBLE AS trade_count,
+          SUM(CASE
+            WHEN swap_type = 'Buy' THEN amount_in
+            WHEN swap_type = 'Sell' THEN amount_out
+            ELSE 0
+          END)::DOUBLE AS volume_sol
+        FROM swaps
+        GROUP BY dex, day
+      );
+
+      -- initial price: SOL per token of the first Buy or Sell
+      CREATE OR REPLACE VIEW new_token_launches AS
+      SELECT
+        t.mint,
+        t.name,
+        t.symbol,
+        t.factory,
+        t.create_slot,
+        t.create_block_time,
+        s.first_swap_block_time,
+        s.first_swap_block_time - t.create_block_time AS seconds_to_first_swap,
+        s.initial_price_sol
+      FROM tokens t
+      LEFT JOIN (
+        SELECT
+          token,
+          MIN(block_time)::BIGINT AS first_swap_block_time,
+          arg_min(
+            CASE
+              WHEN swap_type = 'Buy' THEN amount_in::DOUBLE / amount_out
+              ELSE amount_out::DOUBLE / amount_in
+            END,
+            slot
+          ) AS initial_price_sol
+        FROM swaps
+        WHERE swap_type IN ('Buy', 'Sell')
+        GROUP BY token
+      ) s ON t.mint = s.token;
+
+      -- priority_fee is in micro-lamports per CU
+      CREATE OR REPLACE VIEW fee_overview AS
+      SELECT
+        slot,
+        COUNT(*) AS tx_count,
+        AVG(priority_fee)::DOUBLE AS avg_priority_fee,
+        MAX(priority_fee)::DOUBLE AS max_priority_fee
+      FROM fees
+      GROUP BY slot;
+
+      -- share of the requested compute unit limit that was consumed
+      CREATE OR REPLACE VIEW compute_efficiency AS
+      SELECT
+        f.slot,
+        f.signature,
+        f.compute_unit_limit,
+        t.compute_units AS compute_units_consumed,
+        100.0 * t.compute_units::DOUBLE / f.compute_unit_limit AS efficiency_pct,
+        f.compute_unit_limit - t.compute_units AS wasted_cu
+      FROM fees f
+      JOIN transactions t ON f.signature = t.signature
+      WHERE f.compute_unit_limit > 0;
+      ",
+        )
+    }
+
+    pub fn drop_analytics_views(&self) -> Result<()> {
+        self.conn.execute_batch(
+            "
+      DROP VIEW IF EXISTS token_volume;
+      DROP VIEW IF EXISTS wallet_activity;
+      DROP VIEW IF EXISTS dex_share;
+      DROP VIEW IF EXISTS new_token_launches;
+      DROP VIEW IF EXISTS fee_overview;
+      DROP VIEW IF EXISTS compute_efficiency;
+      ",
+        )
+    }
+
+    pub fn export_table(&self, table: &str, path: &str, format: ExportFormat) -> Result<()> {
+        self.export_query(&format!("SELECT * FROM {}", table), path, format)
+    }
+
+    /// writes the results of `query` to `path` using DuckDB's COPY
+    pub fn export_query(&self, query: &str, path: &str, format: ExportFormat) -> Result<()> {
+        let copy_query = format!("COPY ({}) TO '{}' ({})", query, path, format.copy_options());
+        self.conn.execute_batch(&copy_query)
+    }
+
+    /// Slots, transactions, swaps, new tokens and SOL volume per UTC day, oldest first
+    pub fn aggregate_to_daily_summary(&self) -> Result<Vec<DailySummary>> {
+        let mut stmt = self.conn.prepare(DAILY_SUMMARY_QUERY)?;
+        let summaries = stmt.query_map([], |row| {
+            Ok(DailySummary {
+                date: row.get(0)?,
+                slot_count: row.get(1)?,
+                tx_count: row.get(2)?,
+                swap_count: row.get(3)?,
+                new_token_count: row.get(4)?,
+                unique_traders: row.get(5)?,
+                total_sol_volume: row.get(6)?,
+                total_buy_volume: row.get(7)?,
+                total_sell_volume: row.get(8)?,
+            })
+        })?;
+        summaries.collect()
+    }
+
+    /// Writes `aggregate_to_daily_summary` to a CSV file with header.
+    /// Only aggregates, no signatures or wallets
+    pub fn write_daily_summaries_to_csv(&self, path: &str) -> Result<()> {
+        self.export_query(DAILY_SUMMARY_QUERY, path, ExportFormat::CSV)
+    }
+
+    /// Writes `table` to a single file or, with `partition_by`, to a Hive partitioned directory.
+    /// `block_time` is partitioned by day (`day=YYYY-MM-DD/`), other columns by value.
+    pub fn export_to_parquet(
+        &self,
+        table: &str,
+        output_path: &str,
+        format: ExportFormat,
+        partition_by: Option<&str>,
+    ) -> Result<()> {
+        let Some(column) = partition_by else {
+            return self.export_table(table, output_path, format);
+        };
+        let (query, partition_column) = match column {
+            "block_time" => (
+                format!(
+                    "SELECT *, strftime(to_timestamp(block_time), '%Y-%m-%d') AS day FROM {}",
+                    table
+                ),
+                "day",
+            ),
+            column => (format!("SELECT * FROM {}", table), column),
+        };
+        let copy_query = format!(
+            "COPY ({}) TO '{}' ({}, PARTITION_BY ({}), OVERWRITE_OR_IGNORE)",
+            query,
+            output_path,
+            format.copy_options(),
+            partition_column
+        );
+        self.conn.execute_batch(&copy_query)
+    }
+
+    /// Exports every non-empty table to `{output_dir}/{table}.{extension}`.
+    /// Returns the exported row count per table.
+    pub fn export_all_tables(
+        &self,
+        output_dir: &str,
+        format: ExportFormat,
+    ) -> Result<HashMap<String, usize>> {
+        std::fs::create_dir_all(output_dir)
+            .map_err(|err| duckdb::Error::ToSqlConversionFailure(Box::new(err)))?;
+        let mut exported = HashMap::new();
+        for table in self.list_tables()? {
+            let count = self.count_rows(&table)?;
+            if count == 0 {
+                continue;
+            }
+            let path = format!("{}/{}.{}", output_dir, table, format.extension());
+            self.export_table(&table, &path, format.clone())?;
+            exported.insert(table, count as usize);
+        }
+        Ok(exported)
+    }
+
+    /// Writes schema and data to the `target_path` directory with DuckDB's EXPORT DATABASE.
+    /// Unlike the parquet exports this keeps DuckDB types (ENUM, HUGEINT) and sequences
+    pub fn backup(&self, target_path: &str) -> Result<()> {
+        self.conn
+            .execute_batch(&format!("EXPORT DATABASE '{}';", target_path))
+    }
+
+    /// Writes all tables to a single DuckDB file at `output_path`, e.g. to share an in-memory
+    /// database or to open it with `duckdb.connect(output_path)` in Python.
+    /// The file must not contain any of the tables yet. Indexes and primary keys are not copied.
+    pub fn export_to_duckdb_file(&self, output_path: &str) -> Result<()> {
+        let tables = self.list_tables()?;
+        self.conn
+            .execute_batch(&format!("ATTACH '{}' AS export_target;", output_path))?;
+
+        let mut result = Ok(());
+        for table in tables {
+            let query = format!(
+                "CREATE TABLE export_target.{} AS SELECT * FROM {}",
+                table, table
+            );
+            if let Err(err) = self.conn.execute(&query, []) {
+                result = Err(err);
+                break;
+            }
+        }
+
+        self.conn.execute_batch("DETACH export_target;")?;
+        result
+    }
+
+    /// Creates an in-memory database from a directory written by `backup`
+    pub fn restore(source_path: &str) -> Result<SolanaDatabase> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(&format!("IMPORT DATABASE '{}';", source_path))?;
+        Ok(SolanaDatabase::new_from_connection(conn))
+    }
+
+    pub fn get_block_time(&self, slot: u64) -> Result<i64> {
+        let query = format!("SELECT block_time FROM blocks WHERE slot = {}", slot);
+        let mut stmt = self.conn.prepare(&query)?;
+        let block_time: i64 = stmt.query_row([], |row| row.get(0))?;
+        Ok(block_time)
+    }
+
+    pub fn has_table(&self, table: &str) -> Result<bool> {
+        self.conn.query_row(
+            "
+      SELECT COUNT(*) > 0
+      FROM information_schema.tables
+      WHERE table_catalog = current_database() AND table_schema = 'main'
+        AND table_type = 'BASE TABLE' AND table_name = ?1
+      ",
+            params![table],
+            |row| row.get(0),
+        )
+    }
+
+    /// User tables sorted by name, views are not included
+    pub fn list_tables(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT table_name
+      FROM information_schema.tables
+      WHERE table_catalog = current_database() AND table_schema = 'main'
+        AND table_type = 'BASE TABLE'
+      ORDER BY table_name
+      ",
+        )?;
+        let tables_iter = stmt.query_map([], |row| row.get(0))?;
+        tables_iter.collect()
+    }
+
+    pub fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        self.conn.query_row(
+            "
+      SELECT COUNT(*) > 0
+      FROM information_schema.columns
+      WHERE table_catalog = current_database() AND table_schema = 'main'
+        AND table_name = ?1 AND column_name = ?2
+      ",
+            params![table, column],
+            |row| row.get(0),
+        )
+    }
+
+    /// Runs the migrations in order that are neither recorded in `schema_migrations` nor part of the schema.
+    /// Migrations found by their `check_sql` are recorded without running them.
+    /// Returns the names of the executed migrations
+    pub fn migrate_schema(&mut self, migrations: &[Migration]) -> Result<Vec<String>> {
+        self.conn.execute_batch(
+            "
+      CREATE TABLE IF NOT EXISTS schema_migrations (
+        version TEXT PRIMARY KEY,
+        applied_at TIMESTAMP DEFAULT current_timestamp
+      );
+      ",
+        )?;
+
+        let mut executed = vec![];
+        for migration in migrations {
+            let is_recorded: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM schema_migrations WHERE version = ?1",
+                params![migration.name],
+                |row| row.get(0),
+            )?;
+            if is_recorded {
+                continue;
+            }
+            let is_applied: i64 = self
+                .conn
+                .query_row(&migration.check_sql, [], |row| row.get(0))?;
+            self.with_transaction(|db| {
+                if is_applied == 0 {
+                    db.conn.execute_batch(&migration.up_sql)?;
+                }
+                db.conn.execute(
+                    "INSERT INTO schema_migrations (version) VALUES (?1)",
+                    params![migration.name],
+                )
+            })?;
+            if is_applied == 0 {
+                executed.push(migration.name.clone());
+            }
+        }
+        Ok(executed)
     }
 
     pub fn count_rows(&self, table: &str) -> Result<i64> {
@@ -604,14 +2087,109 @@ impl SolanaDatabase {
         Ok(max)
     }
 
+    /// reclaims space of deleted rows
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM;")
+    }
+
+    /// updates the table statistics used by the query planner
+    pub fn analyze(&self) -> Result<()> {
+        self.conn.execute_batch("ANALYZE;")
+    }
+
+    pub fn get_table_stats(&self) -> Result<Vec<TableStats>> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT
+        t.table_name,
+        t.estimated_size::BIGINT AS row_count,
+        (t.estimated_size * SUM(
+          CASE c.data_type
+            WHEN 'BOOLEAN' THEN 1
+            WHEN 'INTEGER' THEN 4
+            WHEN 'FLOAT' THEN 4
+            WHEN 'BIGINT' THEN 8
+            WHEN 'DOUBLE' THEN 8
+            WHEN 'HUGEINT' THEN 16
+            ELSE 16
+          END
+        ))::BIGINT AS estimated_size_bytes
+      FROM duckdb_tables() t
+      JOIN duckdb_columns() c
+        ON t.database_name = c.database_name
+        AND t.schema_name = c.schema_name
+        AND t.table_name = c.table_name
+      WHERE t.database_name = current_database()
+      GROUP BY t.table_name, t.estimated_size
+      ORDER BY t.table_name
+      ",
+        )?;
+        let stats = stmt.query_map([], |row| {
+            Ok(TableStats {
+                table_name: row.get(0)?,
+                row_count: row.get(1)?,
+                estimated_size_bytes: row.get(2)?,
+            })
+        })?;
+        stats.collect()
+    }
+
+    /// size of the database file or the memory used by in-memory databases
+    pub fn get_database_size_bytes(&self) -> Result<u64> {
+        if let Some(path) = &self.path {
+            if let Ok(metadata) = std::fs::metadata(path) {
+                return Ok(metadata.len());
+            }
+        }
+        self.conn.query_row(
+            "SELECT COALESCE(SUM(memory_usage_bytes), 0)::UBIGINT FROM duckdb_memory()",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn begin_transaction(&mut self) -> Result<()> {
+        self.conn.execute_batch("BEGIN TRANSACTION")
+    }
+
+    pub fn commit_transaction(&mut self) -> Result<()> {
+        self.conn.execute_batch("COMMIT")
+    }
+
+    pub fn rollback_transaction(&mut self) -> Result<()> {
+        self.conn.execute_batch("ROLLBACK")
+    }
+
+    /// Runs `f` in a transaction: commits if it returns Ok, rolls back otherwise.
+    /// Appenders flush when they are dropped so all bulk inserts inside `f` are covered.
+    pub fn with_transaction<T, E, F>(&mut self, f: F) -> std::result::Result<T, E>
+    where
+        F: FnOnce(&mut SolanaDatabase) -> std::result::Result<T, E>,
+        E: From<duckdb::Error>,
+    {
+        self.begin_transaction()?;
+        match f(self) {
+            Ok(result) => {
+                self.commit_transaction()?;
+                Ok(result)
+            }
+            Err(err) => {
+                self.rollback_transaction()?;
+                Err(err)
+            }
+        }
+    }
+
     pub fn insert_block(&mut self, block: &ProcessedBlock) -> Result<usize> {
         if self.no_op {
             return Ok(0);
         }
-        self.conn.execute(
-            "INSERT INTO blocks (slot, block_time, parent_slot, transaction_count) VALUES (?1, ?2, ?3, ?4)",
-            params![block.slot, block.block_time, block.parent_slot, block.transaction_count],
-        )
+        let inserted = self.conn.execute(
+            "INSERT INTO blocks (slot, block_time, parent_slot, transaction_count, blockhash, leader, rewards) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![block.slot, block.block_time, block.parent_slot, block.transaction_count, block.blockhash, block.leader, block.rewards],
+        )?;
+        self.notify_inserts(&[block], InsertNotification::NewBlock);
+        Ok(inserted)
     }
 
     pub fn insert_transactions_bulk(
@@ -637,7 +2215,8 @@ impl SolanaDatabase {
                 transaction
                     .data
                     .as_ref()
-                    .map(|data| serde_json::to_string(data).unwrap())
+                    .map(|data| serde_json::to_string(data).unwrap()),
+                transaction.jito_tip_lamports
             ])?;
         }
         Ok(0)
@@ -657,6 +2236,7 @@ impl SolanaDatabase {
                 transfer.sol
             ])?;
         }
+        self.notify_inserts(transfers, InsertNotification::NewSolTransfer);
         Ok(transfers.len())
     }
 
@@ -675,12 +2255,13 @@ impl SolanaDatabase {
                 transfer.to,
                 transfer.from_acc,
                 transfer.to_acc,
-                transfer.amount,
+                transfer.raw_amount.to_string(),
                 transfer.token,
                 transfer.decimals,
                 transfer.authority
             ])?;
         }
+        self.notify_inserts(transfers, InsertNotification::NewTokenTransfer);
         Ok(transfers.len())
     }
 
@@ -688,11 +2269,7 @@ impl SolanaDatabase {
         let conn = &self.conn;
         let mut appender = conn.appender("swaps")?;
         for swap in swaps {
-            let token = match swap.swap_type {
-                SwapType::Buy => swap.token_out.clone(),
-                SwapType::Sell => swap.token_in.clone(),
-                SwapType::Token => "".to_string(),
-            };
+            let token = get_swap_token(swap);
             appender.append_row(params![
                 swap.slot,
                 swap.block_time,
@@ -708,6 +2285,7 @@ impl SolanaDatabase {
                 token
             ])?;
         }
+        self.notify_inserts(swaps, InsertNotification::NewSwap);
         Ok(swaps.len())
     }
 
@@ -730,9 +2308,48 @@ impl SolanaDatabase {
                 token.uri
             ])?;
         }
+        self.notify_inserts(tokens, InsertNotification::NewToken);
         Ok(tokens.len())
     }
 
+    pub fn insert_token_metadata_bulk(&mut self, metadata: &Vec<&TokenMetadata>) -> Result<usize> {
+        let conn = &self.conn;
+        let mut appender = conn.appender("metadata")?;
+        for meta in metadata {
+            appender.append_row(params![
+                meta.mint,
+                meta.image_url,
+                meta.description,
+                meta.twitter,
+                meta.telegram
+            ])?;
+        }
+        self.notify_inserts(metadata, InsertNotification::NewTokenMetadata);
+        Ok(metadata.len())
+    }
+
+    pub fn insert_sandwich_attacks_bulk(
+        &mut self,
+        attacks: &Vec<&SandwichAttack>,
+    ) -> Result<usize> {
+        let conn = &self.conn;
+        let mut appender = conn.appender("sandwich_attacks")?;
+        for attack in attacks {
+            appender.append_row(params![
+                attack.slot,
+                attack.attacker,
+                attack.victim,
+                attack.token,
+                attack.front_run_sig,
+                attack.back_run_sig,
+                attack.victim_sig,
+                attack.estimated_profit_sol
+            ])?;
+        }
+        self.notify_inserts(attacks, InsertNotification::NewSandwichAttack);
+        Ok(attacks.len())
+    }
+
     pub fn insert_supply_changes_bulk(
         &mut self,
         supply_changes: &Vec<&SupplyChange>,
@@ -748,9 +2365,165 @@ impl SolanaDatabase {
                 supply_change.authority
             ])?;
         }
+        self.notify_inserts(supply_changes, InsertNotification::NewSupplyChange);
         Ok(supply_changes.len())
     }
 
+    pub fn insert_accounts_bulk(&mut self, accounts: &Vec<AccountProcessed>) -> Result<usize> {
+        let conn = &self.conn;
+        let mut appender = conn.appender("accounts")?;
+        for processed in accounts {
+            let account = &processed.account;
+            appender.append_row(params![
+                processed.slot,
+                processed.block_time,
+                processed.signature,
+                account.account,
+                account.owner,
+                account.mint,
+                account.decimals,
+                account.open_tx,
+                account.init_tx,
+                account.close_tx,
+                account.close_destination
+            ])?;
+        }
+        Ok(accounts.len())
+    }
+
+    pub fn insert_bonding_curve_completions_bulk(
+        &mut self,
+        completions: &Vec<&BondingCurveCompletion>,
+    ) -> Result<usize> {
+        let conn = &self.conn;
+        let mut appender = conn.appender("bonding_curve_completions")?;
+        for completion in completions {
+            appender.append_row(params![
+                completion.slot,
+                completion.block_time,
+                completion.signature,
+                completion.mint,
+                completion.bonding_curve,
+                completion.signer
+            ])?;
+        }
+        Ok(completions.len())
+    }
+
+    /// Inserts the routing paths of the swaps, amounts are stored without decimals.
+    /// Returns the number of inserted hops
+    pub fn insert_swap_hops_bulk(&mut self, swaps: &Vec<&SwapInfo>) -> Result<usize> {
+        let conn = &self.conn;
+        let mut appender = conn.appender("swap_hops")?;
+        let mut inserted = 0;
+        for swap in swaps {
+            for (hop_index, hop) in swap.routing_path.iter().enumerate() {
+                appender.append_row(params![
+                    swap.signature,
+                    hop_index as u32,
+                    hop.amm,
+                    hop.input_mint,
+                    hop.output_mint,
+                    hop.input_amount as f64,
+                    hop.output_amount as f64
+                ])?;
+                inserted += 1;
+            }
+        }
+        Ok(inserted)
+    }
+
+    /// Updates the provided fields of a token, None keeps the current value
+    pub fn upsert_token_metadata(
+        &mut self,
+        mint: &str,
+        name: Option<&str>,
+        symbol: Option<&str>,
+        uri: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "
+      UPDATE tokens
+      SET name = COALESCE(?2, name), symbol = COALESCE(?3, symbol), uri = COALESCE(?4, uri)
+      WHERE mint = ?1
+      ",
+            params![mint, name, symbol, uri],
+        )?;
+        Ok(())
+    }
+
+    /// Records the updates in `token_metadata_updates` and applies them to `tokens`.
+    /// Updates without a mint are skipped. Returns the number of recorded updates
+    pub fn insert_token_metadata_updates_bulk(
+        &mut self,
+        updates: &Vec<&MetadataUpdate>,
+    ) -> Result<usize> {
+        let mut inserted = 0;
+        for update in updates {
+            let Some(mint) = &update.mint else {
+                continue;
+            };
+            let old_values: Option<(Option<String>, Option<String>)> = self
+                .conn
+                .prepare("SELECT name, symbol FROM tokens WHERE mint = ?1 LIMIT 1")?
+                .query_map(params![mint], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .next()
+                .transpose()?;
+            let (old_name, old_symbol) = old_values.unwrap_or_default();
+            self.conn.execute(
+                "INSERT INTO token_metadata_updates VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    update.slot,
+                    update.signature,
+                    mint,
+                    old_name,
+                    update.name,
+                    old_symbol,
+                    update.symbol
+                ],
+            )?;
+            self.upsert_token_metadata(
+                mint,
+                update.name.as_deref(),
+                update.symbol.as_deref(),
+                update.uri.as_deref(),
+            )?;
+            inserted += 1;
+        }
+        Ok(inserted)
+    }
+
+    pub fn insert_rewards_bulk(&mut self, rewards: &Vec<RewardProcessed>) -> Result<usize> {
+        let conn = &self.conn;
+        let mut appender = conn.appender("rewards")?;
+        for reward in rewards {
+            appender.append_row(params![
+                reward.slot,
+                reward.block_time,
+                reward.signature,
+                reward.mint,
+                reward.amount,
+                reward.authority
+            ])?;
+        }
+        Ok(rewards.len())
+    }
+
+    pub fn insert_parse_stats_bulk(&mut self, stats: &Vec<TransactionParseStats>) -> Result<usize> {
+        let conn = &self.conn;
+        let mut appender = conn.appender("parse_stats")?;
+        for stat in stats {
+            appender.append_row(params![
+                stat.slot,
+                stat.signature,
+                stat.parse_us,
+                stat.insert_us,
+                stat.ix_count
+            ])?;
+        }
+        Ok(stats.len())
+    }
+
     pub fn insert_parsed_programs_bulk(
         &mut self,
         programs: &Vec<&ProgramParserData>,
@@ -788,55 +2561,4067 @@ impl SolanaDatabase {
         Ok(budget.len())
     }
 
-    pub fn get_swaps(&self) -> Result<Vec<SwapInfo>> {
-        let mut stmt = self.conn.prepare("SELECT slot, block_time, signer, signature, error, dex, swap_type, amount_in, token_in, amount_out, token_out FROM swaps")?;
-        let swaps_iter = stmt.query_map([], |row| {
-            let dex_type_str: String = row.get(5)?;
-            let swap_type_str: String = row.get(6)?;
-            Ok(SwapInfo {
-                slot: row.get(0)?,
-                block_time: row.get(1)?,
-                signer: row.get(2)?,
-                signature: row.get(3)?,
-                error: row.get(4)?,
-                dex: DexType::from_db(&dex_type_str).unwrap(),
-                swap_type: SwapType::from_db(&swap_type_str).unwrap(),
-                amount_in: row.get(7)?,
-                token_in: row.get(8)?,
-                amount_out: row.get(9)?,
-                token_out: row.get(10)?,
-            })
-        })?;
-        let swaps: Result<Vec<_>> = swaps_iter.collect();
-        swaps
+    /// INSERT for the given `columns` of `table`, params are bound in the same order.
+    /// Naming the columns keeps the statement valid when migrations add or reorder columns.
+    /// On tables with a primary key, conflicting rows are skipped instead of failing the whole batch.
+    /// Tables without primary key (swaps, transfers, ..) can't detect duplicates.
+    fn prepare_upsert(&self, table: &str, columns: &[&str]) -> Result<Statement<'_>> {
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let on_conflict = if self.use_primary_keys && has_primary_key(table) {
+            " ON CONFLICT DO NOTHING"
+        } else {
+            ""
+        };
+        self.conn.prepare(&format!(
+            "INSERT INTO {} ({}) VALUES ({}){}",
+            table,
+            columns.join(", "),
+            placeholders,
+            on_conflict
+        ))
     }
 
-    pub fn load_parquet_table(&self, table: &str, file_path: &str) -> Result<()> {
-        let connection = &self.conn;
-        let _ = connection.execute(
-            format!(
-                "COPY {} FROM '{}' (FORMAT 'parquet', COMPRESSION 'ZSTD');",
-                table, file_path
-            )
-            .as_str(),
-            [],
+    pub fn upsert_block(&mut self, block: &ProcessedBlock) -> Result<usize> {
+        if self.no_op {
+            return Ok(0);
+        }
+        let mut stmt = self.prepare_upsert(
+            "blocks",
+            &[
+                "slot",
+                "block_time",
+                "parent_slot",
+                "transaction_count",
+                "blockhash",
+                "leader",
+                "rewards",
+            ],
         )?;
-        Ok(())
+        stmt.execute(params![
+            block.slot,
+            block.block_time,
+            block.parent_slot,
+            block.transaction_count,
+            block.blockhash,
+            block.leader,
+            block.rewards
+        ])
     }
 
-    pub fn print_table(&self, table: &str) -> Result<()> {
-        let limit = 10;
-        self.print_table_with_limit(table, limit)?;
-        Ok(())
+    pub fn upsert_transactions_bulk(
+        &mut self,
+        transactions: &[&ProcessedTransaction],
+    ) -> Result<usize> {
+        let mut stmt = self.prepare_upsert(
+            "transactions",
+            &[
+                "slot",
+                "block_time",
+                "signature",
+                "signer",
+                "error",
+                "top_level_ix_count",
+                "inner_ix_count",
+                "compute_units",
+                "fee",
+                "version",
+                "is_discarded",
+                "discard_reason",
+                "data",
+                "jito_tip_lamports",
+            ],
+        )?;
+        let mut inserted = 0;
+        for transaction in transactions {
+            inserted += stmt.execute(params![
+                transaction.slot,
+                transaction.block_time,
+                transaction.signature,
+                transaction.signer,
+                transaction.has_error,
+                transaction.top_level_ix_count,
+                transaction.inner_ix_count,
+                transaction.compute_units_consumed,
+                transaction.fee,
+                transaction.version,
+                transaction.is_discarded,
+                transaction.discard_reason,
+                transaction
+                    .data
+                    .as_ref()
+                    .map(|data| serde_json::to_string(data).unwrap()),
+                transaction.jito_tip_lamports
+            ])?;
+        }
+        Ok(inserted)
     }
 
-    pub fn print_table_with_limit(&self, table: &str, limit: i32) -> Result<()> {
-        let query = format!("SELECT * FROM {} limit {}", table, limit);
-        let results = self.query_to_json_file(&query)?;
-        print_json_objects_as_table(&results);
-        Ok(())
+    pub fn upsert_sol_transfers_bulk(&mut self, transfers: &[&SolTransfer]) -> Result<usize> {
+        let mut stmt = self.prepare_upsert(
+            "sol_transfers",
+            &[
+                "slot",
+                "block_time",
+                "signature",
+                "src",
+                "dst",
+                "lamports",
+                "sol",
+            ],
+        )?;
+        let mut inserted = 0;
+        for transfer in transfers {
+            inserted += stmt.execute(params![
+                transfer.slot,
+                transfer.block_time,
+                transfer.signature,
+                transfer.from,
+                transfer.to,
+                transfer.lamports,
+                transfer.sol
+            ])?;
+        }
+        Ok(inserted)
     }
-}
 
-#[cfg(test)]
-mod tests {}
+    pub fn upsert_token_transfers_bulk(
+        &mut self,
+        transfers: &[&SplTokenTransfer],
+    ) -> Result<usize> {
+        let mut stmt = self.prepare_upsert(
+            "token_transfers",
+            &[
+                "slot",
+                "block_time",
+                "signature",
+                "src",
+                "dst",
+                "from_acc",
+                "to_acc",
+                "amount",
+                "token",
+                "decimals",
+                "authority",
+            ],
+        )?;
+        let mut inserted = 0;
+        for transfer in transfers {
+            inserted += stmt.execute(params![
+                transfer.slot,
+                transfer.block_time,
+                transfer.signature,
+                transfer.from,
+                transfer.to,
+                transfer.from_acc,
+                transfer.to_acc,
+                transfer.raw_amount.to_string(),
+                transfer.token,
+                transfer.decimals,
+                transfer.authority
+            ])?;
+        }
+        Ok(inserted)
+    }
+
+    pub fn upsert_swaps_bulk(&mut self, swaps: &[&SwapInfo]) -> Result<usize> {
+        let mut stmt = self.prepare_upsert(
+            "swaps",
+            &[
+                "slot",
+                "block_time",
+                "signer",
+                "signature",
+                "error",
+                "dex",
+                "swap_type",
+                "amount_in",
+                "token_in",
+                "amount_out",
+                "token_out",
+                "token",
+            ],
+        )?;
+        let mut inserted = 0;
+        for swap in swaps {
+            inserted += stmt.execute(params![
+                swap.slot,
+                swap.block_time,
+                swap.signer,
+                swap.signature,
+                swap.error,
+                swap.dex.to_db(),
+                swap.swap_type.to_db(),
+                swap.amount_in,
+                swap.token_in,
+                swap.amount_out,
+                swap.token_out,
+                get_swap_token(swap)
+            ])?;
+        }
+        Ok(inserted)
+    }
+
+    pub fn upsert_tokens_bulk(&mut self, tokens: &[&NewToken]) -> Result<usize> {
+        let mut stmt = self.prepare_upsert(
+            "tokens",
+            &[
+                "signer",
+                "mint",
+                "factory",
+                "create_tx",
+                "create_block_time",
+                "create_slot",
+                "initial_supply",
+                "supply",
+                "decimals",
+                "name",
+                "symbol",
+                "uri",
+            ],
+        )?;
+        let mut inserted = 0;
+        for token in tokens {
+            inserted += stmt.execute(params![
+                token.signer,
+                token.mint,
+                token.factory,
+                token.signature,
+                token.block_time,
+                token.slot,
+                token.initial_supply,
+                token.supply,
+                token.decimals,
+                token.name,
+                token.symbol,
+                token.uri
+            ])?;
+        }
+        Ok(inserted)
+    }
+
+    pub fn upsert_token_metadata_bulk(&mut self, metadata: &[&TokenMetadata]) -> Result<usize> {
+        let mut stmt = self.prepare_upsert(
+            "metadata",
+            &["mint", "image_url", "description", "twitter", "telegram"],
+        )?;
+        let mut inserted = 0;
+        for meta in metadata {
+            inserted += stmt.execute(params![
+                meta.mint,
+                meta.image_url,
+                meta.description,
+                meta.twitter,
+                meta.telegram
+            ])?;
+        }
+        Ok(inserted)
+    }
+
+    pub fn upsert_sandwich_attacks_bulk(&mut self, attacks: &[&SandwichAttack]) -> Result<usize> {
+        let mut stmt = self.prepare_upsert(
+            "sandwich_attacks",
+            &[
+                "slot",
+                "attacker",
+                "victim",
+                "token",
+                "front_run_sig",
+                "back_run_sig",
+                "victim_sig",
+                "estimated_profit_sol",
+            ],
+        )?;
+        let mut inserted = 0;
+        for attack in attacks {
+            inserted += stmt.execute(params![
+                attack.slot,
+                attack.attacker,
+                attack.victim,
+                attack.token,
+                attack.front_run_sig,
+                attack.back_run_sig,
+                attack.victim_sig,
+                attack.estimated_profit_sol
+            ])?;
+        }
+        Ok(inserted)
+    }
+
+    pub fn upsert_supply_changes_bulk(
+        &mut self,
+        supply_changes: &[&SupplyChange],
+    ) -> Result<usize> {
+        let mut stmt = self.prepare_upsert(
+            "supply_changes",
+            &["signature", "ix_index", "mint", "amount", "authority"],
+        )?;
+        let mut inserted = 0;
+        for supply_change in supply_changes {
+            inserted += stmt.execute(params![
+                supply_change.signature,
+                supply_change.ix_index,
+                supply_change.mint,
+                supply_change.amount,
+                supply_change.authority
+            ])?;
+        }
+        Ok(inserted)
+    }
+
+    pub fn upsert_parsed_programs_bulk(
+        &mut self,
+        programs: &[&ProgramParserData],
+    ) -> Result<usize> {
+        let mut stmt = self.prepare_upsert(
+            "tx_programs",
+            &[
+                "signature",
+                "ix_index",
+                "program_id",
+                "ix_type",
+                "can_parse",
+                "has_error",
+            ],
+        )?;
+        let mut inserted = 0;
+        for program in programs {
+            inserted += stmt.execute(params![
+                program.signature,
+                program.ix_idx,
+                program.program_id,
+                program.ix_type,
+                program.parsed,
+                program.error
+            ])?;
+        }
+        Ok(inserted)
+    }
+
+    pub fn upsert_compute_budget_bulk(
+        &mut self,
+        budget: &[&ComputeBudgetProcessed],
+    ) -> Result<usize> {
+        let mut stmt = self.prepare_upsert(
+            "fees",
+            &[
+                "slot",
+                "block_time",
+                "signature",
+                "compute_unit_limit",
+                "priority_fee",
+            ],
+        )?;
+        let mut inserted = 0;
+        for budget in budget {
+            inserted += stmt.execute(params![
+                budget.slot,
+                budget.block_time,
+                budget.signature,
+                budget.c_unit_limit,
+                budget.fee
+            ])?;
+        }
+        Ok(inserted)
+    }
+
+    pub fn get_swaps_paginated(&self, page: u64, page_size: u64) -> Result<PagedResult> {
+        self.query_paginated("SELECT * FROM swaps ORDER BY slot, rowid", page, page_size)
+    }
+
+    pub fn get_swaps(&self) -> Result<Vec<SwapInfo>> {
+        let mut stmt = self.conn.prepare("SELECT slot, block_time, signer, signature, error, dex, swap_type, amount_in, token_in, amount_out, token_out FROM swaps")?;
+        let swaps_iter = stmt.query_map([], swap_from_row)?;
+        let swaps: Result<Vec<_>> = swaps_iter.collect();
+        swaps
+    }
+
+    pub fn get_swaps_filtered(&self, filter: &SwapFilter) -> Result<Vec<SwapInfo>> {
+        // all values are bound as parameters, only the static clauses are part of the query
+        let mut clauses: Vec<&str> = vec![];
+        let mut values: Vec<Box<dyn duckdb::ToSql>> = vec![];
+        if let Some(dex) = &filter.dex {
+            clauses.push("dex::TEXT = ?");
+            values.push(Box::new(dex.to_db().to_string()));
+        }
+        if let Some(swap_type) = &filter.swap_type {
+            clauses.push("swap_type::TEXT = ?");
+            values.push(Box::new(swap_type.to_db().to_string()));
+        }
+        if let Some(signer) = &filter.signer {
+            clauses.push("signer = ?");
+            values.push(Box::new(signer.clone()));
+        }
+        if let Some(token) = &filter.token {
+            clauses.push("token = ?");
+            values.push(Box::new(token.clone()));
+        }
+        if let Some(slot_start) = filter.slot_start {
+            clauses.push("slot >= ?");
+            values.push(Box::new(slot_start));
+        }
+        if let Some(slot_end) = filter.slot_end {
+            clauses.push("slot <= ?");
+            values.push(Box::new(slot_end));
+        }
+        if let Some(min_amount_in) = filter.min_amount_in {
+            clauses.push("amount_in >= ?");
+            values.push(Box::new(min_amount_in));
+        }
+
+        let mut query = "SELECT slot, block_time, signer, signature, error, dex, swap_type, amount_in, token_in, amount_out, token_out FROM swaps".to_string();
+        if !clauses.is_empty() {
+            query.push_str(&format!(" WHERE {}", clauses.join(" AND ")));
+        }
+        query.push_str(" ORDER BY slot, rowid");
+        if let Some(limit) = filter.limit {
+            query.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let mut stmt = self.conn.prepare(&query)?;
+        let params: Vec<&dyn duckdb::ToSql> = values.iter().map(|v| v.as_ref()).collect();
+        let swaps_iter = stmt.query_map(params.as_slice(), swap_from_row)?;
+        swaps_iter.collect()
+    }
+
+    /// Tokens ordered by creation, optionally only from one factory program and created at or after `since_block_time`
+    pub fn get_new_tokens(
+        &self,
+        factory: Option<&str>,
+        since_block_time: Option<i64>,
+    ) -> Result<Vec<NewToken>> {
+        let mut clauses: Vec<&str> = vec![];
+        let mut values: Vec<Box<dyn duckdb::ToSql>> = vec![];
+        if let Some(factory) = factory {
+            clauses.push("factory = ?");
+            values.push(Box::new(factory.to_string()));
+        }
+        if let Some(since_block_time) = since_block_time {
+            clauses.push("create_block_time >= ?");
+            values.push(Box::new(since_block_time));
+        }
+
+        let mut query = format!("SELECT {} FROM tokens", TOKEN_COLUMNS);
+        if !clauses.is_empty() {
+            query.push_str(&format!(" WHERE {}", clauses.join(" AND ")));
+        }
+        query.push_str(" ORDER BY create_slot, rowid");
+
+        let mut stmt = self.conn.prepare(&query)?;
+        let params: Vec<&dyn duckdb::ToSql> = values.iter().map(|v| v.as_ref()).collect();
+        let tokens_iter = stmt.query_map(params.as_slice(), token_from_row)?;
+        tokens_iter.collect()
+    }
+
+    pub fn get_block_info(&self, slot: u64) -> Result<Option<BlockInfo>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT slot, block_time, blockhash, leader, rewards FROM blocks WHERE slot = ?1",
+        )?;
+        let mut blocks = stmt.query_map(params![slot], |row| {
+            Ok(BlockInfo {
+                slot: row.get(0)?,
+                block_time: row.get(1)?,
+                blockhash: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                leader: row.get(3)?,
+                rewards: row.get(4)?,
+            })
+        })?;
+        blocks.next().transpose()
+    }
+
+    pub fn get_block_transactions(&self, slot: u64) -> Result<Vec<StoredTransaction>> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT slot, block_time, signature, signer, error, compute_units, fee, version,
+        is_discarded, discard_reason
+      FROM transactions
+      WHERE slot = ?1
+      ORDER BY signature
+      ",
+        )?;
+        let transactions = stmt.query_map(params![slot], |row| {
+            Ok(StoredTransaction {
+                slot: row.get(0)?,
+                block_time: row.get(1)?,
+                signature: row.get(2)?,
+                signer: row.get(3)?,
+                has_error: row.get(4)?,
+                compute_units: row.get(5)?,
+                fee: row.get(6)?,
+                version: row.get(7)?,
+                is_discarded: row.get(8)?,
+                discard_reason: row.get(9)?,
+            })
+        })?;
+        transactions.collect()
+    }
+
+    /// The stored `EncodedTransactionWithStatusMeta` of a transaction.
+    /// `None` if the transaction is unknown or was stored without data
+    pub fn get_transaction_json(&self, signature: &str) -> Result<Option<Value>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT data::VARCHAR FROM transactions WHERE signature = ?1 LIMIT 1")?;
+        let mut rows = stmt.query_map(params![signature], |row| row.get::<_, Option<String>>(0))?;
+        let Some(data) = rows.next().transpose()?.flatten() else {
+            return Ok(None);
+        };
+        serde_json::from_str(&data).map(Some).map_err(|err| {
+            duckdb::Error::FromSqlConversionFailure(0, duckdb::types::Type::Text, Box::new(err))
+        })
+    }
+
+    /// Metadata updates since `since_slot` (inclusive), oldest first
+    pub fn get_token_metadata_updates(&self, since_slot: u64) -> Result<Vec<TokenMetadataUpdate>> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT slot, signature, mint, old_name, new_name, old_symbol, new_symbol
+      FROM token_metadata_updates
+      WHERE slot >= ?1
+      ORDER BY slot, signature
+      ",
+        )?;
+        let updates = stmt.query_map(params![since_slot], |row| {
+            Ok(TokenMetadataUpdate {
+                slot: row.get(0)?,
+                signature: row.get(1)?,
+                mint: row.get(2)?,
+                old_name: row.get(3)?,
+                new_name: row.get(4)?,
+                old_symbol: row.get(5)?,
+                new_symbol: row.get(6)?,
+            })
+        })?;
+        updates.collect()
+    }
+
+    pub fn get_token(&self, mint: &str) -> Result<Option<NewToken>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM tokens WHERE mint = ?1 LIMIT 1",
+            TOKEN_COLUMNS
+        ))?;
+        let mut tokens = stmt.query_map(params![mint], token_from_row)?;
+        tokens.next().transpose()
+    }
+
+    /// Transactions with the highest parse + insert time, requires parse timing to be enabled
+    pub fn get_slowest_transactions(&self, limit: u32) -> Result<Vec<TransactionParseStats>> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT slot, signature, parse_us, insert_us, ix_count
+      FROM parse_stats
+      ORDER BY parse_us + insert_us DESC, signature
+      LIMIT ?1
+      ",
+        )?;
+        let stats_iter = stmt.query_map(params![limit], |row| {
+            Ok(TransactionParseStats {
+                slot: row.get(0)?,
+                signature: row.get(1)?,
+                parse_us: row.get(2)?,
+                insert_us: row.get(3)?,
+                ix_count: row.get(4)?,
+            })
+        })?;
+        stats_iter.collect()
+    }
+
+    /// Mints of pumpfun tokens with a completed bonding curve
+    pub fn get_pumpfun_graduated_tokens(&self) -> Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT mint FROM bonding_curve_completions")?;
+        let mints_iter = stmt.query_map([], |row| row.get(0))?;
+        mints_iter.collect()
+    }
+
+    /// Creates the view `swaps_with_graduation`: all swaps with an `is_post_graduation` column
+    /// that is true for swaps of a graduated token after the slot its bonding curve completed.
+    /// A view instead of a column on `swaps` keeps bulk inserts working and tags new swaps automatically.
+    /// Returns the number of post graduation swaps
+    pub fn tag_post_graduation_swaps(&self) -> Result<u64> {
+        self.conn.execute_batch(
+            "
+      CREATE OR REPLACE VIEW swaps_with_graduation AS
+      SELECT s.*, COALESCE(s.slot > c.slot, false) AS is_post_graduation
+      FROM swaps s
+      LEFT JOIN bonding_curve_completions c ON s.token = c.mint;
+      ",
+        )?;
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM swaps_with_graduation WHERE is_post_graduation",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// Approximate holders of `mint` with a positive balance, sorted by balance.
+    /// Only transfers in the database are counted: balances from before the first parsed block and
+    /// mints/burns are missing. Amounts are raw token units (not divided by the token decimals)
+    pub fn get_token_holder_balances(&self, mint: &str) -> Result<Vec<TokenHolderBalance>> {
+        let query = format!(
+            "{} ORDER BY net_amount DESC, holder",
+            TOKEN_HOLDER_BALANCES_QUERY
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let balances = stmt.query_map(params![mint], |row| {
+            Ok(TokenHolderBalance {
+                holder: row.get(0)?,
+                net_amount: row.get(1)?,
+                buy_amount: row.get(2)?,
+                sell_amount: row.get(3)?,
+            })
+        })?;
+        balances.collect()
+    }
+
+    /// Running supply of `mint` from the supply changes in the database, oldest first.
+    /// Slots come from `transactions`: changes without a stored transaction are skipped
+    pub fn get_supply_history(&self, mint: &str) -> Result<Vec<SupplySnapshot>> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT sc.signature, t.slot, t.block_time, sc.amount,
+        (SUM(sc.amount) OVER (
+          PARTITION BY sc.mint ORDER BY t.slot, sc.ix_index, sc.signature
+          ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW
+        ) + COALESCE(tk.initial_supply, 0))::HUGEINT AS cumulative_supply
+      FROM supply_changes sc
+      JOIN transactions t ON t.signature = sc.signature
+      LEFT JOIN tokens tk ON tk.mint = sc.mint
+      WHERE sc.mint = ?1
+      ORDER BY t.slot, sc.ix_index, sc.signature
+      ",
+        )?;
+        let snapshots = stmt.query_map(params![mint], |row| {
+            Ok(SupplySnapshot {
+                signature: row.get(0)?,
+                slot: row.get(1)?,
+                block_time: row.get(2)?,
+                delta: row.get(3)?,
+                cumulative_supply: row.get(4)?,
+            })
+        })?;
+        snapshots.collect()
+    }
+
+    /// Supply after the last change of `get_supply_history`, the initial supply without changes
+    pub fn get_current_supply(&self, mint: &str) -> Result<i128> {
+        self.query_supply(mint, None)
+    }
+
+    /// Supply after all changes up to and including `slot`
+    pub fn get_supply_at_slot(&self, mint: &str, slot: u64) -> Result<i128> {
+        self.query_supply(mint, Some(slot))
+    }
+
+    fn query_supply(&self, mint: &str, slot: Option<u64>) -> Result<i128> {
+        self.conn.query_row(
+            "
+      SELECT (
+        COALESCE((SELECT initial_supply FROM tokens WHERE mint = ?1 LIMIT 1), 0)
+        + COALESCE((
+          SELECT SUM(sc.amount)
+          FROM supply_changes sc
+          JOIN transactions t ON t.signature = sc.signature
+          WHERE sc.mint = ?1 AND (?2 IS NULL OR t.slot <= ?2)
+        ), 0)
+      )::HUGEINT
+      ",
+            params![mint, slot],
+            |row| row.get(0),
+        )
+    }
+
+    /// Token transfers of `token` (all without), oldest first
+    pub fn get_token_transfers_decoded(
+        &self,
+        token: Option<&str>,
+    ) -> Result<Vec<DecodedTokenTransfer>> {
+        let mut stmt = self.conn.prepare(
+            "
+      WITH transfers AS (
+        SELECT tt.*, COALESCE(tk.decimals, tt.decimals) AS token_decimals
+        FROM token_transfers tt
+        LEFT JOIN tokens tk ON tk.mint = tt.token
+        WHERE ?1 IS NULL OR tt.token = ?1
+      )
+      SELECT
+        slot, block_time, signature, from_acc, to_acc,
+        (amount::HUGEINT::DOUBLE / pow(10, COALESCE(token_decimals, 0)))::DOUBLE,
+        amount::UBIGINT,
+        authority, src, dst, token_decimals, token
+      FROM transfers
+      ORDER BY slot, signature
+      ",
+        )?;
+        let transfers = stmt.query_map(params![token], |row| {
+            Ok(DecodedTokenTransfer {
+                slot: row.get(0)?,
+                block_time: row.get(1)?,
+                signature: row.get(2)?,
+                from_acc: row.get(3)?,
+                to_acc: row.get(4)?,
+                amount: row.get(5)?,
+                raw_amount: row.get(6)?,
+                authority: row.get(7)?,
+                from: row.get(8)?,
+                to: row.get(9)?,
+                decimals: row.get(10)?,
+                token: row.get(11)?,
+            })
+        })?;
+        transfers.collect()
+    }
+
+    /// Number of holders of `get_token_holder_balances`
+    pub fn get_holder_count(&self, mint: &str) -> Result<i64> {
+        let query = format!("SELECT COUNT(*) FROM ({})", TOKEN_HOLDER_BALANCES_QUERY);
+        self.conn.query_row(&query, params![mint], |row| row.get(0))
+    }
+
+    /// Returns `QueryReturnedNoRows` if the token is not in `tokens`
+    pub fn get_token_launch_stats(&self, mint: &str) -> Result<TokenLaunchStats> {
+        let query = format!("{} WHERE t.mint = ?1", TOKEN_LAUNCH_STATS_QUERY);
+        self.conn
+            .query_row(&query, params![mint], token_launch_stats_from_row)
+    }
+
+    /// Traded tokens with the fewest slots between creation and first swap, likely sniped by bots
+    pub fn get_fastest_sniped_tokens(&self, limit: u32) -> Result<Vec<TokenLaunchStats>> {
+        let query = format!(
+            "{} WHERE s.first_trade_slot IS NOT NULL ORDER BY slots_to_first_trade ASC, t.mint LIMIT ?1",
+            TOKEN_LAUNCH_STATS_QUERY
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let stats = stmt.query_map(params![limit], token_launch_stats_from_row)?;
+        stats.collect()
+    }
+
+    /// Returns `QueryReturnedNoRows` if no event of the account was inserted
+    pub fn get_account_lifecycle(&self, account: &str) -> Result<AccountLifecycle> {
+        self.conn.query_row(
+            "SELECT account, mint, owner, open_tx, close_tx, is_closed FROM account_states WHERE account = ?1",
+            params![account],
+            |row| {
+                Ok(AccountLifecycle {
+                    account: row.get(0)?,
+                    mint: row.get(1)?,
+                    owner: row.get(2)?,
+                    open_tx: row.get(3)?,
+                    close_tx: row.get(4)?,
+                    is_closed: row.get(5)?,
+                })
+            },
+        )
+    }
+
+    /// Approximate realized PnL of a wallet in terms of `quote_token`.
+    /// There is no order matching: a token only contributes to the realized PnL once
+    /// at least as many tokens were sold as bought (position closed).
+    pub fn get_wallet_pnl(&self, wallet: &str, quote_token: &str) -> Result<WalletPnl> {
+        let mut stmt = self.conn.prepare(
+            "
+      WITH trades AS (
+        SELECT
+          CASE WHEN token_in = ?2 THEN token_out ELSE token_in END AS token,
+          CASE WHEN token_in = ?2 THEN amount_in ELSE 0 END AS cost,
+          CASE WHEN token_in = ?2 THEN amount_out ELSE 0 END AS bought,
+          CASE WHEN token_out = ?2 THEN amount_out ELSE 0 END AS revenue,
+          CASE WHEN token_out = ?2 THEN amount_in ELSE 0 END AS sold
+        FROM swaps
+        WHERE signer = ?1 AND (token_in = ?2 OR token_out = ?2) AND token_in != token_out
+      )
+      SELECT token, SUM(cost)::DOUBLE, SUM(bought)::DOUBLE, SUM(revenue)::DOUBLE, SUM(sold)::DOUBLE, COUNT(*)
+      FROM trades
+      GROUP BY token
+      ORDER BY token
+      ",
+        )?;
+        let rows = stmt.query_map(params![wallet, quote_token], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, f64>(1)?,
+                row.get::<_, f64>(2)?,
+                row.get::<_, f64>(3)?,
+                row.get::<_, f64>(4)?,
+                row.get::<_, i64>(5)?,
+            ))
+        })?;
+
+        let mut pnl = WalletPnl {
+            realized_pnl: 0.0,
+            total_bought_quote: 0.0,
+            total_sold_quote: 0.0,
+            trade_count: 0,
+            tokens_traded: vec![],
+        };
+        for row in rows {
+            let (token, cost, bought, revenue, sold, count) = row?;
+            pnl.total_bought_quote += cost;
+            pnl.total_sold_quote += revenue;
+            pnl.trade_count += count;
+            pnl.tokens_traded.push(token);
+            // amounts are stored as FLOAT so allow for rounding
+            let is_closed = bought > 0.0 && bought - sold <= bought * 1e-6;
+            if is_closed {
+                pnl.realized_pnl += revenue - cost;
+            }
+        }
+        Ok(pnl)
+    }
+
+    /// Wallets ranked by their swap volume in `quote_token`
+    pub fn get_top_traders(
+        &self,
+        limit: u32,
+        quote_token: &str,
+        since_block_time: Option<i64>,
+    ) -> Result<Vec<TraderStats>> {
+        let query = format!(
+            "
+      SELECT
+        signer,
+        SUM(CASE WHEN token_in = ?1 THEN amount_in ELSE amount_out END)::DOUBLE AS total_volume_quote,
+        SUM(CASE WHEN token_in = ?1 THEN amount_in ELSE 0 END)::DOUBLE AS buy_volume,
+        SUM(CASE WHEN token_out = ?1 THEN amount_out ELSE 0 END)::DOUBLE AS sell_volume,
+        COUNT(*) AS trade_count,
+        COUNT(DISTINCT CASE WHEN token_in = ?1 THEN token_out ELSE token_in END) AS unique_tokens
+      FROM swaps
+      WHERE (token_in = ?1 OR token_out = ?1) AND block_time >= ?2
+      GROUP BY signer
+      ORDER BY total_volume_quote DESC
+      LIMIT {}
+      ",
+            limit
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let since_block_time = since_block_time.unwrap_or(i64::MIN);
+        let traders_iter = stmt.query_map(params![quote_token, since_block_time], |row| {
+            Ok(TraderStats {
+                signer: row.get(0)?,
+                total_volume_quote: row.get(1)?,
+                buy_volume: row.get(2)?,
+                sell_volume: row.get(3)?,
+                trade_count: row.get(4)?,
+                unique_tokens: row.get(5)?,
+            })
+        })?;
+        traders_iter.collect()
+    }
+
+    /// Swap summary of a single wallet in `quote_token`
+    pub fn get_wallet_activity(
+        &self,
+        wallet: &str,
+        quote_token: &str,
+        since_block_time: Option<i64>,
+    ) -> Result<TraderStats> {
+        let since_block_time = since_block_time.unwrap_or(i64::MIN);
+        self.conn.query_row(
+            "
+      SELECT
+        COALESCE(SUM(CASE WHEN token_in = ?1 THEN amount_in ELSE amount_out END), 0)::DOUBLE,
+        COALESCE(SUM(CASE WHEN token_in = ?1 THEN amount_in ELSE 0 END), 0)::DOUBLE,
+        COALESCE(SUM(CASE WHEN token_out = ?1 THEN amount_out ELSE 0 END), 0)::DOUBLE,
+        COUNT(*),
+        COUNT(DISTINCT CASE WHEN token_in = ?1 THEN token_out ELSE token_in END)
+      FROM swaps
+      WHERE signer = ?2 AND (token_in = ?1 OR token_out = ?1) AND block_time >= ?3
+      ",
+            params![quote_token, wallet, since_block_time],
+            |row| {
+                Ok(TraderStats {
+                    signer: wallet.to_string(),
+                    total_volume_quote: row.get(0)?,
+                    buy_volume: row.get(1)?,
+                    sell_volume: row.get(2)?,
+                    trade_count: row.get(3)?,
+                    unique_tokens: row.get(4)?,
+                })
+            },
+        )
+    }
+
+    /// Buy and sell summary of `wallet`, computed in a single aggregation over `swaps`
+    pub fn get_wallet_swap_summary(&self, wallet: &str) -> Result<WalletSwapSummary> {
+        let query = format!(
+            "SELECT ?1::TEXT, {} FROM swaps WHERE signer = ?1",
+            WALLET_SWAP_SUMMARY_COLUMNS
+        );
+        self.conn.query_row(&query, params![wallet], |row| {
+            wallet_swap_summary_from_row(wallet.to_string(), row)
+        })
+    }
+
+    /// Summaries of all wallets with at least `min_trade_count` swaps, most active wallets first
+    pub fn get_all_wallet_summaries(&self, min_trade_count: u32) -> Result<Vec<WalletSwapSummary>> {
+        let query = format!(
+            "
+      SELECT signer, {}
+      FROM swaps
+      GROUP BY signer
+      HAVING COUNT(*) >= ?1
+      ORDER BY COUNT(*) DESC, signer
+      ",
+            WALLET_SWAP_SUMMARY_COLUMNS
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let summaries = stmt.query_map(params![min_trade_count], |row| {
+            wallet_swap_summary_from_row(row.get(0)?, row)
+        })?;
+        summaries.collect()
+    }
+
+    /// Wallets that traded at least `min_shared_tokens` of the tokens of `wallet` in the same slot,
+    /// most shared tokens first
+    pub fn find_wallet_interactions(
+        &self,
+        wallet: &str,
+        min_shared_tokens: u32,
+    ) -> Result<Vec<WalletInteraction>> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT
+        other.signer,
+        string_agg(DISTINCT other.token, ',' ORDER BY other.token),
+        COUNT(DISTINCT other.token),
+        MIN(other.slot)
+      FROM swaps w
+      JOIN swaps other ON other.token = w.token AND other.slot = w.slot AND other.signer != w.signer
+      WHERE w.signer = ?1 AND w.token != ''
+      GROUP BY other.signer
+      HAVING COUNT(DISTINCT other.token) >= ?2
+      ORDER BY COUNT(DISTINCT other.token) DESC, other.signer
+      ",
+        )?;
+        let interactions = stmt.query_map(params![wallet, min_shared_tokens], |row| {
+            let shared_tokens: String = row.get(1)?;
+            Ok(WalletInteraction {
+                other_wallet: row.get(0)?,
+                // mints don't contain commas
+                shared_tokens: shared_tokens.split(',').map(|t| t.to_string()).collect(),
+                shared_token_count: row.get(2)?,
+                first_shared_trade_slot: row.get(3)?,
+            })
+        })?;
+        interactions.collect()
+    }
+
+    /// Wallets whose first buy of `token` is at most `within_n_slots` away from the first buy of
+    /// another wallet
+    pub fn find_co_snipers(&self, token: &str, within_n_slots: u64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "
+      WITH first_buys AS (
+        SELECT signer, MIN(slot) AS first_slot
+        FROM swaps
+        WHERE token = ?1 AND swap_type = 'Buy'
+        GROUP BY signer
+      )
+      SELECT DISTINCT a.signer
+      FROM first_buys a
+      JOIN first_buys b ON a.signer != b.signer AND abs(a.first_slot - b.first_slot) <= ?2::BIGINT
+      ORDER BY a.signer
+      ",
+        )?;
+        let wallets = stmt.query_map(params![token, within_n_slots], |row| row.get(0))?;
+        wallets.collect()
+    }
+
+    /// Wallets with at least `min_round_trips` round trips on a token: a buy followed by a sell
+    /// within `max_slot_gap` slots. Every (buy, sell) pair counts, so a buy followed by two sells is
+    /// two round trips. This is a heuristic with false positives: snipers, arbitrage and market
+    /// making bots trade the same pattern without inflating volume
+    pub fn detect_wash_trading_candidates(
+        &self,
+        min_round_trips: u32,
+        max_slot_gap: u64,
+    ) -> Result<Vec<WashTradingCandidate>> {
+        let mut stmt = self.conn.prepare(
+            "
+      WITH round_trips AS (
+        SELECT b.signer, b.token,
+          b.signature AS buy_signature, b.amount_in AS buy_sol,
+          s.signature AS sell_signature, s.amount_out AS sell_sol
+        FROM swaps b
+        JOIN swaps s ON s.signer = b.signer AND s.token = b.token
+        WHERE b.swap_type = 'Buy' AND s.swap_type = 'Sell' AND NOT b.error AND NOT s.error
+          AND b.slot < s.slot AND s.slot - b.slot <= ?2::BIGINT
+      ),
+      candidates AS (
+        SELECT signer, token, COUNT(*) AS round_trips
+        FROM round_trips
+        GROUP BY signer, token
+        HAVING COUNT(*) >= ?1
+      ),
+      legs AS (
+        SELECT signer, token, buy_signature AS signature, buy_sol AS sol FROM round_trips
+        UNION
+        SELECT signer, token, sell_signature, sell_sol FROM round_trips
+      )
+      SELECT
+        c.signer,
+        c.token,
+        c.round_trips::UINTEGER,
+        SUM(l.sol)::DOUBLE,
+        (COALESCE(SUM(t.fee), 0) / 1000000000.0)::DOUBLE
+      FROM candidates c
+      JOIN legs l ON l.signer = c.signer AND l.token = c.token
+      LEFT JOIN transactions t ON t.signature = l.signature
+      GROUP BY c.signer, c.token, c.round_trips
+      ORDER BY c.round_trips DESC, c.signer, c.token
+      ",
+        )?;
+        let candidates = stmt.query_map(params![min_round_trips, max_slot_gap], |row| {
+            Ok(WashTradingCandidate {
+                wallet: row.get(0)?,
+                token: row.get(1)?,
+                round_trips: row.get(2)?,
+                total_volume: row.get(3)?,
+                total_fees_paid: row.get(4)?,
+            })
+        })?;
+        candidates.collect()
+    }
+
+    /// Swaps since `since_slot` (inclusive) grouped by (token_in, token_out), without the pairs
+    /// below `min_volume_sol`. Edges are sorted by volume
+    pub fn export_swap_graph(&self, since_slot: u64, min_volume_sol: f64) -> Result<SwapGraph> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT
+        token_in,
+        token_out,
+        SUM(CASE swap_type WHEN 'Buy' THEN amount_in WHEN 'Sell' THEN amount_out ELSE 0 END)::DOUBLE AS volume,
+        COUNT(*) AS trade_count
+      FROM swaps
+      WHERE slot >= ?1 AND NOT error
+      GROUP BY token_in, token_out
+      HAVING volume >= ?2
+      ORDER BY volume DESC, token_in, token_out
+      ",
+        )?;
+        let edges = stmt
+            .query_map(params![since_slot, min_volume_sol], |row| {
+                Ok(SwapEdge {
+                    from_token: row.get(0)?,
+                    to_token: row.get(1)?,
+                    volume: row.get(2)?,
+                    trade_count: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        let mut nodes: Vec<String> = edges
+            .iter()
+            .flat_map(|edge| [edge.from_token.clone(), edge.to_token.clone()])
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        nodes.sort();
+        Ok(SwapGraph { nodes, edges })
+    }
+
+    /// Writes the graph of all swaps to `path` in Graphviz DOT format, e.g. `dot -Tsvg`
+    pub fn export_swap_graph_to_dot(&self, path: &str) -> anyhow::Result<()> {
+        let graph = self.export_swap_graph(0, 0.0)?;
+        let mut dot = String::from("digraph swaps {\n");
+        for node in &graph.nodes {
+            dot.push_str(&format!("  \"{}\";\n", node));
+        }
+        for edge in &graph.edges {
+            dot.push_str(&format!(
+                "  \"{}\" -> \"{}\" [label=\"{:.2} SOL ({})\"];\n",
+                edge.from_token, edge.to_token, edge.volume, edge.trade_count
+            ));
+        }
+        dot.push_str("}\n");
+        std::fs::write(path, dot)?;
+        Ok(())
+    }
+
+    /// SOL transfers between wallets up to `depth` hops away from `root_wallet`, in both directions.
+    /// Pairs with less than `min_sol` in total are dropped before the traversal.
+    /// `depth` is capped at 3: the number of wallets grows exponentially with every hop
+    pub fn get_sol_transfer_network(
+        &self,
+        depth: u8,
+        root_wallet: &str,
+        min_sol: f64,
+    ) -> Result<SolTransferNetwork> {
+        let depth = depth.min(3) as u32;
+        let mut stmt = self.conn.prepare(
+            "
+      WITH RECURSIVE edges AS (
+        SELECT src, dst, SUM(lamports)::DOUBLE / 1e9 AS total_sol, COUNT(*) AS transfer_count
+        FROM sol_transfers
+        GROUP BY src, dst
+        HAVING total_sol >= ?3
+      ),
+      reachable(wallet, depth) AS (
+        SELECT ?2::VARCHAR, 0
+        UNION
+        SELECT
+          CASE WHEN edges.src = reachable.wallet THEN edges.dst ELSE edges.src END,
+          reachable.depth + 1
+        FROM reachable
+        JOIN edges ON reachable.wallet IN (edges.src, edges.dst)
+        WHERE reachable.depth < ?1
+      ),
+      expanded AS (
+        -- wallets whose counterparties are part of the network
+        SELECT DISTINCT wallet FROM reachable WHERE depth < ?1
+      )
+      SELECT src, dst, total_sol, transfer_count
+      FROM edges
+      WHERE src IN (SELECT wallet FROM expanded) OR dst IN (SELECT wallet FROM expanded)
+      ORDER BY total_sol DESC, src, dst
+      ",
+        )?;
+        let edges = stmt
+            .query_map(params![depth, root_wallet, min_sol], |row| {
+                Ok(SolEdge {
+                    from: row.get(0)?,
+                    to: row.get(1)?,
+                    total_sol: row.get(2)?,
+                    transfer_count: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        let mut nodes: Vec<String> = edges
+            .iter()
+            .flat_map(|edge| [edge.from.clone(), edge.to.clone()])
+            .chain([root_wallet.to_string()])
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        nodes.sort();
+        Ok(SolTransferNetwork { nodes, edges })
+    }
+
+    /// Writes the SOL transfer network of `root_wallet` to `path` in Graphviz DOT format
+    pub fn export_sol_transfer_network_to_dot(
+        &self,
+        depth: u8,
+        root_wallet: &str,
+        min_sol: f64,
+        path: &str,
+    ) -> anyhow::Result<()> {
+        let network = self.get_sol_transfer_network(depth, root_wallet, min_sol)?;
+        let mut dot = String::from("digraph sol_transfers {\n");
+        for node in &network.nodes {
+            match node == root_wallet {
+                true => dot.push_str(&format!("  \"{}\" [shape=doublecircle];\n", node)),
+                false => dot.push_str(&format!("  \"{}\";\n", node)),
+            }
+        }
+        for edge in &network.edges {
+            dot.push_str(&format!(
+                "  \"{}\" -> \"{}\" [label=\"{:.2} SOL ({})\"];\n",
+                edge.from, edge.to, edge.total_sol, edge.transfer_count
+            ));
+        }
+        dot.push_str("}\n");
+        std::fs::write(path, dot)?;
+        Ok(())
+    }
+
+    /// The first `limit` wallets that bought `mint`, with their first buy
+    pub fn get_token_first_buyers(&self, mint: &str, limit: u32) -> Result<Vec<FirstBuyerStats>> {
+        let mut stmt = self.conn.prepare(
+            "
+      WITH first_buys AS (
+        SELECT signer, slot, block_time, signature, amount_in, amount_out
+        FROM swaps
+        WHERE token_out = ?1 AND swap_type = 'Buy' AND NOT error
+        QUALIFY ROW_NUMBER() OVER (PARTITION BY signer ORDER BY slot, signature) = 1
+      )
+      SELECT
+        signer, slot, block_time, amount_in::DOUBLE, amount_out::DOUBLE,
+        ROW_NUMBER() OVER (ORDER BY slot, signature)::UINTEGER AS rank
+      FROM first_buys
+      ORDER BY rank
+      LIMIT ?2
+      ",
+        )?;
+        let buyers = stmt.query_map(params![mint, limit], |row| {
+            Ok(FirstBuyerStats {
+                signer: row.get(0)?,
+                buy_slot: row.get(1)?,
+                buy_block_time: row.get(2)?,
+                amount_sol_spent: row.get(3)?,
+                amount_token_received: row.get(4)?,
+                rank: row.get(5)?,
+            })
+        })?;
+        buyers.collect()
+    }
+
+    /// Realized PnL (SOL) of `signer` on `mint`: sell revenue minus the average buy cost of the
+    /// sold tokens. Tokens still held are not valued, 0 without buys
+    pub fn get_mint_sniper_pnl(&self, mint: &str, signer: &str) -> Result<f64> {
+        self.conn.query_row(
+            "
+      WITH totals AS (
+        SELECT
+          SUM(CASE WHEN swap_type = 'Buy' THEN amount_in ELSE 0 END)::DOUBLE AS sol_spent,
+          SUM(CASE WHEN swap_type = 'Buy' THEN amount_out ELSE 0 END)::DOUBLE AS tokens_bought,
+          SUM(CASE WHEN swap_type = 'Sell' THEN amount_out ELSE 0 END)::DOUBLE AS sol_received,
+          SUM(CASE WHEN swap_type = 'Sell' THEN amount_in ELSE 0 END)::DOUBLE AS tokens_sold
+        FROM swaps
+        WHERE token = ?1 AND signer = ?2 AND NOT error
+      )
+      SELECT COALESCE(
+        CASE WHEN tokens_bought > 0
+          THEN sol_received - sol_spent * least(tokens_sold / tokens_bought, 1.0)
+        END,
+        0.0
+      )::DOUBLE
+      FROM totals
+      ",
+            params![mint, signer],
+            |row| row.get(0),
+        )
+    }
+
+    /// Price of `token` in `quote` of the swap closest to `slot`, the earlier one on a tie.
+    /// `None` if the pair was never swapped
+    pub fn get_token_price_at_slot(
+        &self,
+        token: &str,
+        slot: u64,
+        quote: &str,
+    ) -> Result<Option<f64>> {
+        let query = format!(
+            "
+      SELECT price
+      FROM ({})
+      ORDER BY abs(slot - ?3::BIGINT), slot
+      LIMIT 1
+      ",
+            PAIR_PRICES_QUERY
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let mut prices = stmt.query_map(params![token, quote, slot], |row| row.get(0))?;
+        prices.next().transpose()
+    }
+
+    /// Prices of `get_token_price_at_slot` every `interval_slots` from `start_slot` to `end_slot`
+    /// (inclusive). Empty if the pair was never swapped
+    pub fn get_token_price_series(
+        &self,
+        token: &str,
+        quote: &str,
+        start_slot: u64,
+        end_slot: u64,
+        interval_slots: u64,
+    ) -> Result<Vec<(u64, f64)>> {
+        // the distance is doubled and later swaps get +1 so that the earlier swap wins a tie
+        let query = format!(
+            "
+      WITH prices AS ({}),
+      samples AS (
+        SELECT unnest(generate_series(?3::BIGINT, ?4::BIGINT, ?5::BIGINT)) AS sample_slot
+      )
+      SELECT
+        sample_slot::UBIGINT,
+        arg_min(price, 2 * abs(slot - sample_slot) + (slot > sample_slot)::INTEGER)
+      FROM samples
+      CROSS JOIN prices
+      GROUP BY sample_slot
+      ORDER BY sample_slot
+      ",
+            PAIR_PRICES_QUERY
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let prices = stmt.query_map(
+            params![token, quote, start_slot, end_slot, interval_slots.max(1)],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        prices.collect()
+    }
+
+    /// Tokens traded by `wallet`, sorted by volume in `quote_token`
+    pub fn get_wallet_tokens(
+        &self,
+        wallet: &str,
+        quote_token: &str,
+    ) -> Result<Vec<WalletTokenVolume>> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT
+        CASE WHEN token_in = ?1 THEN token_out ELSE token_in END AS traded_token,
+        SUM(CASE WHEN token_in = ?1 THEN amount_in ELSE amount_out END)::DOUBLE AS volume_quote,
+        SUM(CASE WHEN token_in = ?1 THEN amount_in ELSE 0 END)::DOUBLE,
+        SUM(CASE WHEN token_out = ?1 THEN amount_out ELSE 0 END)::DOUBLE,
+        COUNT(*)
+      FROM swaps
+      WHERE signer = ?2 AND (token_in = ?1 OR token_out = ?1) AND token_in != token_out
+      GROUP BY traded_token
+      ORDER BY volume_quote DESC, traded_token
+      ",
+        )?;
+        let tokens_iter = stmt.query_map(params![quote_token, wallet], |row| {
+            Ok(WalletTokenVolume {
+                token: row.get(0)?,
+                volume_quote: row.get(1)?,
+                buy_volume: row.get(2)?,
+                sell_volume: row.get(3)?,
+                trade_count: row.get(4)?,
+            })
+        })?;
+        tokens_iter.collect()
+    }
+
+    /// priority fee (SOL) percentiles over all transactions with compute budget instructions
+    /// percentiles are in [0, 1], e.g. [0.5, 0.9, 0.99]
+    pub fn get_fee_percentiles(&self, percentiles: &[f64]) -> Result<Vec<f64>> {
+        let mut result = Vec::with_capacity(percentiles.len());
+        for percentile in percentiles {
+            let query = format!(
+                "SELECT quantile_cont(effective_priority_fee_sol, {})::DOUBLE FROM fee_stats",
+                percentile
+            );
+            let value: Option<f64> = self.conn.query_row(&query, [], |row| row.get(0))?;
+            result.push(value.unwrap_or(0.0));
+        }
+        Ok(result)
+    }
+
+    /// Fees of the transactions signed by `wallet`. `transactions.fee` is the total fee,
+    /// the priority fee is `compute_unit_limit * priority_fee / 1_000_000` of `fees`
+    /// (micro-lamports per CU) and the base fee the rest
+    pub fn get_lifetime_fees(&self, wallet: &str) -> Result<WalletFeeStats> {
+        self.conn.query_row(
+            "
+      WITH wallet_fees AS (
+        SELECT
+          t.fee,
+          COALESCE(ROUND(f.compute_unit_limit::DOUBLE * f.priority_fee / 1000000.0), 0) AS priority_fee
+        FROM transactions t
+        LEFT JOIN fees f ON f.signature = t.signature
+        WHERE t.signer = ?1
+      )
+      SELECT
+        COALESCE(SUM(fee), 0)::BIGINT,
+        COALESCE(SUM(priority_fee), 0)::BIGINT,
+        COUNT(*),
+        COALESCE(AVG(priority_fee), 0)::DOUBLE
+      FROM wallet_fees
+      ",
+            params![wallet],
+            |row| {
+                let total_fee_lamports: i64 = row.get(0)?;
+                let total_priority_fee_lamports: i64 = row.get(1)?;
+                Ok(WalletFeeStats {
+                    total_base_fee_lamports: total_fee_lamports - total_priority_fee_lamports,
+                    total_priority_fee_lamports,
+                    total_fee_lamports,
+                    transaction_count: row.get(2)?,
+                    average_priority_fee_lamports: row.get(3)?,
+                })
+            },
+        )
+    }
+
+    /// [p50, p90, p99] of the priority fees (lamports) of `wallet`'s transactions
+    /// with compute budget instructions, zeros without any
+    pub fn get_fee_percentiles_by_wallet(&self, wallet: &str) -> Result<Vec<f64>> {
+        let mut result = Vec::with_capacity(3);
+        for percentile in [0.5, 0.9, 0.99] {
+            let query = format!(
+                "
+      SELECT quantile_cont(f.compute_unit_limit::DOUBLE * f.priority_fee / 1000000.0, {})::DOUBLE
+      FROM fees f
+      JOIN transactions t ON t.signature = f.signature
+      WHERE t.signer = ?1
+      ",
+                percentile
+            );
+            let value: Option<f64> = self
+                .conn
+                .query_row(&query, params![wallet], |row| row.get(0))?;
+            result.push(value.unwrap_or(0.0));
+        }
+        Ok(result)
+    }
+
+    pub fn get_jito_tip_stats(&self) -> Result<JitoTipStats> {
+        self.conn.query_row(
+            "
+      SELECT
+        COALESCE(SUM(jito_tip_lamports), 0)::UBIGINT,
+        COALESCE(AVG(jito_tip_lamports), 0)::DOUBLE,
+        COUNT(jito_tip_lamports)
+      FROM transactions
+      WHERE jito_tip_lamports IS NOT NULL
+      ",
+            [],
+            |row| {
+                Ok(JitoTipStats {
+                    total_tip_lamports: row.get(0)?,
+                    avg_tip_lamports: row.get(1)?,
+                    tx_count: row.get(2)?,
+                })
+            },
+        )
+    }
+
+    /// Number of transactions per discard reason, transactions without a reason are counted as "None"
+    pub fn get_discard_reason_stats(&self) -> Result<HashMap<String, i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COALESCE(discard_reason, 'None'), COUNT(*) FROM transactions GROUP BY ALL",
+        )?;
+        let counts_iter = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        counts_iter.collect()
+    }
+
+    /// Fraction of transactions that were kept, 0 if there are no transactions
+    pub fn get_non_discarded_ratio(&self) -> Result<f64> {
+        self.conn.query_row(
+            "SELECT COALESCE(COUNT(*) FILTER (WHERE NOT is_discarded) / NULLIF(COUNT(*), 0), 0)::DOUBLE FROM transactions",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// Aggregates the `compute_efficiency` view, requires `create_analytics_views`.
+    /// Only transactions that set a compute unit limit are included
+    pub fn get_compute_efficiency_stats(&self) -> Result<ComputeEfficiencyStats> {
+        const WORST_OFFENDERS: u32 = 10;
+        let (avg_efficiency_pct, median_efficiency_pct, total_wasted_cu) = self.conn.query_row(
+            "
+      SELECT
+        COALESCE(AVG(efficiency_pct), 0)::DOUBLE,
+        COALESCE(median(efficiency_pct), 0)::DOUBLE,
+        COALESCE(SUM(wasted_cu), 0)::BIGINT
+      FROM compute_efficiency
+      ",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        let mut stmt = self.conn.prepare(
+            "SELECT signature FROM compute_efficiency ORDER BY efficiency_pct, signature LIMIT ?1",
+        )?;
+        let worst_offenders = stmt
+            .query_map(params![WORST_OFFENDERS], |row| row.get(0))?
+            .collect::<Result<_>>()?;
+        Ok(ComputeEfficiencyStats {
+            avg_efficiency_pct,
+            median_efficiency_pct,
+            total_wasted_cu,
+            worst_offenders,
+        })
+    }
+
+    /// Programs and instructions of `cant_discard` by number of transactions, most frequent first.
+    /// These are the candidates for new parsers
+    pub fn get_cant_discard_programs(&self) -> Result<Vec<CantDiscardStats>> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT program_id, fn_name, COUNT(*) AS cnt, ANY_VALUE(signature)
+      FROM cant_discard
+      GROUP BY program_id, fn_name
+      ORDER BY cnt DESC, program_id, fn_name
+      ",
+        )?;
+        let stats_iter = stmt.query_map([], |row| {
+            Ok(CantDiscardStats {
+                program_id: row.get(0)?,
+                fn_name: row.get(1)?,
+                occurrence_count: row.get(2)?,
+                example_signature: row.get(3)?,
+            })
+        })?;
+        stats_iter.collect()
+    }
+
+    /// Programs of `cant_discard` that no parser handled in any transaction of `tx_programs`
+    pub fn get_unrecognized_program_ids(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT DISTINCT program_id
+      FROM cant_discard
+      WHERE program_id NOT IN (SELECT program_id FROM tx_programs WHERE can_parse)
+      ORDER BY program_id
+      ",
+        )?;
+        let program_ids = stmt.query_map([], |row| row.get(0))?;
+        program_ids.collect()
+    }
+
+    /// Calls per program of `tx_programs`, most frequent first
+    pub fn get_program_invocation_frequency(&self) -> Result<Vec<ProgramFrequency>> {
+        self.query_program_frequency("")
+    }
+
+    /// Programs that no parser handled with more than `min_calls` calls, most frequent first.
+    /// These are the candidates for new parsers, see also `get_cant_discard_programs`
+    pub fn get_unparsed_high_frequency_programs(
+        &self,
+        min_calls: u64,
+    ) -> Result<Vec<ProgramFrequency>> {
+        self.query_program_frequency(&format!(
+            "HAVING parsed_calls = 0 AND total_calls > {}",
+            min_calls
+        ))
+    }
+
+    fn query_program_frequency(&self, having: &str) -> Result<Vec<ProgramFrequency>> {
+        let query = format!(
+            "
+      SELECT
+        program_id,
+        COUNT(*) AS total_calls,
+        COUNT(*) FILTER (WHERE can_parse) AS parsed_calls,
+        COUNT(*) FILTER (WHERE has_error) AS error_calls,
+        (100.0 * COUNT(*) FILTER (WHERE can_parse) / COUNT(*))::DOUBLE AS parse_rate_pct
+      FROM tx_programs
+      GROUP BY program_id
+      {}
+      ORDER BY total_calls DESC, program_id
+      ",
+            having
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let programs = stmt.query_map([], |row| {
+            Ok(ProgramFrequency {
+                program_id: row.get(0)?,
+                total_calls: row.get(1)?,
+                parsed_calls: row.get(2)?,
+                error_calls: row.get(3)?,
+                parse_rate_pct: row.get(4)?,
+            })
+        })?;
+        programs.collect()
+    }
+
+    pub fn count_swaps_by_dex(&self) -> Result<HashMap<DexType, i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT dex::TEXT, COUNT(*) FROM swaps GROUP BY dex")?;
+        let counts_iter = stmt.query_map([], |row| {
+            let dex: String = row.get(0)?;
+            Ok((DexType::from_db(&dex).unwrap(), row.get(1)?))
+        })?;
+        counts_iter.collect()
+    }
+
+    /// (hour start timestamp, swap count) ordered by hour, empty hours are omitted
+    pub fn count_swaps_by_hour(&self, since_block_time: i64) -> Result<Vec<(i64, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "
+      SELECT (block_time - block_time % 3600) AS hour, COUNT(*)
+      FROM swaps
+      WHERE block_time >= ?1
+      GROUP BY hour
+      ORDER BY hour
+      ",
+        )?;
+        let counts_iter = stmt.query_map(params![since_block_time], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        counts_iter.collect()
+    }
+
+    /// The `top_n` tokens by buy + sell volume in `quote_token`
+    pub fn sum_volume_by_token(&self, quote_token: &str, top_n: u32) -> Result<Vec<(String, f64)>> {
+        let query = format!(
+            "
+      SELECT
+        CASE WHEN token_in = ?1 THEN token_out ELSE token_in END AS traded_token,
+        SUM(CASE WHEN token_in = ?1 THEN amount_in ELSE amount_out END)::DOUBLE AS volume_quote
+      FROM swaps
+      WHERE (token_in = ?1 OR token_out = ?1) AND token_in != token_out
+      GROUP BY traded_token
+      ORDER BY volume_quote DESC, traded_token
+      LIMIT {}
+      ",
+            top_n
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let volume_iter =
+            stmt.query_map(params![quote_token], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        volume_iter.collect()
+    }
+
+    /// Copy all rows of `other` into this database.
+    /// Rows that conflict with existing primary keys are skipped.
+    pub fn merge_from(&mut self, other: &SolanaDatabase) -> Result<()> {
+        if self.path.is_some() && self.path == other.path {
+            return Ok(());
+        }
+
+        // in-memory databases can't be attached from another connection
+        // so they are written to a temporary database file first
+        let temp_file_path = match &other.path {
+            Some(_) => {
+                // make sure everything is written from the wal into the db file
+                other.conn.execute_batch("CHECKPOINT;")?;
+                None
+            }
+            None => {
+                let ts_now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis();
+                let temp_path = self.get_temp_dir().unwrap_or(".".to_string());
+                let temp_file_path = format!("{}/merge_{}.db", temp_path, ts_now);
+                let source_db: String =
+                    other
+                        .conn
+                        .query_row("SELECT current_database()", [], |row| row.get(0))?;
+                other.conn.execute_batch(
+                    format!(
+                        "
+      ATTACH '{}' AS merge_target;
+      COPY FROM DATABASE {} TO merge_target;
+      DETACH merge_target;
+      ",
+                        temp_file_path, source_db
+                    )
+                    .as_str(),
+                )?;
+                Some(temp_file_path)
+            }
+        };
+
+        let source_path = temp_file_path.clone().or(other.path.clone()).unwrap();
+        self.conn.execute_batch(
+            format!("ATTACH '{}' AS merge_source (READ_ONLY);", source_path).as_str(),
+        )?;
+
+        let mut result = Ok(());
+        for table in TABLES {
+            // databases created by older versions don't have all tables
+            match other.has_table(table) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+            let on_conflict = if self.use_primary_keys && has_primary_key(table) {
+                " ON CONFLICT DO NOTHING"
+            } else {
+                ""
+            };
+            let query = format!(
+                "INSERT INTO {} BY NAME SELECT * FROM merge_source.{}{}",
+                table, table, on_conflict
+            );
+            if let Err(err) = self.conn.execute(&query, []) {
+                result = Err(err);
+                break;
+            }
+        }
+
+        self.conn.execute_batch("DETACH merge_source;")?;
+        if let Some(temp_file_path) = temp_file_path {
+            let _ = std::fs::remove_file(&temp_file_path);
+            let _ = std::fs::remove_file(format!("{}.wal", temp_file_path));
+        }
+        result
+    }
+
+    /// Deletes all but the first inserted row for each combination of `key_columns`.
+    /// Returns the number of deleted rows.
+    /// This scans the whole table: run it after merging databases, not in the pipeline hot path.
+    /// Fails with `InvalidColumnName` if `key_columns` is empty.
+    pub fn deduplicate_table(&mut self, table: &str, key_columns: &[&str]) -> Result<u64> {
+        if key_columns.is_empty() {
+            return Err(duckdb::Error::InvalidColumnName(format!(
+                "no key columns to deduplicate {}",
+                table
+            )));
+        }
+        let query = format!(
+            "DELETE FROM {} WHERE rowid NOT IN (SELECT MIN(rowid) FROM {} GROUP BY {})",
+            table,
+            table,
+            key_columns.join(", ")
+        );
+        let deleted = self.conn.execute(&query, [])?;
+        Ok(deleted as u64)
+    }
+
+    /// Runs `deduplicate_table` on every table using `get_dedup_key_columns`.
+    /// Returns the number of deleted rows per table.
+    pub fn deduplicate_all(&mut self) -> Result<HashMap<String, u64>> {
+        let mut deleted = HashMap::new();
+        for table in TABLES {
+            let count = self.deduplicate_table(table, get_dedup_key_columns(table))?;
+            deleted.insert(table.to_string(), count);
+        }
+        Ok(deleted)
+    }
+
+    /// Deletes the rows of all slots before `slot` in one transaction.
+    /// Tables without a slot column are pruned by the signatures of the deleted transactions.
+    /// `tokens` and `metadata` are kept because tokens are traded long after their creation.
+    /// Returns the number of deleted rows per table.
+    pub fn delete_blocks_before_slot(&mut self, slot: u64) -> Result<HashMap<String, u64>> {
+        self.with_transaction(|db| {
+            let mut deleted = HashMap::new();
+            let mut slot_tables = vec![];
+            // signature tables first: they need the transactions that are deleted below
+            for table in TABLES {
+                if db.has_column(table, "slot")? {
+                    slot_tables.push(table);
+                } else if db.has_column(table, "signature")? {
+                    let query = format!(
+                        "DELETE FROM {} WHERE signature IN (SELECT signature FROM transactions WHERE slot < ?1)",
+                        table
+                    );
+                    let count = db.conn.execute(&query, params![slot])?;
+                    deleted.insert(table.to_string(), count as u64);
+                }
+            }
+            for table in slot_tables {
+                let query = format!("DELETE FROM {} WHERE slot < ?1", table);
+                let count = db.conn.execute(&query, params![slot])?;
+                deleted.insert(table.to_string(), count as u64);
+            }
+            Ok(deleted)
+        })
+    }
+
+    /// Like `delete_blocks_before_slot` for the first slot in `blocks` at or after `block_time`
+    pub fn delete_blocks_before_block_time(
+        &mut self,
+        block_time: i64,
+    ) -> Result<HashMap<String, u64>> {
+        let slot: Option<u64> = self.conn.query_row(
+            "
+      SELECT COALESCE(MIN(slot) FILTER (WHERE block_time >= ?1), MAX(slot) + 1)
+      FROM blocks
+      ",
+            params![block_time],
+            |row| row.get(0),
+        )?;
+        match slot {
+            Some(slot) => self.delete_blocks_before_slot(slot),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    /// (min slot, max slot) of `blocks`, `None` without blocks
+    pub fn get_slot_range(&self) -> Result<Option<(u64, u64)>> {
+        let range: (Option<u64>, Option<u64>) =
+            self.conn
+                .query_row("SELECT MIN(slot), MAX(slot) FROM blocks", [], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?;
+        Ok(range.0.zip(range.1))
+    }
+
+    /// Slots between the min and max slot of `blocks` that have no block.
+    /// Includes slots skipped by their leader, which have no block on chain either
+    pub fn get_missing_slots(&self) -> Result<Vec<u64>> {
+        let mut stmt = self.conn.prepare(
+            "
+      WITH slots AS (
+        SELECT slot, LEAD(slot) OVER (ORDER BY slot) AS next_slot
+        FROM (SELECT DISTINCT slot FROM blocks)
+      )
+      SELECT unnest(range(slot + 1, next_slot))::UBIGINT AS missing_slot
+      FROM slots
+      WHERE next_slot > slot + 1
+      ORDER BY missing_slot
+      ",
+        )?;
+        let slots = stmt.query_map([], |row| row.get(0))?;
+        slots.collect()
+    }
+
+    /// Percentage of the slots from `expected_start` to `expected_end` (inclusive) in `blocks`
+    pub fn get_slot_coverage_pct(&self, expected_start: u64, expected_end: u64) -> Result<f64> {
+        if expected_end < expected_start {
+            return Ok(0.0);
+        }
+        let present: i64 = self.conn.query_row(
+            "SELECT COUNT(DISTINCT slot) FROM blocks WHERE slot BETWEEN ?1 AND ?2",
+            params![expected_start, expected_end],
+            |row| row.get(0),
+        )?;
+        let expected = expected_end - expected_start + 1;
+        Ok(present as f64 * 100.0 / expected as f64)
+    }
+
+    /// Keeps the slots from `MAX(slot) - n` of `blocks`, see `delete_blocks_before_slot`
+    pub fn prune_to_last_n_slots(&mut self, n: u64) -> Result<HashMap<String, u64>> {
+        let max_slot: Option<u64> =
+            self.conn
+                .query_row("SELECT MAX(slot) FROM blocks", [], |row| row.get(0))?;
+        match max_slot {
+            Some(max_slot) => self.delete_blocks_before_slot(max_slot.saturating_sub(n)),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    pub fn load_parquet_table(&self, table: &str, file_path: &str) -> Result<()> {
+        let connection = &self.conn;
+        let _ = connection.execute(
+            format!(
+                "COPY {} FROM '{}' (FORMAT 'parquet', COMPRESSION 'ZSTD');",
+                table, file_path
+            )
+            .as_str(),
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Loads all `{table}_*.parquet` files in `dir_path` (recursive) into their tables.
+    /// `tables: None` loads all known tables. A failing table is reported in `errors`
+    /// and does not stop the other tables from loading.
+    pub fn load_and_merge_parquet_directory(
+        &self,
+        dir_path: &str,
+        tables: Option<&[&str]>,
+    ) -> Result<LoadStats> {
+        let dir_path = dir_path.trim_end_matches('/');
+        let tables = tables.unwrap_or(&TABLES);
+        let mut stats = LoadStats::default();
+
+        let (mut files_by_table, unknown_files) = self.group_parquet_files(dir_path)?;
+        files_by_table.retain(|table, _| tables.contains(table));
+        for file in unknown_files {
+            stats
+                .errors
+                .push(format!("{}: no table for file name", file));
+        }
+
+        for table in tables {
+            let Some(file_count) = files_by_table.get(table) else {
+                continue;
+            };
+            let pattern = format!("{}/**/{}_*.parquet", dir_path, table);
+            let query = format!("COPY {} FROM '{}' (FORMAT PARQUET)", table, pattern);
+            match self.conn.execute(&query, []) {
+                Ok(rows) => {
+                    stats.files_loaded += file_count;
+                    stats.rows_loaded.insert(table.to_string(), rows as u64);
+                }
+                Err(e) => stats.errors.push(format!("{}: {}", table, e)),
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Counts the parquet files below `dir_path` per table: the longest known table name
+    /// that prefixes the file name. Also returns the files that match no table
+    fn group_parquet_files(&self, dir_path: &str) -> Result<(HashMap<&str, u32>, Vec<String>)> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT file FROM glob(?) ORDER BY file")?;
+        let files = stmt
+            .query_map([format!("{}/**/*.parquet", dir_path)], |row| {
+                row.get::<_, String>(0)
+            })?
+            .collect::<Result<Vec<String>>>()?;
+
+        let mut files_by_table: HashMap<&str, u32> = HashMap::new();
+        let mut unknown_files = vec![];
+        for file in files {
+            let file_name = std::path::Path::new(&file)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            let table = TABLES
+                .iter()
+                .filter(|table| file_name.starts_with(&format!("{}_", table)))
+                .max_by_key(|table| table.len());
+            match table {
+                Some(table) => *files_by_table.entry(*table).or_default() += 1,
+                None => unknown_files.push(file),
+            }
+        }
+        Ok((files_by_table, unknown_files))
+    }
+
+    /// Loads every `{table}_*.parquet` file below `path` (recursive), fails on the first table
+    /// that can't be loaded. Files that match no table are ignored
+    fn load_parquet_files(&self, path: &str) -> Result<()> {
+        let path = path.trim_end_matches('/');
+        let (files_by_table, _) = self.group_parquet_files(path)?;
+        for table in TABLES
+            .iter()
+            .filter(|table| files_by_table.contains_key(*table))
+        {
+            let pattern = format!("{}/**/{}_*.parquet", path, table);
+            self.conn.execute_batch(&format!(
+                "COPY {} FROM '{}' (FORMAT PARQUET)",
+                table, pattern
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Creates an in-memory database with the `{table}_*.parquet` files below `dir_path`,
+    /// e.g. `swaps_1.parquet` is loaded into `swaps`, and the analytics views.
+    /// The schema of the files must match the tables exactly. Tables have no primary keys,
+    /// so files of overlapping exports are merged with duplicates
+    pub fn create_from_parquet_directory(dir_path: &str) -> Result<SolanaDatabase> {
+        let db = SolanaDatabase::new_with_primary_keys(false)?;
+        db.load_parquet_files(dir_path)?;
+        db.create_analytics_views()?;
+        Ok(db)
+    }
+
+    /// Same as `create_from_parquet_directory` for the files below `s3://{bucket}/{prefix}`.
+    /// httpfs reads the credentials from the `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`
+    /// and `AWS_DEFAULT_REGION` environment variables
+    pub fn create_from_s3_prefix(bucket: &str, prefix: &str) -> Result<SolanaDatabase> {
+        let db = SolanaDatabase::new_with_primary_keys(false)?;
+        db.conn.execute_batch("INSTALL httpfs; LOAD httpfs;")?;
+        let prefix = prefix.trim_matches('/');
+        db.load_parquet_files(&format!("s3://{}/{}", bucket, prefix))?;
+        db.create_analytics_views()?;
+        Ok(db)
+    }
+
+    pub fn print_table(&self, table: &str) -> Result<()> {
+        let limit = 10;
+        self.print_table_with_limit(table, limit)?;
+        Ok(())
+    }
+
+    pub fn print_table_with_limit(&self, table: &str, limit: i32) -> Result<()> {
+        let query = format!("SELECT * FROM {} limit {}", table, limit);
+        let results = self.query_to_json_file(&query)?;
+        print_json_objects_as_table(&results);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arctis_types::SwapHop;
+
+    const WSOL: &str = "So11111111111111111111111111111111111111112";
+
+    fn test_swap(
+        signer: &str,
+        slot: u64,
+        swap_type: SwapType,
+        token_in: &str,
+        amount_in: f64,
+        token_out: &str,
+        amount_out: f64,
+    ) -> SwapInfo {
+        SwapInfo {
+            slot,
+            block_time: 1_700_000_000 + slot as i64,
+            signer: signer.to_string(),
+            signature: format!("sig_{}_{}", signer, slot),
+            error: false,
+            dex: DexType::Pumpfun,
+            swap_type,
+            amount_in,
+            token_in: token_in.to_string(),
+            amount_out,
+            token_out: token_out.to_string(),
+            routing_path: vec![],
+        }
+    }
+
+    fn buy(signer: &str, slot: u64, token: &str, sol: f64, amount: f64) -> SwapInfo {
+        test_swap(signer, slot, SwapType::Buy, WSOL, sol, token, amount)
+    }
+
+    fn sell(signer: &str, slot: u64, token: &str, amount: f64, sol: f64) -> SwapInfo {
+        test_swap(signer, slot, SwapType::Sell, token, amount, WSOL, sol)
+    }
+
+    fn insert_swaps(db: &mut SolanaDatabase, swaps: &[SwapInfo]) {
+        let swaps: Vec<&SwapInfo> = swaps.iter().collect();
+        db.insert_swaps_bulk(&swaps).unwrap();
+    }
+
+    #[test]
+    fn test_get_wallet_pnl() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                // token A: fully closed with 0.5 SOL profit
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                buy("wallet1", 2, "TokenA", 0.5, 500.0),
+                sell("wallet1", 3, "TokenA", 1500.0, 2.0),
+                // token B: still open, not realized
+                buy("wallet1", 4, "TokenB", 1.0, 100.0),
+                sell("wallet1", 5, "TokenB", 50.0, 0.75),
+                // other wallet is ignored
+                buy("wallet2", 6, "TokenA", 3.0, 100.0),
+            ],
+        );
+
+        let pnl = db.get_wallet_pnl("wallet1", WSOL).unwrap();
+        assert_eq!(pnl.realized_pnl, 0.5);
+        assert_eq!(pnl.total_bought_quote, 2.5);
+        assert_eq!(pnl.total_sold_quote, 2.75);
+        assert_eq!(pnl.trade_count, 5);
+        assert_eq!(pnl.tokens_traded, vec!["TokenA", "TokenB"]);
+    }
+
+    #[test]
+    fn test_get_top_traders() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 2, "TokenA", 1000.0, 1.5),
+                buy("wallet2", 3, "TokenA", 5.0, 5000.0),
+                buy("wallet2", 4, "TokenB", 2.0, 10.0),
+                buy("wallet3", 5, "TokenB", 0.5, 10.0),
+            ],
+        );
+
+        let traders = db.get_top_traders(2, WSOL, None).unwrap();
+        assert_eq!(traders.len(), 2);
+        assert_eq!(traders[0].signer, "wallet2");
+        assert_eq!(traders[0].total_volume_quote, 7.0);
+        assert_eq!(traders[0].buy_volume, 7.0);
+        assert_eq!(traders[0].sell_volume, 0.0);
+        assert_eq!(traders[0].trade_count, 2);
+        assert_eq!(traders[0].unique_tokens, 2);
+        assert_eq!(traders[1].signer, "wallet1");
+        assert_eq!(traders[1].total_volume_quote, 2.5);
+
+        // only the last swap is recent enough
+        let traders = db.get_top_traders(10, WSOL, Some(1_700_000_005)).unwrap();
+        assert_eq!(traders.len(), 1);
+        assert_eq!(traders[0].signer, "wallet3");
+    }
+
+    fn test_block(slot: u64) -> ProcessedBlock {
+        ProcessedBlock {
+            slot,
+            block_time: 1_700_000_000 + slot as i64,
+            parent_slot: slot - 1,
+            transaction_count: 1,
+            blockhash: format!("blockhash{}", slot),
+            leader: Some("leader".to_string()),
+            rewards: Some(5000),
+        }
+    }
+
+    #[test]
+    fn test_get_swaps_paginated() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let swaps: Vec<SwapInfo> = (0..25)
+            .map(|slot| buy("wallet1", slot, "TokenA", 1.0, 1000.0))
+            .collect();
+        insert_swaps(&mut db, &swaps);
+
+        let first = db.get_swaps_paginated(0, 10).unwrap();
+        assert_eq!(first.rows.len(), 10);
+        assert_eq!(first.page, 0);
+        assert_eq!(first.total_pages, 3);
+        assert_eq!(first.total_rows, 25);
+        assert_eq!(first.rows[0]["slot"], 0);
+
+        let second = db.get_swaps_paginated(1, 10).unwrap();
+        assert_eq!(second.rows.len(), 10);
+        assert_eq!(second.page, 1);
+        assert_eq!(second.rows[0]["slot"], 10);
+
+        let last = db.get_swaps_paginated(2, 10).unwrap();
+        assert_eq!(last.rows.len(), 5);
+        assert_eq!(last.page, 2);
+        assert_eq!(last.rows[4]["slot"], 24);
+
+        let out_of_range = db.get_swaps_paginated(3, 10).unwrap();
+        assert!(out_of_range.rows.is_empty());
+        assert_eq!(out_of_range.total_pages, 3);
+    }
+
+    #[test]
+    fn test_create_default_indexes() {
+        let db = SolanaDatabase::new().unwrap();
+        db.create_default_indexes().unwrap();
+        // creating them twice is a no-op
+        db.create_default_indexes().unwrap();
+
+        let index_count: i64 = db
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM duckdb_indexes() WHERE index_name LIKE 'idx_%'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(index_count, 8);
+    }
+
+    fn test_token(mint: &str) -> NewToken {
+        NewToken {
+            block_time: 1_700_000_000,
+            slot: 1,
+            signature: format!("sig_{}", mint),
+            signer: "wallet1".to_string(),
+            factory: "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P".to_string(),
+            mint: mint.to_string(),
+            decimals: 6,
+            name: "Token".to_string(),
+            symbol: "TKN".to_string(),
+            uri: "".to_string(),
+            initial_supply: Some(1_000_000),
+            supply: Some(1_000_000),
+            seller_fee_basis_points: None,
+            creators: None,
+        }
+    }
+
+    fn test_token_transfer(from: &str, to: &str, amount: f64, token: &str) -> SplTokenTransfer {
+        SplTokenTransfer {
+            slot: 1,
+            block_time: 1_700_000_000,
+            signature: format!("sig_{}_{}_{}", from, to, amount),
+            from_acc: format!("{}_ata", from),
+            to_acc: format!("{}_ata", to),
+            amount,
+            raw_amount: amount as u64,
+            authority: Some(from.to_string()),
+            from: Some(from.to_string()),
+            to: Some(to.to_string()),
+            decimals: Some(6),
+            token: Some(token.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_token_transfers_decoded() {
+        let mut db = SolanaDatabase::new().unwrap();
+        // 0 decimals and more than 2^53 raw units
+        let mut token = test_token("TokenA");
+        token.decimals = 0;
+        db.insert_tokens_bulk(&vec![&token]).unwrap();
+        let mut large = test_token_transfer("pool", "wallet1", 0.0, "TokenA");
+        large.raw_amount = 9_007_199_254_740_993;
+        let small = test_token_transfer("pool", "wallet2", 1_500_000.0, "TokenB");
+        db.insert_token_transfers_bulk(&vec![&large, &small])
+            .unwrap();
+
+        let transfers = db.get_token_transfers_decoded(None).unwrap();
+        assert_eq!(transfers.len(), 2);
+        let transfers = db.get_token_transfers_decoded(Some("TokenA")).unwrap();
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].raw_amount, 9_007_199_254_740_993);
+        assert_eq!(transfers[0].decimals, Some(0));
+        assert_eq!(transfers[0].from.as_deref(), Some("pool"));
+        assert_eq!(transfers[0].to.as_deref(), Some("wallet1"));
+
+        // unknown token: decimals of the transfer
+        let transfers = db.get_token_transfers_decoded(Some("TokenB")).unwrap();
+        assert_eq!(transfers[0].amount, 1.5);
+        assert_eq!(transfers[0].raw_amount, 1_500_000);
+        assert_eq!(transfers[0].decimals, Some(6));
+    }
+
+    #[test]
+    fn test_daily_summary() {
+        let mut db = SolanaDatabase::new().unwrap();
+        // block times are 1_700_000_000 + slot, 2023-11-15 starts at slot 6400
+        for slot in [1, 2, 7000] {
+            db.insert_block(&test_block(slot)).unwrap();
+        }
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 2, "TokenA", 1000.0, 1.5),
+                buy("wallet2", 2, "TokenA", 0.5, 500.0),
+                buy("wallet1", 7000, "TokenA", 2.0, 1000.0),
+            ],
+        );
+        let tokens = [test_token("TokenA")];
+        db.insert_tokens_bulk(&tokens.iter().collect()).unwrap();
+        db.conn
+            .execute_batch(
+                "
+      INSERT INTO transactions (slot, block_time, signature) VALUES
+        (1, 1700000001, 'sig_1'),
+        (2, 1700000002, 'sig_2'),
+        (7000, 1700007000, 'sig_3');
+      ",
+            )
+            .unwrap();
+
+        let summaries = db.aggregate_to_daily_summary().unwrap();
+        assert_eq!(
+            summaries,
+            vec![
+                DailySummary {
+                    date: "2023-11-14".to_string(),
+                    slot_count: 2,
+                    tx_count: 2,
+                    swap_count: 3,
+                    new_token_count: 1,
+                    unique_traders: 2,
+                    total_sol_volume: 3.0,
+                    total_buy_volume: 1.5,
+                    total_sell_volume: 1.5,
+                },
+                DailySummary {
+                    date: "2023-11-15".to_string(),
+                    slot_count: 1,
+                    tx_count: 1,
+                    swap_count: 1,
+                    new_token_count: 0,
+                    unique_traders: 1,
+                    total_sol_volume: 2.0,
+                    total_buy_volume: 2.0,
+                    total_sell_volume: 0.0,
+                },
+            ]
+        );
+
+        let path = std::env::temp_dir().join(format!("arctis_daily_{}.csv", std::process::id()));
+        db.write_daily_summaries_to_csv(path.to_str().unwrap())
+            .unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("date,slot_count,tx_count"));
+        assert!(lines[1].starts_with("2023-11-14,2,2,3,1,2,"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_new_with_config_settings() {
+        let db = SolanaDatabase::new_with_config(DatabaseConfig {
+            path: None,
+            mode: DatabaseMode::InMemory,
+            with_primary_keys: true,
+            s3: None,
+            create_indexes_on_init: false,
+            with_analytics_views: false,
+            wal_mode: false,
+            memory_limit_gb: Some(1.5),
+            threads: Some(2),
+        })
+        .unwrap();
+        assert_eq!(db.get_setting::<u32>("threads").unwrap(), 2);
+
+        // compare with DuckDB's formatting of the same limit
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("SET memory_limit = '1.5GB';").unwrap();
+        let expected: String = conn
+            .query_row("SELECT current_setting('memory_limit')", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(db.get_setting::<String>("memory_limit").unwrap(), expected);
+        let default_limit: String = SolanaDatabase::new()
+            .unwrap()
+            .get_setting("memory_limit")
+            .unwrap();
+        assert_ne!(default_limit, expected);
+    }
+
+    #[test]
+    fn test_supply_history() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let tokens = [test_token("TokenA")];
+        db.insert_tokens_bulk(&tokens.iter().collect()).unwrap();
+        let burn = |signature: &str, amount: i128| SupplyChange {
+            signature: signature.to_string(),
+            ix_index: 0,
+            account: "account".to_string(),
+            mint: "TokenA".to_string(),
+            authority: "wallet1".to_string(),
+            amount,
+        };
+        let changes = [burn("sig_1", -100_000), burn("sig_2", -50_000)];
+        db.insert_supply_changes_bulk(&changes.iter().collect())
+            .unwrap();
+        db.conn
+            .execute_batch(
+                "
+      INSERT INTO transactions (slot, block_time, signature) VALUES
+        (2, 1700000002, 'sig_1'),
+        (5, 1700000005, 'sig_2');
+      ",
+            )
+            .unwrap();
+
+        let history = db.get_supply_history("TokenA").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            history[0],
+            SupplySnapshot {
+                signature: "sig_1".to_string(),
+                slot: 2,
+                block_time: 1_700_000_002,
+                delta: -100_000,
+                cumulative_supply: 900_000,
+            }
+        );
+        assert_eq!(history[1].cumulative_supply, 850_000);
+
+        assert_eq!(db.get_current_supply("TokenA").unwrap(), 850_000);
+        assert_eq!(db.get_supply_at_slot("TokenA", 1).unwrap(), 1_000_000);
+        assert_eq!(db.get_supply_at_slot("TokenA", 4).unwrap(), 900_000);
+        assert_eq!(db.get_current_supply("unknown").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_token_holder_balances() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let transfers = [
+            test_token_transfer("pool", "wallet1", 1000.0, "TokenA"),
+            test_token_transfer("pool", "wallet2", 500.0, "TokenA"),
+            test_token_transfer("wallet1", "wallet3", 300.0, "TokenA"),
+            // wallet2 sold everything
+            test_token_transfer("wallet2", "pool", 500.0, "TokenA"),
+            test_token_transfer("pool", "wallet2", 50.0, "TokenB"),
+        ];
+        db.insert_token_transfers_bulk(&transfers.iter().collect())
+            .unwrap();
+
+        let balances = db.get_token_holder_balances("TokenA").unwrap();
+        assert_eq!(
+            balances,
+            vec![
+                TokenHolderBalance {
+                    holder: "wallet1".to_string(),
+                    net_amount: 700.0,
+                    buy_amount: 1000.0,
+                    sell_amount: 300.0,
+                },
+                TokenHolderBalance {
+                    holder: "wallet3".to_string(),
+                    net_amount: 300.0,
+                    buy_amount: 300.0,
+                    sell_amount: 0.0,
+                },
+            ]
+        );
+        assert_eq!(db.get_holder_count("TokenA").unwrap(), 2);
+        assert_eq!(db.get_holder_count("TokenB").unwrap(), 1);
+        assert_eq!(db.get_holder_count("TokenC").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_token_metadata_updates() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let tokens = [test_token("TokenA")];
+        db.insert_tokens_bulk(&tokens.iter().collect()).unwrap();
+
+        let update = |slot: u64, mint: Option<&str>, name: Option<&str>| MetadataUpdate {
+            slot,
+            block_time: 1_700_000_000,
+            signature: format!("sig_update_{}", slot),
+            metadata_account: "metadata".to_string(),
+            mint: mint.map(|m| m.to_string()),
+            name: name.map(|n| n.to_string()),
+            symbol: name.map(|_| "NEW".to_string()),
+            uri: None,
+            seller_fee_basis_points: None,
+            update_authority: None,
+            primary_sale_happened: None,
+            is_mutable: None,
+        };
+        let updates = [
+            update(2, Some("TokenA"), Some("Renamed")),
+            // authority change only
+            update(3, Some("TokenA"), None),
+            update(4, None, Some("Unknown mint")),
+            update(5, Some("TokenB"), Some("Not indexed")),
+        ];
+        let inserted = db
+            .insert_token_metadata_updates_bulk(&updates.iter().collect())
+            .unwrap();
+        assert_eq!(inserted, 3);
+
+        let token = db.get_token("TokenA").unwrap().unwrap();
+        assert_eq!(token.name, "Renamed");
+        assert_eq!(token.symbol, "NEW");
+        assert_eq!(token.uri, "");
+
+        let history = db.get_token_metadata_updates(2).unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(
+            history[0],
+            TokenMetadataUpdate {
+                slot: 2,
+                signature: "sig_update_2".to_string(),
+                mint: "TokenA".to_string(),
+                old_name: Some("Token".to_string()),
+                new_name: Some("Renamed".to_string()),
+                old_symbol: Some("TKN".to_string()),
+                new_symbol: Some("NEW".to_string()),
+            }
+        );
+        assert_eq!(history[1].old_name, Some("Renamed".to_string()));
+        assert_eq!(history[1].new_name, None);
+        assert_eq!(history[2].old_name, None);
+        assert_eq!(db.get_token_metadata_updates(4).unwrap().len(), 1);
+
+        db.upsert_token_metadata("TokenA", None, None, Some("https://example.com"))
+            .unwrap();
+        let token = db.get_token("TokenA").unwrap().unwrap();
+        assert_eq!(token.name, "Renamed");
+        assert_eq!(token.uri, "https://example.com");
+    }
+
+    #[test]
+    fn test_detect_wash_trading_candidates() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 2, "TokenA", 1000.0, 1.5),
+                buy("wallet1", 3, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 4, "TokenA", 1000.0, 0.5),
+                // sold too late
+                buy("wallet2", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet2", 10, "TokenA", 1000.0, 1.0),
+                // single round trip
+                buy("wallet3", 1, "TokenB", 1.0, 1000.0),
+                sell("wallet3", 2, "TokenB", 1000.0, 1.0),
+            ],
+        );
+        db.conn
+            .execute_batch(
+                "
+      INSERT INTO transactions (slot, signature, signer, fee) VALUES
+        (1, 'sig_wallet1_1', 'wallet1', 5000),
+        (2, 'sig_wallet1_2', 'wallet1', 5000),
+        (3, 'sig_wallet1_3', 'wallet1', 5000),
+        (4, 'sig_wallet1_4', 'wallet1', 5000);
+      ",
+            )
+            .unwrap();
+
+        // (1, 2) and (3, 4), (1, 4) is more than 2 slots apart
+        let candidates = db.detect_wash_trading_candidates(2, 2).unwrap();
+        assert_eq!(candidates.len(), 1);
+        let candidate = &candidates[0];
+        assert_eq!(candidate.wallet, "wallet1");
+        assert_eq!(candidate.token, "TokenA");
+        assert_eq!(candidate.round_trips, 2);
+        assert!((candidate.total_volume - 4.0).abs() < 1e-6);
+        assert!((candidate.total_fees_paid - 0.00002).abs() < 1e-12);
+
+        // (1, 4) counts with a larger gap
+        let candidates = db.detect_wash_trading_candidates(1, 3).unwrap();
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].round_trips, 3);
+        assert_eq!(candidates[1].wallet, "wallet3");
+        assert_eq!(candidates[1].total_fees_paid, 0.0);
+    }
+
+    #[test]
+    fn test_swap_graph() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                buy("wallet2", 2, "TokenA", 2.0, 1500.0),
+                sell("wallet1", 3, "TokenA", 1000.0, 1.5),
+                buy("wallet1", 4, "TokenB", 0.1, 100.0),
+                test_swap("wallet1", 5, SwapType::Token, "TokenA", 10.0, "TokenB", 5.0),
+            ],
+        );
+
+        let graph = db.export_swap_graph(0, 0.0).unwrap();
+        assert_eq!(graph.nodes, vec![WSOL, "TokenA", "TokenB"]);
+        assert_eq!(graph.edges.len(), 4);
+        assert_eq!(
+            graph.edges[0],
+            SwapEdge {
+                from_token: WSOL.to_string(),
+                to_token: "TokenA".to_string(),
+                volume: 3.0,
+                trade_count: 2,
+            }
+        );
+        assert_eq!(graph.edges[1].from_token, "TokenA");
+        assert_eq!(graph.edges[1].to_token, WSOL);
+        assert_eq!(graph.edges[3].volume, 0.0);
+
+        let graph = db.export_swap_graph(3, 1.0).unwrap();
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!(graph.edges[0].volume, 1.5);
+        assert_eq!(graph.nodes, vec![WSOL, "TokenA"]);
+
+        let path = std::env::temp_dir().join(format!("arctis_swaps_{}.dot", std::process::id()));
+        db.export_swap_graph_to_dot(path.to_str().unwrap()).unwrap();
+        let dot = std::fs::read_to_string(&path).unwrap();
+        assert!(dot.starts_with("digraph swaps {"));
+        assert!(dot.contains(&format!(
+            "\"{}\" -> \"TokenA\" [label=\"3.00 SOL (2)\"];",
+            WSOL
+        )));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_sol_transfer_network() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let transfer = |slot: u64, from: &str, to: &str, sol: f64| SolTransfer {
+            slot,
+            block_time: 1_700_000_000 + slot as i64,
+            signature: format!("sig_{}", slot),
+            from: from.to_string(),
+            to: to.to_string(),
+            lamports: (sol * 1e9) as u64,
+            sol,
+        };
+        let transfers = [
+            transfer(1, "root", "walletA", 1.5),
+            transfer(2, "root", "walletA", 0.5),
+            transfer(3, "walletA", "walletB", 1.0),
+            transfer(4, "walletB", "walletC", 1.0),
+            transfer(5, "walletC", "walletD", 1.0),
+            transfer(6, "walletE", "root", 0.01),
+            transfer(7, "walletX", "walletY", 5.0),
+        ];
+        db.insert_sol_transfer_bulk(&transfers.iter().collect())
+            .unwrap();
+
+        let network = db.get_sol_transfer_network(1, "root", 0.0).unwrap();
+        assert_eq!(network.nodes, vec!["root", "walletA", "walletE"]);
+        assert_eq!(
+            network.edges[0],
+            SolEdge {
+                from: "root".to_string(),
+                to: "walletA".to_string(),
+                total_sol: 2.0,
+                transfer_count: 2,
+            }
+        );
+        assert_eq!(network.edges[1].from, "walletE");
+
+        let network = db.get_sol_transfer_network(2, "root", 0.1).unwrap();
+        assert_eq!(network.nodes, vec!["root", "walletA", "walletB"]);
+        assert_eq!(network.edges.len(), 2);
+
+        // capped at 3 hops: walletC -> walletD is not included
+        let network = db.get_sol_transfer_network(10, "root", 0.1).unwrap();
+        assert_eq!(network.nodes, vec!["root", "walletA", "walletB", "walletC"]);
+        assert_eq!(network.edges.len(), 3);
+
+        let network = db.get_sol_transfer_network(2, "unknown", 0.0).unwrap();
+        assert_eq!(network.nodes, vec!["unknown"]);
+        assert!(network.edges.is_empty());
+
+        let path =
+            std::env::temp_dir().join(format!("arctis_sol_network_{}.dot", std::process::id()));
+        db.export_sol_transfer_network_to_dot(1, "root", 0.1, path.to_str().unwrap())
+            .unwrap();
+        let dot = std::fs::read_to_string(&path).unwrap();
+        assert!(dot.starts_with("digraph sol_transfers {"));
+        assert!(dot.contains("\"root\" [shape=doublecircle];"));
+        assert!(dot.contains("\"root\" -> \"walletA\" [label=\"2.00 SOL (2)\"];"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_token_first_buyers() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet2", 3, "TokenA", 0.5, 400.0),
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                // second buy of wallet1 doesn't count
+                buy("wallet1", 2, "TokenA", 1.0, 900.0),
+                buy("wallet3", 3, "TokenA", 2.0, 1500.0),
+                buy("wallet4", 1, "TokenB", 1.0, 1000.0),
+                sell("wallet1", 5, "TokenA", 950.0, 3.0),
+            ],
+        );
+
+        let buyers = db.get_token_first_buyers("TokenA", 10).unwrap();
+        assert_eq!(buyers.len(), 3);
+        assert_eq!(
+            buyers[0],
+            FirstBuyerStats {
+                signer: "wallet1".to_string(),
+                buy_slot: 1,
+                buy_block_time: 1_700_000_001,
+                amount_sol_spent: 1.0,
+                amount_token_received: 1000.0,
+                rank: 1,
+            }
+        );
+        // same slot: ordered by signature
+        assert_eq!(buyers[1].signer, "wallet2");
+        assert_eq!(buyers[2].signer, "wallet3");
+        assert_eq!(buyers[2].rank, 3);
+        assert_eq!(db.get_token_first_buyers("TokenA", 1).unwrap().len(), 1);
+
+        // half of the 1900 tokens for 2 SOL sold for 3 SOL
+        let pnl = db.get_mint_sniper_pnl("TokenA", "wallet1").unwrap();
+        assert!((pnl - 2.0).abs() < 1e-6);
+        // nothing sold yet
+        assert_eq!(db.get_mint_sniper_pnl("TokenA", "wallet2").unwrap(), 0.0);
+        assert_eq!(db.get_mint_sniper_pnl("TokenA", "unknown").unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_token_price_at_slot() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                // 0.001 SOL per token at slot 10, 0.002 at slot 20
+                buy("wallet1", 10, "TokenA", 1.0, 1000.0),
+                sell("wallet2", 20, "TokenA", 1000.0, 2.0),
+                buy("wallet1", 15, "TokenB", 1.0, 10.0),
+            ],
+        );
+
+        let price = |slot| db.get_token_price_at_slot("TokenA", slot, WSOL).unwrap();
+        assert!((price(0).unwrap() - 0.001).abs() < 1e-9);
+        assert!((price(14).unwrap() - 0.001).abs() < 1e-9);
+        // equal distance: the earlier swap
+        assert!((price(15).unwrap() - 0.001).abs() < 1e-9);
+        assert!((price(16).unwrap() - 0.002).abs() < 1e-9);
+        assert!((price(100).unwrap() - 0.002).abs() < 1e-9);
+        assert_eq!(
+            db.get_token_price_at_slot("TokenC", 10, WSOL).unwrap(),
+            None
+        );
+
+        let series = db.get_token_price_series("TokenA", WSOL, 5, 25, 5).unwrap();
+        let slots: Vec<u64> = series.iter().map(|(slot, _)| *slot).collect();
+        assert_eq!(slots, vec![5, 10, 15, 20, 25]);
+        let prices: Vec<f64> = series.iter().map(|(_, price)| *price).collect();
+        for (price, expected) in prices.iter().zip([0.001, 0.001, 0.001, 0.002, 0.002]) {
+            assert!((price - expected).abs() < 1e-9);
+        }
+        assert!(db
+            .get_token_price_series("TokenC", WSOL, 5, 25, 5)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_wallet_interactions() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 10, "TokenA", 1.0, 1000.0),
+                buy("wallet1", 20, "TokenB", 1.0, 1000.0),
+                buy("wallet1", 30, "TokenC", 1.0, 1000.0),
+                // same token and slot as wallet1
+                buy("bot", 10, "TokenA", 1.0, 1000.0),
+                sell("bot", 20, "TokenB", 1000.0, 1.0),
+                // same token, different slot
+                buy("wallet2", 31, "TokenC", 1.0, 1000.0),
+                // same slot, different token
+                buy("wallet3", 10, "TokenB", 1.0, 1000.0),
+                buy("wallet4", 30, "TokenC", 1.0, 1000.0),
+            ],
+        );
+
+        let interactions = db.find_wallet_interactions("wallet1", 1).unwrap();
+        assert_eq!(
+            interactions,
+            vec![
+                WalletInteraction {
+                    other_wallet: "bot".to_string(),
+                    shared_tokens: vec!["TokenA".to_string(), "TokenB".to_string()],
+                    shared_token_count: 2,
+                    first_shared_trade_slot: 10,
+                },
+                WalletInteraction {
+                    other_wallet: "wallet4".to_string(),
+                    shared_tokens: vec!["TokenC".to_string()],
+                    shared_token_count: 1,
+                    first_shared_trade_slot: 30,
+                },
+            ]
+        );
+        assert_eq!(db.find_wallet_interactions("wallet1", 2).unwrap().len(), 1);
+
+        assert_eq!(
+            db.find_co_snipers("TokenC", 1).unwrap(),
+            vec!["wallet1", "wallet2", "wallet4"]
+        );
+        assert_eq!(
+            db.find_co_snipers("TokenC", 0).unwrap(),
+            vec!["wallet1", "wallet4"]
+        );
+        assert!(db.find_co_snipers("TokenB", 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_token_launch_stats() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let tokens = [
+            test_token("TokenA"),
+            test_token("TokenB"),
+            test_token("TokenC"),
+        ];
+        db.insert_tokens_bulk(&tokens.iter().collect()).unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                // TokenA: first traded by a sell (e.g. creator dev sell), first buy later
+                sell("wallet1", 5, "TokenA", 100.0, 0.1),
+                buy("wallet2", 7, "TokenA", 1.0, 1000.0),
+                buy("wallet3", 9, "TokenA", 1.0, 900.0),
+                // TokenB: sniped in the creation slot
+                buy("sniper", 1, "TokenB", 2.0, 2000.0),
+            ],
+        );
+
+        let stats = db.get_token_launch_stats("TokenA").unwrap();
+        assert_eq!(
+            stats,
+            TokenLaunchStats {
+                mint: "TokenA".to_string(),
+                create_block_time: 1_700_000_000,
+                first_trade_block_time: Some(1_700_000_005),
+                first_trade_slot: Some(5),
+                first_buyer: Some("wallet2".to_string()),
+                slots_to_first_trade: Some(4),
+                seconds_to_first_trade: Some(5),
+            }
+        );
+
+        let untraded = db.get_token_launch_stats("TokenC").unwrap();
+        assert_eq!(untraded.first_trade_slot, None);
+        assert_eq!(untraded.first_buyer, None);
+        assert_eq!(untraded.slots_to_first_trade, None);
+        assert!(db.get_token_launch_stats("TokenD").is_err());
+
+        let fastest = db.get_fastest_sniped_tokens(10).unwrap();
+        let mints: Vec<_> = fastest.iter().map(|s| s.mint.as_str()).collect();
+        assert_eq!(mints, vec!["TokenB", "TokenA"]);
+        assert_eq!(fastest[0].slots_to_first_trade, Some(0));
+        assert_eq!(fastest[0].first_buyer, Some("sniper".to_string()));
+        assert_eq!(db.get_fastest_sniped_tokens(1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_get_new_tokens() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let pumpfun_token = test_token("TokenA");
+        let mut metaplex_token = test_token("TokenB");
+        metaplex_token.factory = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s".to_string();
+        metaplex_token.slot = 2;
+        metaplex_token.block_time = 1_700_000_001;
+        metaplex_token.initial_supply = None;
+        metaplex_token.supply = None;
+        db.insert_tokens_bulk(&vec![&pumpfun_token, &metaplex_token])
+            .unwrap();
+
+        let tokens = db.get_new_tokens(None, None).unwrap();
+        assert_eq!(tokens, vec![pumpfun_token.clone(), metaplex_token.clone()]);
+
+        let tokens = db
+            .get_new_tokens(Some("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P"), None)
+            .unwrap();
+        assert_eq!(tokens, vec![pumpfun_token.clone()]);
+
+        let tokens = db.get_new_tokens(None, Some(1_700_000_001)).unwrap();
+        assert_eq!(tokens, vec![metaplex_token.clone()]);
+
+        assert_eq!(db.get_token("TokenB").unwrap(), Some(metaplex_token));
+        assert_eq!(db.get_token("TokenC").unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_block_info() {
+        let mut db = SolanaDatabase::new().unwrap();
+        db.insert_block(&test_block(1)).unwrap();
+
+        let block = db.get_block_info(1).unwrap().unwrap();
+        assert_eq!(
+            block,
+            BlockInfo {
+                slot: 1,
+                block_time: 1_700_000_001,
+                blockhash: "blockhash1".to_string(),
+                leader: Some("leader".to_string()),
+                rewards: Some(5000),
+            }
+        );
+        assert_eq!(db.get_block_info(2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_block_transactions() {
+        let db = SolanaDatabase::new().unwrap();
+        db.conn
+            .execute_batch(
+                r#"
+      INSERT INTO transactions
+        (slot, block_time, signature, signer, error, compute_units, fee, version,
+         is_discarded, discard_reason, data)
+      VALUES
+        (1, 1700000001, 'sig_2', 'wallet2', true, 5000, 5000, 0, true, 'vote', NULL),
+        (1, 1700000001, 'sig_1', 'wallet1', false, 150000, 10000, -1, false, NULL,
+         '{"meta": {"fee": 10000}}'),
+        (2, 1700000002, 'sig_3', 'wallet1', false, 1000, 5000, 0, false, NULL, NULL);
+      "#,
+            )
+            .unwrap();
+
+        let transactions = db.get_block_transactions(1).unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(
+            transactions[0],
+            StoredTransaction {
+                slot: 1,
+                block_time: 1_700_000_001,
+                signature: "sig_1".to_string(),
+                signer: "wallet1".to_string(),
+                has_error: false,
+                compute_units: 150_000,
+                fee: 10_000,
+                version: -1,
+                is_discarded: false,
+                discard_reason: None,
+            }
+        );
+        assert_eq!(transactions[1].discard_reason.as_deref(), Some("vote"));
+        assert!(db.get_block_transactions(3).unwrap().is_empty());
+
+        let data = db.get_transaction_json("sig_1").unwrap().unwrap();
+        assert_eq!(data["meta"]["fee"], 10000);
+        assert_eq!(db.get_transaction_json("sig_2").unwrap(), None);
+        assert_eq!(db.get_transaction_json("unknown").unwrap(), None);
+    }
+
+    #[test]
+    fn test_upsert_skips_duplicates() {
+        let mut db = SolanaDatabase::new().unwrap();
+
+        assert_eq!(db.upsert_block(&test_block(1)).unwrap(), 1);
+        assert_eq!(db.upsert_block(&test_block(1)).unwrap(), 0);
+        assert_eq!(db.count_rows("blocks").unwrap(), 1);
+
+        let token_a = test_token("TokenA");
+        let token_b = test_token("TokenB");
+        assert_eq!(db.upsert_tokens_bulk(&[&token_a]).unwrap(), 1);
+        // partially failed batch can be re-run
+        assert_eq!(db.upsert_tokens_bulk(&[&token_a, &token_b]).unwrap(), 1);
+        assert_eq!(db.count_rows("tokens").unwrap(), 2);
+
+        // no primary key: rows are appended
+        let swap = buy("wallet1", 1, "TokenA", 1.0, 1000.0);
+        assert_eq!(db.upsert_swaps_bulk(&[&swap]).unwrap(), 1);
+        assert_eq!(db.upsert_swaps_bulk(&[&swap]).unwrap(), 1);
+        assert_eq!(db.count_rows("swaps").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_upsert_column_order() {
+        let mut db = SolanaDatabase::new().unwrap();
+        // re-adding a column moves it to the end of the table
+        db.conn
+            .execute_batch(
+                "ALTER TABLE sol_transfers DROP COLUMN src;
+                 ALTER TABLE sol_transfers ADD COLUMN src TEXT;",
+            )
+            .unwrap();
+
+        let transfer = SolTransfer {
+            slot: 1,
+            block_time: 1_700_000_000,
+            signature: "sig".to_string(),
+            from: "walletA".to_string(),
+            to: "walletB".to_string(),
+            lamports: 1_500_000_000,
+            sol: 1.5,
+        };
+        assert_eq!(db.upsert_sol_transfers_bulk(&[&transfer]).unwrap(), 1);
+        let (src, dst, lamports): (String, String, u64) = db
+            .conn
+            .query_row("SELECT src, dst, lamports FROM sol_transfers", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!(src, "walletA");
+        assert_eq!(dst, "walletB");
+        assert_eq!(lamports, 1_500_000_000);
+    }
+
+    #[test]
+    fn test_get_fee_percentiles() {
+        let mut db = SolanaDatabase::new().unwrap();
+        assert_eq!(db.get_fee_percentiles(&[0.5]).unwrap(), vec![0.0]);
+
+        // 200k CU at 1..=5 million micro-lamports/CU = 0.0002..0.001 SOL
+        let fees: Vec<ComputeBudgetProcessed> = (1..=5)
+            .map(|i| ComputeBudgetProcessed {
+                slot: 1,
+                block_time: 1_700_000_000,
+                signature: format!("sig_{}", i),
+                c_unit_limit: 200_000,
+                fee: i * 1_000_000,
+            })
+            .collect();
+        db.insert_compute_budget_bulk(&fees).unwrap();
+
+        let percentiles = db.get_fee_percentiles(&[0.0, 0.5, 1.0]).unwrap();
+        assert_eq!(percentiles.len(), 3);
+        assert!((percentiles[0] - 0.0002).abs() < 1e-9);
+        assert!((percentiles[1] - 0.0006).abs() < 1e-9);
+        assert!((percentiles[2] - 0.001).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lifetime_fees() {
+        let mut db = SolanaDatabase::new().unwrap();
+        // 200k CU at 1..=3 million micro-lamports/CU = 200k..600k lamports
+        let fees: Vec<ComputeBudgetProcessed> = (1..=3)
+            .map(|i| ComputeBudgetProcessed {
+                slot: 1,
+                block_time: 1_700_000_000,
+                signature: format!("sig_{}", i),
+                c_unit_limit: 200_000,
+                fee: i * 1_000_000,
+            })
+            .collect();
+        db.insert_compute_budget_bulk(&fees).unwrap();
+        db.conn
+            .execute_batch(
+                "
+      INSERT INTO transactions (slot, signature, signer, fee) VALUES
+        (1, 'sig_1', 'wallet1', 205000),
+        (1, 'sig_2', 'wallet1', 405000),
+        (1, 'sig_3', 'wallet2', 605000),
+        (1, 'sig_4', 'wallet1', 5000);
+      ",
+            )
+            .unwrap();
+
+        let stats = db.get_lifetime_fees("wallet1").unwrap();
+        assert_eq!(
+            stats,
+            WalletFeeStats {
+                total_base_fee_lamports: 15_000,
+                total_priority_fee_lamports: 600_000,
+                total_fee_lamports: 615_000,
+                transaction_count: 3,
+                average_priority_fee_lamports: 200_000.0,
+            }
+        );
+        assert_eq!(
+            db.get_lifetime_fees("unknown").unwrap().transaction_count,
+            0
+        );
+
+        let percentiles = db.get_fee_percentiles_by_wallet("wallet1").unwrap();
+        assert_eq!(percentiles.len(), 3);
+        assert!((percentiles[0] - 300_000.0).abs() < 1e-6);
+        assert!((percentiles[1] - 380_000.0).abs() < 1e-6);
+        assert!((percentiles[2] - 398_000.0).abs() < 1e-6);
+        assert_eq!(
+            db.get_fee_percentiles_by_wallet("unknown").unwrap(),
+            vec![0.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_wallet_swap_summaries() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                buy("wallet1", 2, "TokenB", 2.0, 500.0),
+                sell("wallet1", 3, "TokenA", 1000.0, 1.5),
+                buy("wallet1", 4, "TokenA", 0.5, 400.0),
+                buy("wallet2", 5, "TokenA", 8.0, 1000.0),
+                sell("wallet2", 6, "TokenA", 1000.0, 9.0),
+                buy("wallet3", 7, "TokenC", 1.0, 100.0),
+            ],
+        );
+
+        let summary = db.get_wallet_swap_summary("wallet1").unwrap();
+        assert_eq!(
+            summary,
+            WalletSwapSummary {
+                signer: "wallet1".to_string(),
+                buy_count: 3,
+                sell_count: 1,
+                total_sol_spent: 3.5,
+                total_sol_received: 1.5,
+                unique_tokens_bought: 2,
+                unique_tokens_sold: 1,
+                first_trade_slot: Some(1),
+                last_trade_slot: Some(4),
+            }
+        );
+
+        let empty = db.get_wallet_swap_summary("unknown").unwrap();
+        assert_eq!(empty.buy_count, 0);
+        assert_eq!(empty.total_sol_spent, 0.0);
+        assert_eq!(empty.first_trade_slot, None);
+
+        let summaries = db.get_all_wallet_summaries(2).unwrap();
+        let signers: Vec<&str> = summaries.iter().map(|s| s.signer.as_str()).collect();
+        assert_eq!(signers, vec!["wallet1", "wallet2"]);
+        assert_eq!(summaries[1].total_sol_received, 9.0);
+        assert_eq!(db.get_all_wallet_summaries(1).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_wallet_activity_and_tokens() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 2, "TokenA", 1000.0, 2.0),
+                buy("wallet1", 3, "TokenB", 4.0, 500.0),
+                buy("wallet2", 4, "TokenA", 8.0, 1000.0),
+            ],
+        );
+
+        let activity = db.get_wallet_activity("wallet1", WSOL, None).unwrap();
+        assert_eq!(activity.trade_count, 3);
+        assert_eq!(activity.total_volume_quote, 7.0);
+        assert_eq!(activity.buy_volume, 5.0);
+        assert_eq!(activity.sell_volume, 2.0);
+        assert_eq!(activity.unique_tokens, 2);
+
+        // only swaps since slot 3
+        let recent = db
+            .get_wallet_activity("wallet1", WSOL, Some(1_700_000_003))
+            .unwrap();
+        assert_eq!(recent.trade_count, 1);
+
+        let empty = db.get_wallet_activity("unknown", WSOL, None).unwrap();
+        assert_eq!(empty.trade_count, 0);
+        assert_eq!(empty.total_volume_quote, 0.0);
+
+        let tokens = db.get_wallet_tokens("wallet1", WSOL).unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].token, "TokenB");
+        assert_eq!(tokens[0].volume_quote, 4.0);
+        assert_eq!(tokens[1].token, "TokenA");
+        assert_eq!(tokens[1].volume_quote, 3.0);
+        assert_eq!(tokens[1].trade_count, 2);
+    }
+
+    #[test]
+    fn test_analytics_views() {
+        let mut db = SolanaDatabase::new().unwrap();
+        db.create_analytics_views().unwrap();
+        // idempotent
+        db.create_analytics_views().unwrap();
+
+        let tokens = [test_token("TokenA")];
+        db.insert_tokens_bulk(&tokens.iter().collect()).unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 2, "TokenA", 500.0, 0.75),
+                buy("wallet2", 3, "TokenA", 2.0, 1000.0),
+            ],
+        );
+        let fees: Vec<ComputeBudgetProcessed> = (1..=2)
+            .map(|i| ComputeBudgetProcessed {
+                slot: 1,
+                block_time: 1_700_000_000,
+                signature: format!("sig_{}", i),
+                c_unit_limit: 200_000,
+                fee: i * 1_000_000,
+            })
+            .collect();
+        db.insert_compute_budget_bulk(&fees).unwrap();
+
+        let query_f64 =
+            |query: &str| -> f64 { db.conn.query_row(query, [], |row| row.get(0)).unwrap() };
+
+        assert_eq!(
+            query_f64("SELECT SUM(volume_sol) FROM token_volume WHERE token = 'TokenA'"),
+            3.75
+        );
+        assert_eq!(
+            query_f64("SELECT SUM(volume_token) FROM token_volume"),
+            2500.0
+        );
+        assert_eq!(
+            query_f64("SELECT SUM(volume_sol) FROM wallet_activity WHERE signer = 'wallet1'"),
+            1.75
+        );
+        assert_eq!(query_f64("SELECT SUM(trade_share) FROM dex_share"), 1.0);
+        assert_eq!(
+            query_f64("SELECT trade_count FROM dex_share WHERE dex = 'Pumpfun'"),
+            3.0
+        );
+        assert_eq!(
+            query_f64("SELECT initial_price_sol FROM new_token_launches WHERE mint = 'TokenA'"),
+            0.001
+        );
+        assert_eq!(
+            query_f64("SELECT avg_priority_fee FROM fee_overview WHERE slot = 1"),
+            1_500_000.0
+        );
+
+        db.drop_analytics_views().unwrap();
+        assert!(db.conn.execute_batch("SELECT * FROM token_volume").is_err());
+    }
+
+    #[test]
+    fn test_compute_efficiency_stats() {
+        let mut db = SolanaDatabase::new().unwrap();
+        db.create_analytics_views().unwrap();
+        db.conn
+            .execute_batch(
+                "
+      INSERT INTO transactions (slot, signature, compute_units) VALUES
+        (1, 'sig_1', 150000),
+        (1, 'sig_2', 20000),
+        (1, 'sig_3', 90000),
+        (1, 'sig_4', 5000);
+      ",
+            )
+            .unwrap();
+        let fee = |signature: &str, c_unit_limit: u64| ComputeBudgetProcessed {
+            slot: 1,
+            block_time: 1_700_000_000,
+            signature: signature.to_string(),
+            c_unit_limit,
+            fee: 1_000_000,
+        };
+        // sig_4 has no limit and is ignored
+        let fees = vec![
+            fee("sig_1", 200_000),
+            fee("sig_2", 200_000),
+            fee("sig_3", 100_000),
+            fee("sig_4", 0),
+        ];
+        db.insert_compute_budget_bulk(&fees).unwrap();
+
+        let stats = db.get_compute_efficiency_stats().unwrap();
+        // 75%, 10%, 90%
+        assert!((stats.avg_efficiency_pct - 175.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.median_efficiency_pct, 75.0);
+        assert_eq!(stats.total_wasted_cu, 50_000 + 180_000 + 10_000);
+        assert_eq!(stats.worst_offenders, vec!["sig_2", "sig_1", "sig_3"]);
+    }
+
+    #[test]
+    fn test_account_lifecycle() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let event = |slot: u64, open: bool| {
+            let signature = format!("sig_{}", slot);
+            AccountProcessed {
+                slot,
+                block_time: 1_700_000_000 + slot as i64,
+                signature: signature.clone(),
+                account: AccountInfo {
+                    account: "ata".to_string(),
+                    owner: "wallet1".to_string(),
+                    open_tx: open.then(|| signature.clone()),
+                    init_tx: None,
+                    close_tx: (!open).then(|| signature.clone()),
+                    close_destination: (!open).then(|| "wallet1".to_string()),
+                    // only known when the account is created
+                    mint: open.then(|| "TokenA".to_string()),
+                    decimals: open.then_some(6),
+                },
+            }
+        };
+
+        // opened and closed in the same slot
+        db.insert_accounts_bulk(&vec![event(1, true), event(1, false)])
+            .unwrap();
+        let lifecycle = db.get_account_lifecycle("ata").unwrap();
+        assert_eq!(
+            lifecycle,
+            AccountLifecycle {
+                account: "ata".to_string(),
+                mint: Some("TokenA".to_string()),
+                owner: Some("wallet1".to_string()),
+                open_tx: Some("sig_1".to_string()),
+                close_tx: Some("sig_1".to_string()),
+                is_closed: true,
+            }
+        );
+
+        // reopened
+        db.insert_accounts_bulk(&vec![event(5, true)]).unwrap();
+        let lifecycle = db.get_account_lifecycle("ata").unwrap();
+        assert_eq!(lifecycle.open_tx, Some("sig_5".to_string()));
+        assert_eq!(lifecycle.close_tx, Some("sig_1".to_string()));
+        assert!(!lifecycle.is_closed);
+
+        assert!(db.get_account_lifecycle("missing").is_err());
+    }
+
+    #[test]
+    fn test_post_graduation_swaps() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let completion = BondingCurveCompletion {
+            slot: 5,
+            block_time: 1_700_000_005,
+            signature: "sig_complete".to_string(),
+            mint: "TokenA".to_string(),
+            bonding_curve: "curve".to_string(),
+            signer: "wallet1".to_string(),
+        };
+        db.insert_bonding_curve_completions_bulk(&vec![&completion])
+            .unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 3, "TokenA", 1.0, 1000.0),
+                // the completing buy is still on the curve
+                buy("wallet1", 5, "TokenA", 1.0, 1000.0),
+                sell("wallet2", 7, "TokenA", 1000.0, 2.0),
+                buy("wallet2", 8, "TokenB", 1.0, 1000.0),
+            ],
+        );
+
+        assert_eq!(
+            db.get_pumpfun_graduated_tokens().unwrap(),
+            HashSet::from(["TokenA".to_string()])
+        );
+        assert_eq!(db.tag_post_graduation_swaps().unwrap(), 1);
+
+        // new swaps are tagged without calling it again
+        insert_swaps(&mut db, &[buy("wallet3", 9, "TokenA", 1.0, 1000.0)]);
+        let count: u64 = db
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM swaps_with_graduation WHERE is_post_graduation",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_get_slowest_transactions() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let stats = |signature: &str, parse_us: u64, insert_us: u64| TransactionParseStats {
+            slot: 1,
+            signature: signature.to_string(),
+            parse_us,
+            insert_us,
+            ix_count: 3,
+        };
+        db.insert_parse_stats_bulk(&vec![
+            stats("sig_fast", 10, 5),
+            stats("sig_slow_parse", 500, 5),
+            stats("sig_slow_insert", 20, 600),
+        ])
+        .unwrap();
+
+        let slowest = db.get_slowest_transactions(2).unwrap();
+        assert_eq!(
+            slowest,
+            vec![
+                stats("sig_slow_insert", 20, 600),
+                stats("sig_slow_parse", 500, 5)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_tables() {
+        let db = SolanaDatabase::new().unwrap();
+        db.create_analytics_views().unwrap();
+
+        let mut expected: Vec<String> = TABLES.iter().map(|table| table.to_string()).collect();
+        expected.sort();
+        assert_eq!(db.list_tables().unwrap(), expected);
+        for table in TABLES {
+            assert!(db.has_table(table).unwrap());
+        }
+        // views are not tables
+        assert!(!db.has_table("token_volume").unwrap());
+        assert!(!db.has_table("missing").unwrap());
+
+        assert!(db.has_column("blocks", "blockhash").unwrap());
+        assert!(!db.has_column("blocks", "missing").unwrap());
+        assert!(!db.has_column("missing", "slot").unwrap());
+    }
+
+    #[test]
+    fn test_insert_swap_hops() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let hop = |amm: &str, input_mint: &str, output_mint: &str| SwapHop {
+            amm: amm.to_string(),
+            input_mint: input_mint.to_string(),
+            output_mint: output_mint.to_string(),
+            input_amount: 1_000,
+            output_amount: 2_000,
+        };
+        let mut routed = buy("wallet1", 1, "TokenA", 1.0, 1000.0);
+        routed.routing_path = vec![hop("amm1", WSOL, "USDC"), hop("amm2", "USDC", "TokenA")];
+        let direct = buy("wallet2", 2, "TokenA", 1.0, 1000.0);
+
+        let inserted = db.insert_swap_hops_bulk(&vec![&routed, &direct]).unwrap();
+        assert_eq!(inserted, 2);
+        let amms: Vec<String> = db
+            .conn
+            .prepare("SELECT amm FROM swap_hops WHERE signature = ?1 ORDER BY hop_index")
+            .unwrap()
+            .query_map(params![routed.signature], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(amms, vec!["amm1", "amm2"]);
+    }
+
+    #[test]
+    fn test_migrate_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "
+      CREATE TABLE blocks (slot BIGINT PRIMARY KEY, block_time BIGINT, parent_slot BIGINT, transaction_count INTEGER);
+      CREATE TABLE transactions (slot BIGINT, signature TEXT PRIMARY KEY, data JSON);
+      CREATE TABLE token_transfers (slot BIGINT, amount FLOAT);
+      CREATE TYPE SwapType AS ENUM ('Buy', 'Sell');
+      CREATE TYPE DexType AS ENUM ('Jupiterv6', 'Pumpfun');
+      CREATE TABLE swaps (slot BIGINT, dex DexType, swap_type SwapType);
+      INSERT INTO blocks VALUES (1, 1700000000, 0, 10);
+      INSERT INTO token_transfers VALUES (1, 1000.0);
+      INSERT INTO swaps VALUES (1, 'Pumpfun', 'Buy');
+      ",
+        )
+        .unwrap();
+        let mut db = SolanaDatabase::new_from_connection(conn);
+
+        let migrations = default_migrations();
+        let executed = db.migrate_schema(&migrations).unwrap();
+        assert_eq!(executed.len(), migrations.len());
+        assert_eq!(executed[0], "add_transactions_jito_tip_lamports");
+        assert!(db.has_column("blocks", "blockhash").unwrap());
+        assert!(db.has_column("blocks", "leader").unwrap());
+        assert!(db.has_column("transactions", "jito_tip_lamports").unwrap());
+        assert!(db.has_table("rewards").unwrap());
+        assert_eq!(db.count_rows("blocks").unwrap(), 1);
+        let amount: String = db
+            .conn
+            .query_row("SELECT amount FROM token_transfers", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(amount, "1000");
+        // values added to the enums since are accepted, existing rows keep their values
+        db.conn
+            .execute_batch("INSERT INTO swaps VALUES (2, 'Helium', 'Token')")
+            .unwrap();
+        assert_eq!(
+            db.count_rows_where("swaps", "dex = 'Pumpfun' AND swap_type = 'Buy'")
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            db.count_rows("schema_migrations").unwrap(),
+            migrations.len() as i64
+        );
+
+        // recorded migrations are skipped
+        assert!(db.migrate_schema(&migrations).unwrap().is_empty());
+
+        // a current schema only records the migrations
+        let mut db = SolanaDatabase::new().unwrap();
+        assert!(db.migrate_schema(&migrations).unwrap().is_empty());
+        assert_eq!(
+            db.count_rows("schema_migrations").unwrap(),
+            migrations.len() as i64
+        );
+    }
+
+    #[test]
+    fn test_merge_from_missing_table() {
+        let mut old_db = SolanaDatabase::new().unwrap();
+        insert_swaps(&mut old_db, &[buy("wallet1", 1, "TokenA", 1.0, 1000.0)]);
+        old_db.conn.execute_batch("DROP TABLE fees").unwrap();
+
+        let mut db = SolanaDatabase::new().unwrap();
+        db.merge_from(&old_db).unwrap();
+        assert_eq!(db.count_rows("swaps").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_swap_counts() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let mut raydium_sell = sell("wallet2", 1000, "TokenB", 500.0, 4.0);
+        raydium_sell.dex = DexType::RaydiumAmm;
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 2, "TokenA", 1000.0, 1.5),
+                buy("wallet2", 3, "TokenB", 0.5, 500.0),
+                raydium_sell,
+            ],
+        );
+
+        let by_dex = db.count_swaps_by_dex().unwrap();
+        assert_eq!(by_dex.len(), 2);
+        assert_eq!(by_dex[&DexType::Pumpfun], 3);
+        assert_eq!(by_dex[&DexType::RaydiumAmm], 1);
+
+        // block times start at 1_700_000_000 + slot, the next hour starts at 1_700_002_800
+        let by_hour = db.count_swaps_by_hour(0).unwrap();
+        assert_eq!(by_hour, vec![(1_699_999_200, 3), (1_700_002_800, 1)]);
+        let by_hour = db.count_swaps_by_hour(1_700_000_002).unwrap();
+        assert_eq!(by_hour, vec![(1_699_999_200, 2), (1_700_002_800, 1)]);
+
+        let volume = db.sum_volume_by_token(WSOL, 10).unwrap();
+        assert_eq!(
+            volume,
+            vec![("TokenB".to_string(), 4.5), ("TokenA".to_string(), 2.5)]
+        );
+        let volume = db.sum_volume_by_token(WSOL, 1).unwrap();
+        assert_eq!(volume.len(), 1);
+    }
+
+    #[test]
+    fn test_insert_notifications() {
+        let mut db = SolanaDatabase::new().unwrap();
+        assert!(db.subscribe_inserts().is_none());
+        // no subscribers yet
+        db.enable_insert_notifications();
+        insert_swaps(&mut db, &[buy("wallet1", 1, "TokenA", 1.0, 1000.0)]);
+
+        let mut receiver = db.subscribe_inserts().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 2, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 3, "TokenA", 1000.0, 2.0),
+            ],
+        );
+        db.upsert_swaps_bulk(&[&buy("wallet2", 4, "TokenA", 1.0, 1000.0)])
+            .unwrap();
+
+        let slots: Vec<u64> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|notification| match notification {
+                InsertNotification::NewSwap(swap) => swap.slot,
+                other => panic!("unexpected notification {:?}", other),
+            })
+            .collect();
+        assert_eq!(slots, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_with_transaction() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let result: Result<()> = db.with_transaction(|db| {
+            insert_swaps(db, &[buy("wallet1", 1, "TokenA", 1.0, 1000.0)]);
+            // fails after the swaps were appended
+            db.conn
+                .execute_batch("INSERT INTO missing_table VALUES (1)")
+        });
+        assert!(result.is_err());
+        assert_eq!(db.count_rows("swaps").unwrap(), 0);
+
+        db.with_transaction(|db| {
+            insert_swaps(db, &[buy("wallet1", 1, "TokenA", 1.0, 1000.0)]);
+            Ok::<_, duckdb::Error>(())
+        })
+        .unwrap();
+        assert_eq!(db.count_rows("swaps").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_export_table() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                buy("wallet2", 2, "TokenA", 1.0, 1000.0),
+            ],
+        );
+
+        let dir = std::env::temp_dir();
+        let csv_path = dir.join(format!("arctis_export_{}.csv", std::process::id()));
+        let csv_path = csv_path.to_str().unwrap();
+        db.export_query(
+            "SELECT * FROM swaps WHERE signer = 'wallet1'",
+            csv_path,
+            ExportFormat::CSV,
+        )
+        .unwrap();
+        let csv = std::fs::read_to_string(csv_path).unwrap();
+        // header + 1 row
+        assert_eq!(csv.lines().count(), 2);
+        std::fs::remove_file(csv_path).unwrap();
+
+        let parquet_path = dir.join(format!("arctis_export_{}.parquet", std::process::id()));
+        let parquet_path = parquet_path.to_str().unwrap();
+        db.export_table("swaps", parquet_path, ExportFormat::PARQUET_ZSTD)
+            .unwrap();
+        assert_eq!(
+            db.get_file_size(parquet_path).unwrap() as u64,
+            std::fs::metadata(parquet_path).unwrap().len()
+        );
+        let db2 = SolanaDatabase::new().unwrap();
+        db2.load_parquet_table("swaps", parquet_path).unwrap();
+        assert_eq!(db2.count_rows("swaps").unwrap(), 2);
+        std::fs::remove_file(parquet_path).unwrap();
+    }
+
+    #[test]
+    fn test_export_to_parquet() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                buy("wallet2", 2, "TokenA", 1.0, 1000.0),
+                // next day
+                sell("wallet1", 86_400, "TokenA", 1000.0, 2.0),
+            ],
+        );
+
+        let dir = std::env::temp_dir().join(format!("arctis_partitioned_{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+        db.export_to_parquet("swaps", dir, ExportFormat::PARQUET_ZSTD, Some("block_time"))
+            .unwrap();
+        assert!(std::path::Path::new(&format!("{}/day=2023-11-14", dir)).is_dir());
+        assert!(std::path::Path::new(&format!("{}/day=2023-11-15", dir)).is_dir());
+
+        let db2 = SolanaDatabase::new().unwrap();
+        db2.load_parquet_table("swaps", &format!("{}/day=2023-11-14/*.parquet", dir))
+            .unwrap();
+        assert_eq!(db2.count_rows("swaps").unwrap(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+
+        let out_dir =
+            std::env::temp_dir().join(format!("arctis_export_all_{}", std::process::id()));
+        let out_dir = out_dir.to_str().unwrap();
+        let exported = db
+            .export_all_tables(out_dir, ExportFormat::PARQUET)
+            .unwrap();
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported["swaps"], 3);
+
+        let db3 = SolanaDatabase::new().unwrap();
+        db3.load_parquet_table("swaps", &format!("{}/swaps.parquet", out_dir))
+            .unwrap();
+        assert_eq!(db3.count_rows("swaps").unwrap(), 3);
+        std::fs::remove_dir_all(out_dir).unwrap();
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let mut db = SolanaDatabase::new().unwrap();
+        db.insert_block(&test_block(1)).unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 2, "TokenA", 1000.0, 2.0),
+            ],
+        );
+        let tokens = [test_token("TokenA")];
+        db.insert_tokens_bulk(&tokens.iter().collect()).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("arctis_backup_{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+        db.backup(dir).unwrap();
+
+        let restored = SolanaDatabase::restore(dir).unwrap();
+        for table in TABLES {
+            assert_eq!(
+                restored.count_rows(table).unwrap(),
+                db.count_rows(table).unwrap(),
+                "{}",
+                table
+            );
+        }
+        // the dex enum survives the round trip
+        assert_eq!(restored.get_swaps().unwrap(), db.get_swaps().unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_to_duckdb_file() {
+        let mut db = SolanaDatabase::new().unwrap();
+        db.insert_block(&test_block(1)).unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 2, "TokenA", 1000.0, 2.0),
+            ],
+        );
+
+        let path = std::env::temp_dir().join(format!("arctis_export_{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+        db.export_to_duckdb_file(path).unwrap();
+
+        let conn = Connection::open(path).unwrap();
+        let exported = SolanaDatabase::new_from_connection(conn);
+        assert_eq!(exported.list_tables().unwrap(), db.list_tables().unwrap());
+        assert_eq!(exported.count_rows("blocks").unwrap(), 1);
+        assert_eq!(exported.count_rows("swaps").unwrap(), 2);
+        assert!(exported.has_column("swaps", "dex").unwrap());
+        drop(exported);
+        std::fs::remove_file(path).unwrap();
+        let _ = std::fs::remove_file(format!("{}.wal", path));
+    }
+
+    #[test]
+    fn test_load_and_merge_parquet_directory() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 2, "TokenA", 1000.0, 2.0),
+            ],
+        );
+        let tokens = [test_token("TokenA")];
+        db.insert_tokens_bulk(&tokens.iter().collect()).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("arctis_merge_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("block_2")).unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        db.export_table("swaps", &path("swaps_1.parquet"), ExportFormat::PARQUET)
+            .unwrap();
+        db.export_table(
+            "swaps",
+            &path("block_2/swaps_2.parquet"),
+            ExportFormat::PARQUET,
+        )
+        .unwrap();
+        db.export_table("tokens", &path("tokens_1.parquet"), ExportFormat::PARQUET)
+            .unwrap();
+        db.export_table("tokens", &path("other_1.parquet"), ExportFormat::PARQUET)
+            .unwrap();
+
+        let db2 = SolanaDatabase::new_with_primary_keys(false).unwrap();
+        let stats = db2
+            .load_and_merge_parquet_directory(dir.to_str().unwrap(), None)
+            .unwrap();
+        assert_eq!(stats.files_loaded, 3);
+        assert_eq!(stats.rows_loaded["swaps"], 4);
+        assert_eq!(stats.rows_loaded["tokens"], 1);
+        assert!(!stats.rows_loaded.contains_key("token_transfers"));
+        assert_eq!(stats.errors.len(), 1);
+        assert_eq!(db2.count_rows("swaps").unwrap(), 4);
+
+        let db3 = SolanaDatabase::new().unwrap();
+        let stats = db3
+            .load_and_merge_parquet_directory(dir.to_str().unwrap(), Some(&["tokens"]))
+            .unwrap();
+        assert_eq!(stats.files_loaded, 1);
+        assert_eq!(db3.count_rows("swaps").unwrap(), 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_from_parquet_directory() {
+        let mut db = SolanaDatabase::new().unwrap();
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 2, "TokenA", 1000.0, 2.0),
+            ],
+        );
+        db.insert_block(&test_block(1)).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("arctis_from_parquet_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        db.export_to_parquet(
+            "swaps",
+            &path("swaps_1.parquet"),
+            ExportFormat::PARQUET,
+            None,
+        )
+        .unwrap();
+        db.export_to_parquet(
+            "blocks",
+            &path("blocks_1.parquet"),
+            ExportFormat::PARQUET,
+            None,
+        )
+        .unwrap();
+        db.export_to_parquet(
+            "tokens",
+            &path("unknown.parquet"),
+            ExportFormat::PARQUET,
+            None,
+        )
+        .unwrap();
+
+        let loaded = SolanaDatabase::create_from_parquet_directory(dir.to_str().unwrap()).unwrap();
+        assert_eq!(loaded.count_rows("swaps").unwrap(), 2);
+        assert_eq!(loaded.count_rows("blocks").unwrap(), 1);
+        assert_eq!(loaded.count_rows("tokens").unwrap(), 0);
+        assert!(loaded.get_block_info(1).unwrap().is_some());
+        // analytics views are created
+        loaded
+            .conn
+            .query_row("SELECT COUNT(*) FROM compute_efficiency", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .unwrap();
+
+        // a file that doesn't match the schema of its table fails
+        db.export_to_parquet(
+            "tokens",
+            &path("swaps_2.parquet"),
+            ExportFormat::PARQUET,
+            None,
+        )
+        .unwrap();
+        assert!(SolanaDatabase::create_from_parquet_directory(dir.to_str().unwrap()).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_get_swaps_filtered() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let mut raydium_buy = buy("wallet2", 3, "TokenB", 4.0, 500.0);
+        raydium_buy.dex = DexType::RaydiumAmm;
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 2, "TokenA", 1000.0, 2.0),
+                raydium_buy,
+                buy("wallet2", 4, "TokenA", 8.0, 1000.0),
+            ],
+        );
+
+        let count = |filter: SwapFilter| db.get_swaps_filtered(&filter).unwrap().len();
+
+        assert_eq!(count(SwapFilter::default()), 4);
+        assert_eq!(
+            count(SwapFilter::builder().with_dex(DexType::RaydiumAmm).build()),
+            1
+        );
+        assert_eq!(
+            count(SwapFilter::builder().with_swap_type(SwapType::Sell).build()),
+            1
+        );
+        assert_eq!(
+            count(SwapFilter::builder().with_signer("wallet1").build()),
+            2
+        );
+        assert_eq!(count(SwapFilter::builder().with_token("TokenA").build()), 3);
+        assert_eq!(
+            count(SwapFilter::builder().with_slot_range(2, 3).build()),
+            2
+        );
+        assert_eq!(
+            count(SwapFilter::builder().with_min_amount_in(4.0).build()),
+            3
+        );
+        assert_eq!(count(SwapFilter::builder().with_limit(3).build()), 3);
+
+        let swaps = db
+            .get_swaps_filtered(
+                &SwapFilter::builder()
+                    .with_signer("wallet2")
+                    .with_token("TokenA")
+                    .with_swap_type(SwapType::Buy)
+                    .build(),
+            )
+            .unwrap();
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].slot, 4);
+
+        // values are bound as parameters, not interpolated
+        assert_eq!(
+            count(SwapFilter::builder().with_signer("' OR 1=1 --").build()),
+            0
+        );
+    }
+
+    #[test]
+    fn test_merge_from() {
+        let mut db1 = SolanaDatabase::new().unwrap();
+        db1.insert_block(&test_block(1)).unwrap();
+        insert_swaps(&mut db1, &[buy("wallet1", 1, "TokenA", 1.0, 1000.0)]);
+
+        let mut db2 = SolanaDatabase::new().unwrap();
+        db2.insert_block(&test_block(2)).unwrap();
+        db2.insert_block(&test_block(3)).unwrap();
+        insert_swaps(
+            &mut db2,
+            &[
+                buy("wallet2", 2, "TokenA", 1.0, 1000.0),
+                sell("wallet2", 3, "TokenA", 1000.0, 1.0),
+            ],
+        );
+
+        db1.merge_from(&db2).unwrap();
+        assert_eq!(db1.count_rows("blocks").unwrap(), 3);
+        assert_eq!(db1.count_rows("swaps").unwrap(), 3);
+
+        // merging again does not duplicate primary key rows
+        db1.merge_from(&db2).unwrap();
+        assert_eq!(db1.count_rows("blocks").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_slot_coverage() {
+        let mut db = SolanaDatabase::new().unwrap();
+        assert_eq!(db.get_slot_range().unwrap(), None);
+        assert!(db.get_missing_slots().unwrap().is_empty());
+        assert_eq!(db.get_slot_coverage_pct(1, 10).unwrap(), 0.0);
+
+        for slot in [1, 2, 5, 6, 8] {
+            db.insert_block(&test_block(slot)).unwrap();
+        }
+        assert_eq!(db.get_slot_range().unwrap(), Some((1, 8)));
+        assert_eq!(db.get_missing_slots().unwrap(), vec![3, 4, 7]);
+        assert_eq!(db.get_slot_coverage_pct(1, 10).unwrap(), 50.0);
+        assert_eq!(db.get_slot_coverage_pct(5, 6).unwrap(), 100.0);
+        assert_eq!(db.get_slot_coverage_pct(10, 1).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_delete_blocks_before_slot() {
+        let mut db = SolanaDatabase::new().unwrap();
+        for slot in 1..=4 {
+            db.insert_block(&test_block(slot)).unwrap();
+        }
+        insert_swaps(
+            &mut db,
+            &[
+                buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+                buy("wallet1", 2, "TokenA", 1.0, 1000.0),
+                sell("wallet1", 4, "TokenA", 2000.0, 3.0),
+            ],
+        );
+        let tokens = [test_token("TokenA")];
+        db.insert_tokens_bulk(&tokens.iter().collect()).unwrap();
+
+        let deleted = db.delete_blocks_before_slot(2).unwrap();
+        assert_eq!(deleted["blocks"], 1);
+        assert_eq!(deleted["swaps"], 1);
+        assert_eq!(deleted["tx_programs"], 0);
+        assert!(!deleted.contains_key("tokens"));
+        assert_eq!(db.count_rows("tokens").unwrap(), 1);
+
+        // test_block(3) has block_time 1_700_000_003
+        let deleted = db.delete_blocks_before_block_time(1_700_000_003).unwrap();
+        assert_eq!(deleted["blocks"], 1);
+        assert_eq!(deleted["swaps"], 1);
+
+        let deleted = db.prune_to_last_n_slots(0).unwrap();
+        assert_eq!(deleted["blocks"], 1);
+        assert_eq!(db.count_rows("blocks").unwrap(), 1);
+        assert_eq!(db.count_rows("swaps").unwrap(), 1);
+
+        let deleted = db.delete_blocks_before_block_time(i64::MAX).unwrap();
+        assert_eq!(deleted["blocks"], 1);
+        assert!(db.prune_to_last_n_slots(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_discard_reason_stats() {
+        let db = SolanaDatabase::new().unwrap();
+        assert!(db.get_discard_reason_stats().unwrap().is_empty());
+        assert_eq!(db.get_non_discarded_ratio().unwrap(), 0.0);
+
+        db.conn
+            .execute_batch(
+                "
+      INSERT INTO transactions (slot, signature, is_discarded, discard_reason) VALUES
+        (1, 'sig1', true, 'Vote'),
+        (1, 'sig2', true, 'Vote'),
+        (1, 'sig3', true, 'Processed'),
+        (1, 'sig4', false, 'Unknown'),
+        (1, 'sig5', false, NULL);
+      ",
+            )
+            .unwrap();
+
+        let stats = db.get_discard_reason_stats().unwrap();
+        assert_eq!(stats.len(), 4);
+        assert_eq!(stats["Vote"], 2);
+        assert_eq!(stats["Processed"], 1);
+        assert_eq!(stats["Unknown"], 1);
+        assert_eq!(stats["None"], 1);
+        assert_eq!(db.get_non_discarded_ratio().unwrap(), 0.4);
+    }
+
+    #[test]
+    fn test_cant_discard_programs() {
+        let db = SolanaDatabase::new().unwrap();
+        db.conn
+            .execute_batch(
+                "
+      INSERT INTO cant_discard VALUES
+        (1, 'sig1', 'ProgramA', 'deposit'),
+        (1, 'sig2', 'ProgramA', 'deposit'),
+        (2, 'sig3', 'ProgramA', 'withdraw'),
+        (2, 'sig4', 'ProgramB', '?');
+      INSERT INTO tx_programs VALUES
+        ('sig5', 0, 'ProgramA', 'withdraw', true, false),
+        ('sig4', 0, 'ProgramB', '', false, false);
+      ",
+            )
+            .unwrap();
+
+        let stats = db.get_cant_discard_programs().unwrap();
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[0].program_id, "ProgramA");
+        assert_eq!(stats[0].fn_name, "deposit");
+        assert_eq!(stats[0].occurrence_count, 2);
+        assert!(["sig1", "sig2"].contains(&stats[0].example_signature.as_str()));
+
+        assert_eq!(db.get_unrecognized_program_ids().unwrap(), vec!["ProgramB"]);
+    }
+
+    #[test]
+    fn test_program_invocation_frequency() {
+        let db = SolanaDatabase::new().unwrap();
+        db.conn
+            .execute_batch(
+                "
+      INSERT INTO tx_programs VALUES
+        ('sig1', 0, 'ProgramA', 'swap', true, false),
+        ('sig2', 0, 'ProgramA', 'swap', true, true),
+        ('sig3', 0, 'ProgramA', '', false, false),
+        ('sig4', 0, 'ProgramA', 'swap', true, false),
+        ('sig1', 1, 'ProgramB', '', false, false),
+        ('sig2', 1, 'ProgramB', '', false, true),
+        ('sig3', 1, 'ProgramB', '', false, false),
+        ('sig4', 1, 'ProgramC', '', false, false);
+      ",
+            )
+            .unwrap();
+
+        let programs = db.get_program_invocation_frequency().unwrap();
+        assert_eq!(programs.len(), 3);
+        assert_eq!(
+            programs[0],
+            ProgramFrequency {
+                program_id: "ProgramA".to_string(),
+                total_calls: 4,
+                parsed_calls: 3,
+                error_calls: 1,
+                parse_rate_pct: 75.0,
+            }
+        );
+        assert_eq!(programs[1].program_id, "ProgramB");
+        assert_eq!(programs[1].parse_rate_pct, 0.0);
+
+        let unparsed = db.get_unparsed_high_frequency_programs(0).unwrap();
+        let program_ids: Vec<&str> = unparsed.iter().map(|p| p.program_id.as_str()).collect();
+        assert_eq!(program_ids, vec!["ProgramB", "ProgramC"]);
+        let unparsed = db.get_unparsed_high_frequency_programs(1).unwrap();
+        assert_eq!(unparsed.len(), 1);
+        assert_eq!(unparsed[0].program_id, "ProgramB");
+    }
+
+    #[test]
+    fn test_deduplicate() {
+        let mut db = SolanaDatabase::new_with_primary_keys(false).unwrap();
+        db.insert_block(&test_block(1)).unwrap();
+        db.insert_block(&test_block(1)).unwrap();
+        db.insert_block(&test_block(2)).unwrap();
+
+        let swaps = [
+            buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+            buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+            buy("wallet1", 1, "TokenA", 1.0, 1000.0),
+            sell("wallet1", 2, "TokenA", 1000.0, 1.5),
+        ];
+        insert_swaps(&mut db, &swaps);
+        assert_eq!(db.count_rows("swaps").unwrap(), 4);
+
+        assert_eq!(
+            db.deduplicate_table("swaps", get_dedup_key_columns("swaps"))
+                .unwrap(),
+            2
+        );
+        assert_eq!(db.count_rows("swaps").unwrap(), 2);
+        assert!(db.deduplicate_table("swaps", &[]).is_err());
+
+        let deleted = db.deduplicate_all().unwrap();
+        assert_eq!(deleted["blocks"], 1);
+        assert_eq!(deleted["swaps"], 0);
+        assert_eq!(deleted.len(), TABLES.len());
+        assert_eq!(db.count_rows("blocks").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_table_stats() {
+        let db_path = std::env::temp_dir()
+            .join(format!("arctis_stats_test_{}.db", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        let mut db = SolanaDatabase::new_from_file(&db_path).unwrap();
+        db.insert_block(&test_block(1)).unwrap();
+        db.insert_block(&test_block(2)).unwrap();
+        db.analyze().unwrap();
+        db.vacuum().unwrap();
+
+        let stats = db.get_table_stats().unwrap();
+        assert_eq!(stats.len(), TABLES.len());
+        let blocks = stats.iter().find(|s| s.table_name == "blocks").unwrap();
+        assert_eq!(blocks.row_count, 2);
+        // slot, block_time, parent_slot, rewards: BIGINT, transaction_count: INTEGER, blockhash, leader: TEXT
+        assert_eq!(blocks.estimated_size_bytes, 2 * 68);
+
+        db.conn.execute_batch("CHECKPOINT;").unwrap();
+        assert!(db.get_database_size_bytes().unwrap() > 0);
+
+        drop(db);
+        let _ = std::fs::remove_file(&db_path);
+        let _ = std::fs::remove_file(format!("{}.wal", db_path));
+    }
+
+    #[test]
+    fn test_query_to_json_parsed_large_enums() {
+        let db = SolanaDatabase::new().unwrap();
+        // > 256 values: UInt16 keys, > 65536 values: UInt32 keys
+        db.conn
+            .execute_batch(
+                "
+      CREATE TYPE enum_16 AS ENUM (SELECT 'v' || range::VARCHAR FROM range(300));
+      CREATE TYPE enum_32 AS ENUM (SELECT 'v' || range::VARCHAR FROM range(70000));
+      CREATE TABLE large_enums (e16 enum_16, e32 enum_32);
+      INSERT INTO large_enums VALUES ('v0', 'v0'), ('v299', 'v69999');
+      ",
+            )
+            .unwrap();
+
+        let rows = db
+            .query_to_json_parsed("SELECT e16, e32 FROM large_enums ORDER BY rowid")
+            .unwrap();
+        assert_eq!(rows[0]["e16"], "v0");
+        assert_eq!(rows[0]["e32"], "v0");
+        assert_eq!(rows[1]["e16"], "v299");
+        assert_eq!(rows[1]["e32"], "v69999");
+    }
+
+    #[test]
+    fn test_query_to_json_parsed_hugeint() {
+        let mut db = SolanaDatabase::new().unwrap();
+        let supply_change = SupplyChange {
+            signature: "sig".to_string(),
+            ix_index: 0,
+            account: "account".to_string(),
+            mint: "TokenA".to_string(),
+            authority: "authority".to_string(),
+            amount: i64::MAX as i128 + 1,
+        };
+        db.insert_supply_changes_bulk(&vec![&supply_change])
+            .unwrap();
+
+        let rows = db
+            .query_to_json_parsed("SELECT amount FROM supply_changes")
+            .unwrap();
+        assert_eq!(rows[0]["amount"], "9223372036854775808");
+    }
+}
@@ -1,42 +1,227 @@
-use crate::utils::get_ts_precise;
+use crate::client::{get_block_commitment, FallbackRpcClient};
+use crate::utils::{get_ts_precise, log_message};
 use anyhow::{anyhow, Result};
 use solana_client::nonblocking::pubsub_client::PubsubClient;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_config::{RpcBlockConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter};
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::reward_type::RewardType;
 use solana_transaction_status::{UiConfirmedBlock, UiTransactionEncoding};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use futures::stream::StreamExt;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use tokio::time::{sleep, Duration};
 
+#[cfg(feature = "geyser")]
+use std::collections::HashMap;
+#[cfg(feature = "geyser")]
+use yellowstone_grpc_client::{ClientTlsConfig, GeyserGrpcClient};
+#[cfg(feature = "geyser")]
+use yellowstone_grpc_proto::convert_from::create_tx_with_meta;
+#[cfg(feature = "geyser")]
+use yellowstone_grpc_proto::prelude::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest, SubscribeRequestFilterBlocks,
+    SubscribeUpdateBlock,
+};
+
 pub enum BlockStrategy {
     SlotFetch,
     BlocksWS,
     Geyser,
 }
 
-async fn monitor_blocks_ws(
+#[derive(Debug, Clone)]
+pub struct BackoffConfig {
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub multiplier: f64,
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        BackoffConfig {
+            initial_delay_ms: 500,
+            max_delay_ms: 60_000,
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl BackoffConfig {
+    /// min(initial * multiplier^attempt, max)
+    pub fn delay_ms(&self, attempt: u32) -> u64 {
+        let delay = self.initial_delay_ms as f64 * self.multiplier.powi(attempt as i32);
+        delay.min(self.max_delay_ms as f64) as u64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MonitorConfig {
+    /// SlotFetch: throttle block fetches to avoid RPC rate limits
+    pub sleep_ms_between_slots: u64,
+    /// Geyser: gRPC endpoint of the Yellowstone plugin
+    pub geyser_endpoint: Option<String>,
+    /// Geyser: optional x-token for authenticated endpoints
+    pub geyser_token: Option<String>,
+    /// BlocksWS: delay between reconnect attempts
+    pub reconnect_backoff: BackoffConfig,
+    /// BlocksWS: give up after n consecutive failed attempts (None = retry forever)
+    pub max_reconnect_attempts: Option<u32>,
+    /// BlocksWS: max number of missing slots that are fetched after a gap
+    pub max_gap_recovery: u32,
+    /// BlocksWS: number of detected gaps, shared with the caller
+    pub gap_count: Arc<AtomicU64>,
+    /// commitment of subscriptions and block fetches (at least confirmed)
+    pub commitment: CommitmentConfig,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        MonitorConfig {
+            sleep_ms_between_slots: 0,
+            geyser_endpoint: None,
+            geyser_token: None,
+            reconnect_backoff: BackoffConfig::default(),
+            max_reconnect_attempts: None,
+            max_gap_recovery: 50,
+            gap_count: Arc::new(AtomicU64::new(0)),
+            commitment: CommitmentConfig::confirmed(),
+        }
+    }
+}
+
+/// capacity of the block channel returned by `monitor_blocks_with_progress`
+const PROGRESS_CHANNEL_CAPACITY: usize = 100;
+
+/// State of a block subscription, updated after each block that is sent to the channel
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SlotProgress {
+    /// latest slot announced by the subscription
+    pub last_received_slot: u64,
+    /// latest slot sent to the block channel
+    pub last_processed_slot: u64,
+    /// time between block_time and receiving the block
+    pub receive_lag_ms: i64,
+    /// number of blocks queued in the channel
+    pub processing_backlog: usize,
+}
+
+/// records a block that was just sent to `block_sender`
+fn update_progress(
+    progress: &watch::Sender<SlotProgress>,
+    block_sender: &mpsc::Sender<Option<(UiConfirmedBlock, i64, u64)>>,
+    slot: u64,
+    block_time: Option<i64>,
+    ts_received: i64,
+) {
+    // send_modify also updates the value if there are no receivers
+    progress.send_modify(|progress| {
+        progress.last_received_slot = progress.last_received_slot.max(slot);
+        progress.last_processed_slot = slot;
+        if let Some(block_time) = block_time {
+            progress.receive_lag_ms = ts_received - block_time * 1000;
+        }
+        progress.processing_backlog = block_sender.max_capacity() - block_sender.capacity();
+    });
+}
+
+/// fetches up to `max_gap_recovery` missing slots after `last_confirmed_slot` and sends them in slot order
+async fn recover_gap(
+    rpc_client: &FallbackRpcClient,
+    block_sender: &mpsc::Sender<Option<(UiConfirmedBlock, i64, u64)>>,
+    progress: &watch::Sender<SlotProgress>,
+    last_confirmed_slot: u64,
+    slot: u64,
+    max_gap_recovery: u32,
+    commitment: CommitmentConfig,
+) {
+    let missing = slot - last_confirmed_slot - 1;
+    let recover_until = last_confirmed_slot + missing.min(max_gap_recovery as u64);
+    for gap_slot in (last_confirmed_slot + 1)..=recover_until {
+        match get_block_with_fallback(rpc_client, gap_slot, 200, None, commitment).await {
+            Ok(Some((block, _))) => {
+                let ts_now = get_ts_precise();
+                let block_time = block.block_time;
+                let _ = block_sender.send(Some((block, ts_now, gap_slot))).await;
+                update_progress(progress, block_sender, gap_slot, block_time, ts_now);
+            }
+            Ok(None) => {
+                // slot was skipped by the leader
+            }
+            Err(e) => {
+                println!("Error fetching gap block {}: {:?}", gap_slot, e);
+            }
+        }
+    }
+}
+
+fn monitor_blocks_ws(
+    rpc_client: &Arc<FallbackRpcClient>,
     ws_rpc_url: &str,
     block_sender: mpsc::Sender<Option<(UiConfirmedBlock, i64, u64)>>,
+    config: MonitorConfig,
+    progress: watch::Sender<SlotProgress>,
 ) -> Result<u8> {
+    let rpc_client = rpc_client.clone();
     let ws_rpc_url = ws_rpc_url.to_string();
+    let MonitorConfig {
+        reconnect_backoff: backoff,
+        max_reconnect_attempts,
+        max_gap_recovery,
+        gap_count,
+        commitment,
+        ..
+    } = config;
 
     // Start subscription in separate task
     tokio::spawn(async move {
-        let mut slot_notification_client;
+        let mut attempt: u32 = 0;
+        let mut last_confirmed_slot: u64 = 0;
 
         // loop for automatic reconnect
         loop {
+            if let Some(max_attempts) = max_reconnect_attempts
+                && attempt >= max_attempts
+            {
+                log_message(
+                    &["WS", "BlocksWS"],
+                    &format!("Giving up after {} reconnect attempts", attempt),
+                    None,
+                );
+                let _ = block_sender.send(None).await;
+                return Err::<(), _>(anyhow!(
+                    "Block subscription failed after {} reconnect attempts",
+                    attempt
+                ));
+            }
+
+            if attempt > 0 {
+                let delay_ms = backoff.delay_ms(attempt - 1);
+                log_message(
+                    &["WS", "BlocksWS"],
+                    &format!("Reconnect attempt {} in {}ms", attempt, delay_ms),
+                    None,
+                );
+                sleep(Duration::from_millis(delay_ms)).await;
+            }
+
             println!("Subscribing to block notifications");
 
-            slot_notification_client = PubsubClient::new(&ws_rpc_url.to_string()).await.unwrap();
+            let slot_notification_client = match PubsubClient::new(&ws_rpc_url).await {
+                Ok(client) => client,
+                Err(e) => {
+                    println!("Error connecting to websocket: {:?}", e);
+                    attempt += 1;
+                    continue;
+                }
+            };
 
             let block_config = RpcBlockSubscribeConfig {
                 encoding: Some(UiTransactionEncoding::Json), // perf: base64 > json >> base58 > binary
                 transaction_details: Some(solana_transaction_status::TransactionDetails::Full),
-                commitment: Some(CommitmentConfig::confirmed()),
+                commitment: Some(get_block_commitment(commitment)),
                 max_supported_transaction_version: Some(0),
                 show_rewards: None,
             };
@@ -54,8 +239,40 @@ async fn monitor_blocks_ws(
                         }
 
                         let slot = val.slot;
+                        attempt = 0;
+                        progress.send_modify(|progress| {
+                            progress.last_received_slot = progress.last_received_slot.max(slot);
+                        });
+
+                        // already sent, e.g. recovered as part of a gap
+                        if slot <= last_confirmed_slot {
+                            continue;
+                        }
+                        if last_confirmed_slot > 0 && slot > last_confirmed_slot + 1 {
+                            gap_count.fetch_add(1, Ordering::Relaxed);
+                            log_message(
+                                &["WS", "BlocksWS"],
+                                &format!(
+                                    "Gap detected: missing {} slots {} - {}",
+                                    slot - last_confirmed_slot - 1,
+                                    last_confirmed_slot + 1,
+                                    slot - 1
+                                ),
+                                None,
+                            );
+                            recover_gap(
+                                &rpc_client,
+                                &block_sender,
+                                &progress,
+                                last_confirmed_slot,
+                                slot,
+                                max_gap_recovery,
+                                commitment,
+                            )
+                            .await;
+                        }
+                        last_confirmed_slot = slot;
 
-                        // TODO detect if blocks are sequential and fetch missing if it's the case
                         let ts_now = get_ts_precise();
                         let block = val.block.unwrap();
                         /*
@@ -64,13 +281,99 @@ async fn monitor_blocks_ws(
                         TODO log_metrics(slot, 0, diff_to_now, 0, 0);
                          */
 
+                        let block_time = block.block_time;
                         let _ = block_sender.send(Some((block, ts_now, slot))).await;
+                        update_progress(&progress, &block_sender, slot, block_time, ts_now);
                     }
                     println!("Websocket was killed - trying to reconnect");
                     slot_unsubscribe().await;
                 }
                 Err(e) => {
                     println!("Error subscribing to blocks: {:?}", e);
+                }
+            }
+            attempt += 1;
+        }
+    });
+
+    Ok(1)
+}
+
+/// Subscribes to slot notifications and fetches each block via rpc.
+/// Works with providers that don't support `blockSubscribe`.
+async fn monitor_blocks_slot_fetch(
+    rpc_client: &Arc<FallbackRpcClient>,
+    ws_rpc_url: &str,
+    block_sender: mpsc::Sender<Option<(UiConfirmedBlock, i64, u64)>>,
+    sleep_ms_between_slots: u64,
+    commitment: CommitmentConfig,
+) -> Result<u8> {
+    let rpc_client = rpc_client.clone();
+    let ws_rpc_url = ws_rpc_url.to_string();
+
+    // Start subscription in separate task
+    tokio::spawn(async move {
+        let mut last_processed_slot: u64 = 0;
+
+        // loop for automatic reconnect
+        loop {
+            println!("Subscribing to slot notifications");
+
+            let slot_notification_client = match PubsubClient::new(&ws_rpc_url).await {
+                Ok(client) => client,
+                Err(e) => {
+                    println!("Error connecting to websocket: {:?}", e);
+                    sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+
+            match slot_notification_client.slot_subscribe().await {
+                Ok((mut slot_subscription, slot_unsubscribe)) => {
+                    while let Some(slot_info) = slot_subscription.next().await {
+                        let slot = slot_info.slot;
+                        // notifications can be repeated or arrive out of order
+                        if slot <= last_processed_slot {
+                            continue;
+                        }
+                        if last_processed_slot > 0 && slot > last_processed_slot + 1 {
+                            log_message(
+                                &["WS", "SlotFetch"],
+                                &format!(
+                                    "Skipped {} slots: {} - {}",
+                                    slot - last_processed_slot - 1,
+                                    last_processed_slot + 1,
+                                    slot - 1
+                                ),
+                                None,
+                            );
+                        }
+                        last_processed_slot = slot;
+
+                        match get_block_with_fallback(&rpc_client, slot, 200, None, commitment)
+                            .await
+                        {
+                            Ok(Some((block, _))) => {
+                                let ts_now = get_ts_precise();
+                                let _ = block_sender.send(Some((block, ts_now, slot))).await;
+                            }
+                            Ok(None) => {
+                                // slot was skipped by the leader
+                            }
+                            Err(e) => {
+                                println!("Error fetching block {}: {:?}", slot, e);
+                            }
+                        }
+
+                        if sleep_ms_between_slots > 0 {
+                            sleep(Duration::from_millis(sleep_ms_between_slots)).await;
+                        }
+                    }
+                    println!("Websocket was killed - trying to reconnect");
+                    slot_unsubscribe().await;
+                }
+                Err(e) => {
+                    println!("Error subscribing to slots: {:?}", e);
                     sleep(Duration::from_secs(5)).await;
                 }
             }
@@ -80,40 +383,249 @@ async fn monitor_blocks_ws(
     Ok(1)
 }
 
+#[cfg(feature = "geyser")]
+const GEYSER_INITIAL_BACKOFF_MS: u64 = 500;
+#[cfg(feature = "geyser")]
+const GEYSER_MAX_BACKOFF_MS: u64 = 60_000;
+
+/// blocks are only subscribed with confirmed or finalized commitment, like blockSubscribe
+#[cfg(feature = "geyser")]
+fn get_geyser_commitment(commitment: CommitmentConfig) -> CommitmentLevel {
+    if get_block_commitment(commitment).is_finalized() {
+        CommitmentLevel::Finalized
+    } else {
+        CommitmentLevel::Confirmed
+    }
+}
+
+#[cfg(feature = "geyser")]
+fn geyser_block_to_ui_block(mut block: SubscribeUpdateBlock) -> Result<UiConfirmedBlock> {
+    // geyser does not guarantee block order for transactions
+    block.transactions.sort_by_key(|tx| tx.index);
+
+    let mut transactions = Vec::with_capacity(block.transactions.len());
+    for tx in block.transactions {
+        let tx =
+            create_tx_with_meta(tx).map_err(|e| anyhow!("Failed to convert transaction: {}", e))?;
+        transactions.push(tx.encode(UiTransactionEncoding::Json, Some(0), false)?);
+    }
+
+    Ok(UiConfirmedBlock {
+        previous_blockhash: block.parent_blockhash,
+        blockhash: block.blockhash,
+        parent_slot: block.parent_slot,
+        transactions: Some(transactions),
+        signatures: None,
+        rewards: None,
+        num_reward_partitions: None,
+        block_time: block.block_time.map(|t| t.timestamp),
+        block_height: block.block_height.map(|h| h.block_height),
+    })
+}
+
+#[cfg(feature = "geyser")]
+async fn subscribe_blocks_geyser(
+    endpoint: &str,
+    x_token: Option<String>,
+    block_sender: &mpsc::Sender<Option<(UiConfirmedBlock, i64, u64)>>,
+    backoff_ms: &mut u64,
+    commitment: CommitmentConfig,
+) -> Result<()> {
+    let mut client = GeyserGrpcClient::build_from_shared(endpoint.to_string())?
+        .x_token(x_token)?
+        .tls_config(ClientTlsConfig::new().with_native_roots())?
+        .connect()
+        .await?;
+
+    let mut blocks = HashMap::new();
+    blocks.insert(
+        "arctis".to_string(),
+        SubscribeRequestFilterBlocks {
+            account_include: vec![],
+            include_transactions: Some(true),
+            include_accounts: Some(false),
+            include_entries: Some(false),
+        },
+    );
+    let request = SubscribeRequest {
+        blocks,
+        commitment: Some(get_geyser_commitment(commitment) as i32),
+        ..Default::default()
+    };
+
+    let (_subscribe_tx, mut stream) = client.subscribe_with_request(Some(request)).await?;
+    println!("Subscribed to geyser block updates");
+
+    while let Some(message) = stream.next().await {
+        if let Some(UpdateOneof::Block(block)) = message?.update_oneof {
+            // connection is healthy again
+            *backoff_ms = GEYSER_INITIAL_BACKOFF_MS;
+
+            let ts_now = get_ts_precise();
+            let slot = block.slot;
+            match geyser_block_to_ui_block(block) {
+                Ok(block) => {
+                    let _ = block_sender.send(Some((block, ts_now, slot))).await;
+                }
+                Err(e) => {
+                    println!("Failed to convert geyser block {}: {:?}", slot, e);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "geyser")]
+async fn monitor_blocks_geyser(
+    endpoint: &str,
+    x_token: Option<String>,
+    block_sender: mpsc::Sender<Option<(UiConfirmedBlock, i64, u64)>>,
+    commitment: CommitmentConfig,
+) -> Result<u8> {
+    let endpoint = endpoint.to_string();
+
+    // Start subscription in separate task
+    tokio::spawn(async move {
+        let mut backoff_ms = GEYSER_INITIAL_BACKOFF_MS;
+
+        // loop for automatic reconnect with exponential backoff
+        loop {
+            match subscribe_blocks_geyser(
+                &endpoint,
+                x_token.clone(),
+                &block_sender,
+                &mut backoff_ms,
+                commitment,
+            )
+            .await
+            {
+                Ok(_) => println!("Geyser stream closed - trying to reconnect"),
+                Err(e) => println!("Geyser error: {:?}", e),
+            }
+            println!("Reconnecting to geyser in {}ms", backoff_ms);
+            sleep(Duration::from_millis(backoff_ms)).await;
+            backoff_ms = (backoff_ms * 2).min(GEYSER_MAX_BACKOFF_MS);
+        }
+    });
+
+    Ok(1)
+}
+
+#[cfg(not(feature = "geyser"))]
+async fn monitor_blocks_geyser(
+    _endpoint: &str,
+    _x_token: Option<String>,
+    _block_sender: mpsc::Sender<Option<(UiConfirmedBlock, i64, u64)>>,
+    _commitment: CommitmentConfig,
+) -> Result<u8> {
+    Err(anyhow!(
+        "Geyser strategy requires sol-lib to be built with the `geyser` feature"
+    ))
+}
+
+/// Blocks are fetched via rpc by the SlotFetch strategy and to recover gaps, using the fallbacks of `rpc_client`
 pub async fn monitor_blocks(
-    _rpc_client: &Arc<RpcClient>,
+    rpc_client: &Arc<FallbackRpcClient>,
     ws_rpc_url: &str,
     block_sender: mpsc::Sender<Option<(UiConfirmedBlock, i64, u64)>>,
     strategy: BlockStrategy,
+    config: &MonitorConfig,
 ) -> Result<()> {
     println!("Monitoring blocks...");
 
     match strategy {
         BlockStrategy::SlotFetch => {
-            // monitor_blocks_slot_fetch(rpc_client, ws_rpc_url, block_sender).await?;
+            monitor_blocks_slot_fetch(
+                rpc_client,
+                ws_rpc_url,
+                block_sender,
+                config.sleep_ms_between_slots,
+                config.commitment,
+            )
+            .await?;
+            return Ok(());
         }
         BlockStrategy::BlocksWS => {
-            monitor_blocks_ws(ws_rpc_url, block_sender).await?;
+            // nobody observes the progress of this subscription
+            let (progress, _) = watch::channel(SlotProgress::default());
+            monitor_blocks_ws(
+                rpc_client,
+                ws_rpc_url,
+                block_sender,
+                config.clone(),
+                progress,
+            )?;
             return Ok(());
         }
         BlockStrategy::Geyser => {
-            // monitor_blocks_geyser(rpc_client, ws_rpc_url, block_sender).await?;
+            let endpoint = config
+                .geyser_endpoint
+                .as_deref()
+                .ok_or(anyhow!("Geyser strategy requires a geyser endpoint"))?;
+            monitor_blocks_geyser(
+                endpoint,
+                config.geyser_token.clone(),
+                block_sender,
+                config.commitment,
+            )
+            .await?;
         }
     }
 
     Ok(())
 }
 
+/// Subscribes to blocks via `blockSubscribe` (BlocksWS) in a background task.
+/// The returned watch receiver always holds the latest `SlotProgress`, e.g. for health checks.
+/// Like `monitor_blocks`, the channel receives `None` when the subscription gives up.
+pub fn monitor_blocks_with_progress(
+    rpc_client: &Arc<FallbackRpcClient>,
+    ws_rpc_url: &str,
+    config: &MonitorConfig,
+) -> Result<(
+    mpsc::Receiver<Option<(UiConfirmedBlock, i64, u64)>>,
+    watch::Receiver<SlotProgress>,
+)> {
+    let (block_sender, block_receiver) = mpsc::channel(PROGRESS_CHANNEL_CAPACITY);
+    let (progress, progress_receiver) = watch::channel(SlotProgress::default());
+    monitor_blocks_ws(
+        rpc_client,
+        ws_rpc_url,
+        block_sender,
+        config.clone(),
+        progress,
+    )?;
+    Ok((block_receiver, progress_receiver))
+}
+
+/// `get_block_with_retries` with the primary client, then with each fallback if it fails
+pub async fn get_block_with_fallback(
+    rpc_client: &FallbackRpcClient,
+    slot: u64,
+    sleep_time_ms: u64,
+    retries: Option<u8>,
+    commitment: CommitmentConfig,
+) -> Result<Option<(UiConfirmedBlock, u8)>> {
+    rpc_client
+        .with_fallback(|client| async move {
+            get_block_with_retries(&client, slot, sleep_time_ms, retries, commitment).await
+        })
+        .await
+}
+
 pub async fn get_block_with_retries(
     rpc_client: &Arc<RpcClient>,
     slot: u64,
     sleep_time_ms: u64,
     retries: Option<u8>,
+    commitment: CommitmentConfig,
 ) -> Result<Option<(UiConfirmedBlock, u8)>> {
     let block_config = RpcBlockConfig {
         encoding: Some(UiTransactionEncoding::Json), // perf: base64 > json >> base58 > binary
         transaction_details: Some(solana_transaction_status::TransactionDetails::Full),
-        commitment: Some(CommitmentConfig::confirmed()),
+        commitment: Some(get_block_commitment(commitment)),
         max_supported_transaction_version: Some(0),
         rewards: None,
     };
@@ -164,6 +676,17 @@ pub async fn get_block_with_retries(
     ))
 }
 
+/// Returns the leader that produced the block and its fee reward in lamports.
+/// None if the block was fetched without rewards.
+pub fn get_block_leader(block: &UiConfirmedBlock) -> Option<(String, u64)> {
+    block
+        .rewards
+        .as_ref()?
+        .iter()
+        .find(|reward| reward.reward_type == Some(RewardType::Fee))
+        .map(|reward| (reward.pubkey.clone(), reward.lamports.max(0) as u64))
+}
+
 /*
 async fn get_block_with_cache(
   slot: u64,
@@ -173,7 +696,8 @@ async fn get_block_with_cache(
   config: &DownloadConfig,
 ) -> Result<Option<UiConfirmedBlock>> {
 
-  let DownloadConfig { max_retry_global, max_retry, sleep_duration_ms, data_location: _ } = config;
+  // rpc_semaphore has config.download_concurrency permits
+  let DownloadConfig { max_retry_global, max_retry, sleep_duration_ms, data_location: _, download_concurrency: _ } = config;
 
   let mut should_fetch = false;
   let mut block_retries: u8 = 0;
@@ -252,3 +776,59 @@ async fn get_block_with_cache(
 
 }
  */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_update_progress() {
+        let (block_sender, mut block_receiver) = mpsc::channel(10);
+        let (progress, progress_receiver) = watch::channel(SlotProgress::default());
+
+        for slot in [100, 101] {
+            block_sender.send(None).await.unwrap();
+            update_progress(
+                &progress,
+                &block_sender,
+                slot,
+                Some(1_700_000_000),
+                1_700_000_000_250,
+            );
+        }
+        assert_eq!(
+            *progress_receiver.borrow(),
+            SlotProgress {
+                last_received_slot: 101,
+                last_processed_slot: 101,
+                receive_lag_ms: 250,
+                processing_backlog: 2,
+            }
+        );
+
+        block_receiver.recv().await.unwrap();
+        update_progress(&progress, &block_sender, 99, None, 0);
+        let current = progress_receiver.borrow().clone();
+        assert_eq!(current.last_received_slot, 101);
+        assert_eq!(current.last_processed_slot, 99);
+        assert_eq!(current.receive_lag_ms, 250);
+        assert_eq!(current.processing_backlog, 1);
+    }
+
+    #[cfg(feature = "geyser")]
+    #[test]
+    fn test_get_geyser_commitment() {
+        assert_eq!(
+            get_geyser_commitment(CommitmentConfig::finalized()),
+            CommitmentLevel::Finalized
+        );
+        assert_eq!(
+            get_geyser_commitment(CommitmentConfig::confirmed()),
+            CommitmentLevel::Confirmed
+        );
+        assert_eq!(
+            get_geyser_commitment(CommitmentConfig::processed()),
+            CommitmentLevel::Confirmed
+        );
+    }
+}
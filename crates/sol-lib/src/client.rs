@@ -1,8 +1,195 @@
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use futures::future::{select_ok, FutureExt};
+use solana_client::rpc_config::{RpcBlockConfig, RpcTransactionConfig};
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiConfirmedBlock};
 
 pub use solana_client::nonblocking::rpc_client::RpcClient;
+pub use solana_sdk::commitment_config::CommitmentConfig;
 
 pub fn get_client(rpc_url: &str) -> Arc<RpcClient> {
     let client = RpcClient::new(rpc_url.to_string());
     Arc::new(client)
 }
+
+/// parses processed | confirmed | finalized
+pub fn parse_commitment(commitment: &str) -> Result<CommitmentConfig> {
+    match commitment {
+        "processed" => Ok(CommitmentConfig::processed()),
+        "confirmed" => Ok(CommitmentConfig::confirmed()),
+        "finalized" => Ok(CommitmentConfig::finalized()),
+        other => Err(anyhow!(
+            "Invalid commitment '{}': expected processed, confirmed or finalized",
+            other
+        )),
+    }
+}
+
+/// getBlock, getTransaction and blockSubscribe don't support `processed`
+pub fn get_block_commitment(commitment: CommitmentConfig) -> CommitmentConfig {
+    if commitment.is_at_least_confirmed() {
+        commitment
+    } else {
+        CommitmentConfig::confirmed()
+    }
+}
+
+pub fn get_client_with_fallbacks(primary: &str, fallbacks: &[&str]) -> Arc<FallbackRpcClient> {
+    let fallbacks = fallbacks.iter().map(|url| get_client(url)).collect();
+    Arc::new(FallbackRpcClient::new(get_client(primary), fallbacks))
+}
+
+/// Tries the primary client first and then each fallback in order until one succeeds
+pub struct FallbackRpcClient {
+    clients: Vec<Arc<RpcClient>>,
+}
+
+impl FallbackRpcClient {
+    pub fn new(primary: Arc<RpcClient>, fallbacks: Vec<Arc<RpcClient>>) -> Self {
+        let mut clients = vec![primary];
+        clients.extend(fallbacks);
+        FallbackRpcClient { clients }
+    }
+
+    pub fn primary(&self) -> Arc<RpcClient> {
+        self.clients[0].clone()
+    }
+
+    /// runs `f` with every client until it succeeds, returns the error of the last client
+    pub async fn with_fallback<T, F, Fut>(&self, f: F) -> Result<T>
+    where
+        F: Fn(Arc<RpcClient>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut last_error = None;
+        for (idx, client) in self.clients.iter().enumerate() {
+            match f(client.clone()).await {
+                Ok(result) => return Ok(result),
+                Err(err) => {
+                    log::warn!("RPC {} failed: {}", client.url(), err);
+                    if idx + 1 < self.clients.len() {
+                        log::warn!("Trying fallback {}", self.clients[idx + 1].url());
+                    }
+                    last_error = Some(err);
+                }
+            }
+        }
+        Err(last_error.unwrap_or(anyhow!("No RPC client configured")))
+    }
+
+    /// returns the current slot of the first healthy endpoint
+    pub async fn check_health(&self) -> Result<u64> {
+        self.get_slot().await
+    }
+
+    pub async fn get_slot(&self) -> Result<u64> {
+        self.with_fallback(|client| async move { Ok(client.get_slot().await?) })
+            .await
+    }
+
+    pub async fn get_block_with_config(
+        &self,
+        slot: u64,
+        config: RpcBlockConfig,
+    ) -> Result<UiConfirmedBlock> {
+        self.with_fallback(
+            |client| async move { Ok(client.get_block_with_config(slot, config).await?) },
+        )
+        .await
+    }
+
+    pub async fn get_transaction_with_config(
+        &self,
+        signature: &Signature,
+        config: RpcTransactionConfig,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+        self.with_fallback(|client| async move {
+            Ok(client
+                .get_transaction_with_config(signature, config)
+                .await?)
+        })
+        .await
+    }
+}
+
+/// Pings all candidates in parallel and returns the first one that responds.
+/// Falls back to the first candidate if none of them is healthy, `None` if `candidates` is empty.
+pub async fn get_best_client(candidates: &[&str]) -> Option<Arc<RpcClient>> {
+    const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+    if candidates.is_empty() {
+        return None;
+    }
+    let clients: Vec<Arc<RpcClient>> = candidates.iter().map(|url| get_client(url)).collect();
+    let pings = clients.iter().map(|client| {
+        let client = client.clone();
+        async move {
+            match tokio::time::timeout(PING_TIMEOUT, client.get_slot()).await {
+                Ok(Ok(_slot)) => Ok(client),
+                Ok(Err(err)) => Err(anyhow!("{}: {}", client.url(), err)),
+                Err(_) => Err(anyhow!("{}: timeout", client.url())),
+            }
+        }
+        .boxed()
+    });
+
+    match select_ok(pings).await {
+        Ok((client, _pending)) => Some(client),
+        Err(err) => {
+            log::warn!("No healthy RPC endpoint: {}", err);
+            Some(clients[0].clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commitment() {
+        assert_eq!(
+            parse_commitment("finalized").unwrap(),
+            CommitmentConfig::finalized()
+        );
+        assert!(parse_commitment("max").is_err());
+        assert_eq!(
+            get_block_commitment(parse_commitment("processed").unwrap()),
+            CommitmentConfig::confirmed()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_best_client_without_candidates() {
+        assert!(get_best_client(&[]).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_with_fallback() {
+        let client = get_client_with_fallbacks(
+            "http://primary:8899",
+            &["http://fallback1:8899", "http://fallback2:8899"],
+        );
+
+        let url = client
+            .with_fallback(|client| async move {
+                match client.url().contains("primary") {
+                    true => Err(anyhow!("primary down")),
+                    false => Ok(client.url()),
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(url, "http://fallback1:8899");
+
+        let err = client
+            .with_fallback(|client| async move { Err::<(), _>(anyhow!("{} down", client.url())) })
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "http://fallback2:8899 down");
+    }
+}
@@ -1 +1,2 @@
+pub mod openbook;
 pub mod pumpfun;
@@ -0,0 +1,212 @@
+use anchor_lang::prelude::{borsh, Pubkey};
+use anchor_lang::{event, AnchorDeserialize, AnchorSerialize};
+use anyhow::{anyhow, Result};
+use arctis_types::{DexType, SwapInfo, SwapType};
+use base64::Engine;
+use std::str::FromStr;
+
+pub const OPENBOOK_V2_PROGRAM_ID: &str = "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb";
+
+#[event]
+#[derive(Debug)]
+pub struct FillLog {
+    pub market: Pubkey,
+    pub taker_side: u8, // 0: bid, 1: ask
+    pub maker_slot: u8,
+    pub maker_out: bool,
+    pub timestamp: u64,
+    pub seq_num: u64,
+    pub maker: Pubkey,
+    pub maker_client_order_id: u64,
+    pub maker_fee: u64,
+    pub maker_timestamp: u64,
+    pub taker: Pubkey,
+    pub taker_client_order_id: u64,
+    pub taker_fee_ceil: u64,
+    pub price: i64,
+    pub quantity: i64,
+}
+
+/// Parse an openbook v2 log into a fill event
+/// log: base64 encoded log without prefix
+/// returns None for events other than FillLog
+pub fn parse_openbook_log(log: &str) -> Result<Option<FillLog>> {
+    const DISCRIMINATOR_SIZE: usize = 8;
+
+    let bytes = base64::prelude::BASE64_STANDARD
+        .decode(log)
+        .ok()
+        .filter(|bytes| bytes.len() >= DISCRIMINATOR_SIZE)
+        .ok_or(anyhow!("Invalid base64 log"))?;
+
+    let (discriminator, buffer) = bytes.split_at(DISCRIMINATOR_SIZE);
+    match discriminator {
+        // sha256("event:FillLog")[..8]
+        [150, 23, 41, 148, 152, 162, 215, 64] => {
+            let event = FillLog::try_from_slice(buffer)?;
+            Ok(Some(event))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The market authority PDA owns the base and quote vaults of a market
+pub fn get_market_authority(market: &Pubkey) -> Result<Pubkey> {
+    let program_id = Pubkey::from_str(OPENBOOK_V2_PROGRAM_ID)?;
+    let (authority, _bump) =
+        Pubkey::find_program_address(&[b"Market", market.as_ref()], &program_id);
+    Ok(authority)
+}
+
+/// Lot sizes in native units, stored in the market account and not available from the tx
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarketLotSizes {
+    pub base_lot_size: i64,
+    pub quote_lot_size: i64,
+}
+
+/// The tokens of a market, resolved from the market vaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketTokens {
+    pub base_mint: String,
+    pub base_decimals: u8,
+    pub quote_mint: String,
+    pub quote_decimals: u8,
+}
+
+/// Creates one swap for the taker and one for the maker of a fill.
+/// The fill is in lots: quantity in base lots and price in quote lots per base lot.
+pub fn fill_log_to_swaps(
+    fill: &FillLog,
+    tokens: &MarketTokens,
+    lot_sizes: &MarketLotSizes,
+    signature: &str,
+    slot: u64,
+    block_time: i64,
+) -> Vec<SwapInfo> {
+    let base_native = (fill.quantity as f64) * (lot_sizes.base_lot_size as f64);
+    let quote_native =
+        (fill.price as f64) * (fill.quantity as f64) * (lot_sizes.quote_lot_size as f64);
+    let base_quantity = base_native / 10f64.powi(tokens.base_decimals as i32);
+    let quote_quantity = quote_native / 10f64.powi(tokens.quote_decimals as i32);
+    let base_mint = tokens.base_mint.as_str();
+    let quote_mint = tokens.quote_mint.as_str();
+
+    let taker_is_bid = fill.taker_side == 0;
+
+    let swap = |signer: &Pubkey, is_buy: bool| {
+        let (swap_type, token_in, amount_in, token_out, amount_out) = if is_buy {
+            (
+                SwapType::Buy,
+                quote_mint,
+                quote_quantity,
+                base_mint,
+                base_quantity,
+            )
+        } else {
+            (
+                SwapType::Sell,
+                base_mint,
+                base_quantity,
+                quote_mint,
+                quote_quantity,
+            )
+        };
+        SwapInfo {
+            slot,
+            block_time,
+            signer: signer.to_string(),
+            signature: signature.to_string(),
+            error: false,
+            dex: DexType::OpenBook,
+            swap_type,
+            amount_in,
+            token_in: token_in.to_string(),
+            amount_out,
+            token_out: token_out.to_string(),
+            routing_path: vec![],
+        }
+    };
+
+    vec![
+        swap(&fill.taker, taker_is_bid),
+        swap(&fill.maker, !taker_is_bid),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::WSOL;
+    use anchor_lang::Discriminator;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn get_test_fill(taker_side: u8) -> FillLog {
+        FillLog {
+            market: Pubkey::new_unique(),
+            taker_side,
+            maker_slot: 0,
+            maker_out: false,
+            timestamp: 1_700_000_000,
+            seq_num: 1,
+            maker: Pubkey::new_unique(),
+            maker_client_order_id: 0,
+            maker_fee: 0,
+            maker_timestamp: 1_700_000_000,
+            taker: Pubkey::new_unique(),
+            taker_client_order_id: 0,
+            taker_fee_ceil: 0,
+            price: 150,
+            quantity: 2,
+        }
+    }
+
+    #[test]
+    fn test_parse_fill_log() {
+        let fill = get_test_fill(0);
+        let mut bytes = FillLog::discriminator().to_vec();
+        bytes.extend(fill.try_to_vec().unwrap());
+        let log = base64::prelude::BASE64_STANDARD.encode(bytes);
+
+        let parsed = parse_openbook_log(&log).unwrap().unwrap();
+        assert_eq!(parsed.maker, fill.maker);
+        assert_eq!(parsed.taker, fill.taker);
+        assert_eq!(parsed.price, 150);
+        assert_eq!(parsed.quantity, 2);
+    }
+
+    #[test]
+    fn test_fill_log_to_swaps() {
+        let fill = get_test_fill(0);
+        let tokens = MarketTokens {
+            base_mint: WSOL.to_string(),
+            base_decimals: 9,
+            quote_mint: USDC.to_string(),
+            quote_decimals: 6,
+        };
+        let lot_sizes = MarketLotSizes {
+            base_lot_size: 1_000_000,
+            quote_lot_size: 10,
+        };
+        let swaps = fill_log_to_swaps(&fill, &tokens, &lot_sizes, "sig", 1, 2);
+        assert_eq!(swaps.len(), 2);
+
+        let taker = &swaps[0];
+        assert_eq!(taker.signer, fill.taker.to_string());
+        assert_eq!(taker.swap_type, SwapType::Buy);
+        assert_eq!(taker.token_in, USDC);
+        // 150 * 2 quote lots of 10 native units
+        assert_eq!(taker.amount_in, 0.003);
+        assert_eq!(taker.token_out, WSOL);
+        // 2 base lots of 0.001 SOL
+        assert_eq!(taker.amount_out, 0.002);
+
+        let maker = &swaps[1];
+        assert_eq!(maker.signer, fill.maker.to_string());
+        assert_eq!(maker.swap_type, SwapType::Sell);
+        assert_eq!(maker.token_in, WSOL);
+        assert_eq!(maker.token_out, USDC);
+        assert_eq!(maker.dex, DexType::OpenBook);
+    }
+}
@@ -151,6 +151,7 @@ pub fn pumpfun_event_to_swap(
         token_in,
         amount_out,
         token_out,
+        routing_path: vec![],
     };
 
     Ok(Some(swap_info))
@@ -0,0 +1,78 @@
+use anyhow::Result;
+use regex::Regex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+const GATEWAYS: [&str; 3] = [
+    "https://gateway.pinata.cloud/ipfs/",
+    "https://ipfs.io/ipfs/",
+    "https://cf-ipfs.com/ipfs/",
+];
+
+static NEXT_GATEWAY: AtomicUsize = AtomicUsize::new(0);
+
+pub fn get_cid_from_url(ipfs_url: &str) -> Option<String> {
+    for gateway in GATEWAYS {
+        if ipfs_url.starts_with(gateway) {
+            let cid = ipfs_url.replace(gateway, "");
+            return Some(cid);
+        }
+    }
+    let re = Regex::new(r"(Qm[1-9A-Za-z]{44})").unwrap();
+    re.captures(ipfs_url)
+        .and_then(|cap| cap.get(0).map(|cid| cid.as_str().to_string()))
+}
+
+// round-robin over the known gateways to spread requests and avoid rate limits
+pub fn get_best_gateway(_cid: &str) -> &'static str {
+    let idx = NEXT_GATEWAY.fetch_add(1, Ordering::Relaxed);
+    GATEWAYS[idx % GATEWAYS.len()]
+}
+
+pub async fn fetch_ipfs_metadata(cid: &str, gateway: &str) -> Result<serde_json::Value> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()?;
+    let url = format!("{}{}", gateway, cid);
+    let response = client.get(&url).send().await?.error_for_status()?;
+    let metadata = response.json::<serde_json::Value>().await?;
+    Ok(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CID: &str = "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG";
+
+    #[test]
+    fn test_get_cid_from_gateway_urls() {
+        for gateway in GATEWAYS {
+            let url = format!("{}{}", gateway, CID);
+            assert_eq!(get_cid_from_url(&url), Some(CID.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_get_cid_from_unknown_gateway() {
+        let url = format!("https://example.mypinata.cloud/ipfs/{}", CID);
+        assert_eq!(get_cid_from_url(&url), Some(CID.to_string()));
+
+        let url = format!("ipfs://{}", CID);
+        assert_eq!(get_cid_from_url(&url), Some(CID.to_string()));
+    }
+
+    #[test]
+    fn test_get_cid_from_url_without_cid() {
+        assert_eq!(get_cid_from_url("https://example.com/metadata.json"), None);
+    }
+
+    #[test]
+    fn test_get_best_gateway_round_robin() {
+        let first = get_best_gateway(CID);
+        let second = get_best_gateway(CID);
+        assert!(GATEWAYS.contains(&first));
+        assert!(GATEWAYS.contains(&second));
+        assert_ne!(first, second);
+    }
+}
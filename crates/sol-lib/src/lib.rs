@@ -4,5 +4,6 @@
 pub mod blocks;
 pub mod client;
 pub mod dexes;
+pub mod ipfs;
 pub mod transaction;
 pub mod utils;
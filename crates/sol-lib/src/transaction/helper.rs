@@ -258,6 +258,19 @@ fn get_transaction_instructions_with_inner(
     instructions
 }
 
+/**
+ * Returns (top_level_ix_index, inner_instructions) for every top level instruction of the program
+ */
+pub fn get_all_inner_instructions(
+    transaction: &EncodedTransactionWithStatusMeta,
+    program_id: &str,
+) -> Vec<(u8, Vec<UiCompiledInstruction>)> {
+    get_transaction_instructions_with_inner(transaction, Some(program_id))
+        .into_iter()
+        .map(|ix| (ix.instruction_index, ix.inner_instructions))
+        .collect()
+}
+
 pub fn get_inner_instructions(
     transaction: &EncodedTransactionWithStatusMeta,
     program_id: &str,
@@ -21,6 +21,21 @@ impl<'a> InstructionWrapper<'a> {
             pix_idx,
         }
     }
+
+    /// Resolves the account indexes of the instruction with the accounts of the transaction.
+    /// Returns None if an index is out of range
+    pub fn get_account_addresses<'b>(&self, accounts: &'b [String]) -> Option<Vec<&'b str>> {
+        self.ix
+            .accounts
+            .iter()
+            .map(|account_idx| accounts.get(*account_idx as usize).map(String::as_str))
+            .collect()
+    }
+
+    /// Panics if the program id index is out of range
+    pub fn get_program_address<'b>(&self, accounts: &'b [String]) -> &'b str {
+        &accounts[self.ix.program_id_index as usize]
+    }
 }
 
 pub fn parse_compiled_instruction(
@@ -64,3 +79,34 @@ pub fn parse_ui_instruction(
         ui_compiled_instruction.stack_height,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_addresses() {
+        let accounts = ["signer", "mint", "token_account", "program"].map(String::from);
+        let ix = UiCompiledInstruction {
+            program_id_index: 3,
+            accounts: vec![2, 0, 1, 0],
+            data: "".to_string(),
+            stack_height: None,
+        };
+        let ix = InstructionWrapper::new(&ix, 0, 0);
+        assert_eq!(ix.get_program_address(&accounts), "program");
+        assert_eq!(
+            ix.get_account_addresses(&accounts),
+            Some(vec!["token_account", "signer", "mint", "signer"])
+        );
+
+        let ix = UiCompiledInstruction {
+            program_id_index: 3,
+            accounts: vec![0, 4],
+            data: "".to_string(),
+            stack_height: None,
+        };
+        let ix = InstructionWrapper::new(&ix, 0, 0);
+        assert_eq!(ix.get_account_addresses(&accounts), None);
+    }
+}
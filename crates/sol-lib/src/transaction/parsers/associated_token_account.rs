@@ -1,8 +1,8 @@
-use super::Parser;
+use super::{Parser, ParserError};
 use crate::transaction::wrapper::TransactionWrapper;
 use crate::transaction::{parse_ui_instruction, InstructionWrapper};
-use anyhow::{anyhow, Result};
-use arctis_types::{AccountInfo, BlockInfo, ParserResult, ParserResultData};
+use anyhow::Result;
+use arctis_types::{AccountInfo, BlockInfo, ParserResult, ParserResultData, SplTokenTransfer};
 
 pub struct AssociatedTokenAccountProgramParser;
 
@@ -12,18 +12,16 @@ impl Parser for AssociatedTokenAccountProgramParser {
         &self,
         ix: &InstructionWrapper,
         tx: &TransactionWrapper,
-        _block: &BlockInfo,
-    ) -> Result<ParserResult> {
+        block: &BlockInfo,
+    ) -> Result<ParserResult, ParserError> {
         // let BlockInfo{ slot, block_time } = block;
-        let ix = &ix.ix;
-
         let accounts = tx.get_accounts();
-        let ix_parsed = parse_ui_instruction(ix, &accounts).unwrap();
-
-        if ix_parsed.program_id != "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL" {
-            return Err(anyhow!("Invalid program id: {}", ix_parsed.program_id));
+        if ix.get_program_address(&accounts) != "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL" {
+            return Err(ParserError::NotMyInstruction);
         }
 
+        let ix_parsed = parse_ui_instruction(ix.ix, &accounts).unwrap();
+
         let ix_type = ix_parsed.parsed["type"].as_str().unwrap();
         match ix_type {
             "create" => {
@@ -49,19 +47,22 @@ impl Parser for AssociatedTokenAccountProgramParser {
                 })
             }
             "recoverNested" => {
+                let parsed = &ix_parsed.parsed["info"];
+                let (account_info, transfer) = parse_recover_nested(parsed, tx, block)?;
                 Ok(ParserResult {
-                    parsed: false,
+                    parsed: true,
                     ix_type: "recoverNested".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            _ => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: ix_type.to_string(),
-                    data: ParserResultData::NoData,
+                    data: ParserResultData::MultiResult(vec![
+                        ParserResultData::Account(account_info),
+                        ParserResultData::TokenTransfer(transfer),
+                    ]),
                 })
             }
+            _ => Ok(ParserResult {
+                parsed: false,
+                ix_type: ix_type.to_string(),
+                data: ParserResultData::NoData,
+            }),
         }
     }
 }
@@ -86,3 +87,65 @@ fn parse_create(parsed: &serde_json::Value, sig: String) -> AccountInfo {
         decimals: None,
     }
 }
+
+/// recoverNested closes a nested ATA (an ATA owned by another ATA) and
+/// transfers its balance to the wallet's ATA for the nested mint
+fn parse_recover_nested(
+    parsed: &serde_json::Value,
+    tx: &TransactionWrapper,
+    block: &BlockInfo,
+) -> Result<(AccountInfo, SplTokenTransfer), ParserError> {
+    let BlockInfo {
+        slot, block_time, ..
+    } = block;
+    let get = |key: &str| {
+        parsed[key]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or(ParserError::MissingAccount(format!(
+                "recoverNested: {}",
+                key
+            )))
+    };
+    let nested_source = get("nestedSource")?;
+    let nested_mint = get("nestedMint")?;
+    let destination = get("destination")?;
+    let nested_owner = get("nestedOwner")?;
+    let wallet = get("wallet")?;
+
+    let signature = tx.get_signature();
+    // the whole balance of the nested account is recovered
+    // token transfers store raw amounts, read them exactly instead of scaling the ui amount
+    let raw_amount = tx.get_pre_token_raw_amount(&nested_source).unwrap_or(0);
+    let source_info = tx.get_account_lookup().remove(&nested_source);
+    let decimals = source_info.as_ref().map(|info| info.decimals);
+
+    let account_info = AccountInfo {
+        account: nested_source.clone(),
+        owner: nested_owner.clone(),
+        open_tx: None,
+        init_tx: None,
+        close_tx: Some(signature.clone()),
+        // rent of the closed account goes to the wallet
+        close_destination: Some(wallet.clone()),
+        mint: Some(nested_mint.clone()),
+        decimals,
+    };
+
+    let transfer = SplTokenTransfer {
+        slot: *slot,
+        block_time: *block_time,
+        signature,
+        from_acc: nested_source,
+        to_acc: destination,
+        amount: raw_amount as f64,
+        raw_amount,
+        authority: Some(nested_owner.clone()),
+        from: Some(nested_owner),
+        to: Some(wallet),
+        decimals,
+        token: Some(nested_mint),
+    };
+
+    Ok((account_info, transfer))
+}
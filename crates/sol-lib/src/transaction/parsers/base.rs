@@ -1,8 +1,13 @@
 use super::associated_token_account::AssociatedTokenAccountProgramParser;
 use super::compute_budget::ComputeBudgetProgramParser;
+use super::helium::{HeliumParser, HELIUM_HNT_PROGRAM_ID, HELIUM_IOT_PROGRAM_ID};
+use super::metaplex::MetaplexTokenMetadataParser;
+use super::openbook::OpenBookV2Parser;
 use super::pumpfun::PumpfunParser;
 use super::raydium::RaydiumAmmParser;
+use super::raydium_stable::RaydiumStableAmmParser;
 use super::sequence_enforcer::SequenceEnforcerParser;
+use super::squads::{SquadsParser, SquadsVersion};
 use super::system_program::SystemProgramParser;
 use super::token_program::TokenProgramParser;
 use crate::transaction::parsers::jupiter::JupiterV6Parser;
@@ -10,6 +15,45 @@ use crate::transaction::wrapper::TransactionWrapper;
 use crate::transaction::InstructionWrapper;
 use anyhow::Result;
 use arctis_types::{BlockInfo, ParserResult, ParserResultData};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::sync::{OnceLock, RwLock};
+
+/// Why a parser could not produce a result
+#[derive(Debug)]
+pub enum ParserError {
+    /// the instruction belongs to a different program or is not handled by the parser
+    NotMyInstruction,
+    /// instruction data, logs or events could not be decoded
+    MalformedData(String),
+    /// an account or balance the parser depends on is not part of the transaction
+    MissingAccount(String),
+    /// additional data could not be fetched: the caller may retry
+    RpcError(anyhow::Error),
+    /// a known instruction variant that is not supported yet
+    Unsupported(String),
+}
+
+impl Display for ParserError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ParserError::NotMyInstruction => write!(f, "Not my instruction"),
+            ParserError::MalformedData(msg) => write!(f, "Malformed data: {}", msg),
+            ParserError::MissingAccount(msg) => write!(f, "Missing account: {}", msg),
+            ParserError::RpcError(err) => write!(f, "Rpc error: {}", err),
+            ParserError::Unsupported(msg) => write!(f, "Unsupported: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ParserError {}
+
+// most decoding helpers return anyhow errors
+impl From<anyhow::Error> for ParserError {
+    fn from(err: anyhow::Error) -> Self {
+        ParserError::MalformedData(err.to_string())
+    }
+}
 
 pub trait Parser {
     // oix is the program-specific instruction index (relative to program not transaction)
@@ -18,7 +62,7 @@ pub trait Parser {
         ix: &InstructionWrapper,
         tx: &TransactionWrapper,
         block: &BlockInfo,
-    ) -> Result<ParserResult>;
+    ) -> Result<ParserResult, ParserError>;
 }
 
 struct NoopParser;
@@ -28,7 +72,7 @@ impl Parser for NoopParser {
         _ix: &InstructionWrapper,
         _tx: &TransactionWrapper,
         _block: &BlockInfo,
-    ) -> Result<ParserResult> {
+    ) -> Result<ParserResult, ParserError> {
         Ok(ParserResult {
             parsed: false,
             ix_type: "NoOp".to_string(),
@@ -37,74 +81,248 @@ impl Parser for NoopParser {
     }
 }
 
+type ParserFactory = Box<dyn Fn() -> Box<dyn Parser> + Send + Sync>;
+
+/// Maps program ids to parser factories
+pub struct ParserRegistry {
+    parsers: RwLock<HashMap<String, ParserFactory>>,
+}
+
+impl Default for ParserRegistry {
+    fn default() -> Self {
+        let mut registry = ParserRegistry::new();
+        register_default_parsers(&mut registry);
+        registry
+    }
+}
+
+impl ParserRegistry {
+    /// creates an empty registry without the default parsers
+    pub fn new() -> Self {
+        ParserRegistry {
+            parsers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// registers (or replaces) the parser for a program id
+    pub fn register<F>(&self, program_id: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn Parser> + Send + Sync + 'static,
+    {
+        self.parsers
+            .write()
+            .unwrap()
+            .insert(program_id.to_string(), Box::new(factory));
+    }
+
+    pub fn get(&self, program_id: &str) -> Option<Box<dyn Parser>> {
+        self.parsers
+            .read()
+            .unwrap()
+            .get(program_id)
+            .map(|factory| factory())
+    }
+}
+
+// factories can't be printed, list the registered program ids instead
+impl fmt::Debug for ParserRegistry {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_set()
+            .entries(self.parsers.read().unwrap().keys())
+            .finish()
+    }
+}
+
+static GLOBAL_REGISTRY: OnceLock<ParserRegistry> = OnceLock::new();
+
+/// The registry used by `get_parser`. Additional parsers can be registered before starting the pipeline.
+pub fn global() -> &'static ParserRegistry {
+    GLOBAL_REGISTRY.get_or_init(ParserRegistry::default)
+}
+
+pub fn register_default_parsers(registry: &mut ParserRegistry) {
+    registry.register("11111111111111111111111111111111", || {
+        Box::new(SystemProgramParser)
+    });
+    registry.register("ComputeBudget111111111111111111111111111111", || {
+        Box::new(ComputeBudgetProgramParser)
+    });
+
+    // ########################## SPL ##########################
+    // Associated Token Account Program
+    registry.register("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL", || {
+        Box::new(AssociatedTokenAccountProgramParser)
+    });
+    // Token Program
+    registry.register("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", || {
+        Box::new(TokenProgramParser)
+    });
+    // MEMO
+    registry.register("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo", || {
+        Box::new(NoopParser)
+    });
+    registry.register("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr", || {
+        Box::new(NoopParser)
+    });
+    // Metaplex Token Metadata
+    registry.register("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s", || {
+        Box::new(MetaplexTokenMetadataParser)
+    });
+    // Sequence Enforcer
+    registry.register("GDDMwNyyx8uB6zrqwBFHjLLG3TBYk2F8Az4yrQC5RzMp", || {
+        Box::new(SequenceEnforcerParser)
+    });
+
+    // ########################## MULTISIG ##########################
+    // Squads v3
+    registry.register("SMPLecH534NA9acpos4G6x7uf3LWbCAwZQE9e8ZekMu", || {
+        Box::new(SquadsParser {
+            version: SquadsVersion::V3,
+        })
+    });
+    // Squads v4
+    registry.register("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52Znn", || {
+        Box::new(SquadsParser {
+            version: SquadsVersion::V4,
+        })
+    });
+
+    // ########################## STAKING ##########################
+    // Helium HNT / IOT staking
+    registry.register(HELIUM_HNT_PROGRAM_ID, || Box::new(HeliumParser));
+    registry.register(HELIUM_IOT_PROGRAM_ID, || Box::new(HeliumParser));
+
+    // ########################## DEXES ##########################
+    // Raydium v4
+    registry.register("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8", || {
+        Box::new(RaydiumAmmParser)
+    });
+    // Raydium stable swap AMM (v5)
+    registry.register("5quBtoiQqxF9Jv6KYKctB59NT3gtFD2QuEAqwTKdEinX", || {
+        Box::new(RaydiumStableAmmParser)
+    });
+    // Openbook V2
+    registry.register("opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb", || {
+        Box::new(OpenBookV2Parser::default())
+    });
+    // Jupiter Aggregator v6
+    registry.register("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4", || {
+        Box::new(JupiterV6Parser)
+    });
+    // Jupiter Aggregator v4
+    registry.register("JUP4Fb2cqiRUcaTHdrPC8h2gNsA2ETXiPDD33WcGuJB", || {
+        Box::new(NoopParser)
+    });
+    // Jupiter DCA program
+    // registry.register("DCA265Vj8a9CEuX1eb1LWRnDT7uK6q1xMipnNyatn23M", || Box::new(JupiterDCAParser));
+    // Pumpfun
+    registry.register("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P", || {
+        Box::new(PumpfunParser)
+    });
+    // Raydium AMM Router
+    registry.register("routeUGWgWzqBWFcrCfv8tritsqukccJPu3q5GPP3xS", || {
+        Box::new(NoopParser)
+    });
+    // https://github.com/Ellipsis-Labs/phoenix-v1
+    registry.register("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY", || {
+        Box::new(NoopParser)
+    });
+    // OKX DEX: Aggregation Router V2
+    registry.register("6m2CDdhRgxpH4WjvdzxAYbGxwdGUz5MziiL5jek2kBma", || {
+        Box::new(NoopParser)
+    });
+
+    // ########################## GAMING ##########################
+    // star atlas sage
+    registry.register("SAGE2HAwep459SNq61LHvjxPk4pLPEJLoMETef7f7EE", || {
+        Box::new(NoopParser)
+    });
+
+    // ########################## PERPS ##########################
+    // https://www.drift.trade/
+    registry.register("dRiftyHA39MWEi3m9aunc5MzRF1JYuBsbn6VPcn33UH", || {
+        Box::new(NoopParser)
+    });
+    // https://www.zeta.markets/
+    registry.register("ZETAxsqBRek56DhiGXrn75yj2NHU3aYUnxvHXpkf3aD", || {
+        Box::new(NoopParser)
+    });
+
+    // ########################## ORACLES ##########################
+    // chainlink data store
+    registry.register("cjg3oHmg9uuPsP8D6g29NWvhySJkdYdAo9D25PRbKXJ", || {
+        Box::new(NoopParser)
+    });
+    // pyth oracle
+    registry.register("pythWSnswVUd12oZpeFP8e9CVaEqJg25g1Vtc2biRsT", || {
+        Box::new(NoopParser)
+    });
+
+    // ########################## DeFi ##########################
+    // monaco liquidity network : https://www.monacoprotocol.xyz/
+    registry.register("monacoUXKtUi6vKsQwaLyxmXKSievfNWEcYXTgkbCih", || {
+        Box::new(NoopParser)
+    });
+
+    // ########################## Trading Bots ##########################
+    // Trojan
+    registry.register("tro46jTMkb56A3wPepo5HT7JcvX9wFWvR8VaJzgdjEf", || {
+        Box::new(NoopParser)
+    });
+
+    // ########################## OTHERS ##########################
+    // JITO tip program
+    registry.register("T1pyyaTNZsKv2WcRAB8oVnk93mLJw2XzjtVYqCsaHqt", || {
+        Box::new(NoopParser)
+    });
+    // SOL incinerator
+    registry.register("F6fmDVCQfvnEq2KR8hhfZSEczfM9JK9fWbCsYJNbTGn7", || {
+        Box::new(NoopParser)
+    });
+}
+
 pub fn get_parser(program_id: &str) -> Option<Box<dyn Parser>> {
-    match program_id {
-        "11111111111111111111111111111111" => Some(Box::new(SystemProgramParser)),
-        "ComputeBudget111111111111111111111111111111" => Some(Box::new(ComputeBudgetProgramParser)),
-
-        // ########################## SPL ##########################
-        // Associated Token Account Program
-        "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL" => {
-            Some(Box::new(AssociatedTokenAccountProgramParser))
+    global().get(program_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockParser;
+    impl Parser for MockParser {
+        fn parse(
+            &self,
+            _ix: &InstructionWrapper,
+            _tx: &TransactionWrapper,
+            _block: &BlockInfo,
+        ) -> Result<ParserResult, ParserError> {
+            Ok(ParserResult {
+                parsed: true,
+                ix_type: "Mock".to_string(),
+                data: ParserResultData::NoData,
+            })
         }
-        // Token Program
-        "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA" => Some(Box::new(TokenProgramParser)),
-        // MEMO
-        "Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo" => Some(Box::new(NoopParser)),
-        "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr" => Some(Box::new(NoopParser)),
-        // Sequence Enforcer
-        "GDDMwNyyx8uB6zrqwBFHjLLG3TBYk2F8Az4yrQC5RzMp" => Some(Box::new(SequenceEnforcerParser)),
-
-        // ########################## DEXES ##########################
-        // Raydium v4
-        "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8" => Some(Box::new(RaydiumAmmParser)),
-        // Openbook V2
-        "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb" => Some(Box::new(NoopParser)),
-        // Jupiter Aggregator v6
-        "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4" => Some(Box::new(JupiterV6Parser)),
-        // Jupiter Aggregator v4
-        "JUP4Fb2cqiRUcaTHdrPC8h2gNsA2ETXiPDD33WcGuJB" => Some(Box::new(NoopParser)),
-        // Jupiter DCA program
-        // "DCA265Vj8a9CEuX1eb1LWRnDT7uK6q1xMipnNyatn23M" => Some(Box::new(JupiterDCAParser)),
-        // Pumpfun
-        "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P" => Some(Box::new(PumpfunParser)),
-        // Raydium AMM Router
-        "routeUGWgWzqBWFcrCfv8tritsqukccJPu3q5GPP3xS" => Some(Box::new(NoopParser)),
-        // https://github.com/Ellipsis-Labs/phoenix-v1
-        "PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY" => Some(Box::new(NoopParser)),
-        // OKX DEX: Aggregation Router V2
-        "6m2CDdhRgxpH4WjvdzxAYbGxwdGUz5MziiL5jek2kBma" => Some(Box::new(NoopParser)),
-
-        // ########################## GAMING ##########################
-        // star atlas sage
-        "SAGE2HAwep459SNq61LHvjxPk4pLPEJLoMETef7f7EE" => Some(Box::new(NoopParser)),
-
-        // ########################## PERPS ##########################
-        // https://www.drift.trade/
-        "dRiftyHA39MWEi3m9aunc5MzRF1JYuBsbn6VPcn33UH" => Some(Box::new(NoopParser)),
-        // https://www.zeta.markets/
-        "ZETAxsqBRek56DhiGXrn75yj2NHU3aYUnxvHXpkf3aD" => Some(Box::new(NoopParser)),
-
-        // ########################## ORACLES ##########################
-        // chainlink data store
-        "cjg3oHmg9uuPsP8D6g29NWvhySJkdYdAo9D25PRbKXJ" => Some(Box::new(NoopParser)),
-        // pyth oracle
-        "pythWSnswVUd12oZpeFP8e9CVaEqJg25g1Vtc2biRsT" => Some(Box::new(NoopParser)),
-
-        // ########################## DeFi ##########################
-        // monaco liquidity network : https://www.monacoprotocol.xyz/
-        "monacoUXKtUi6vKsQwaLyxmXKSievfNWEcYXTgkbCih" => Some(Box::new(NoopParser)),
-
-        // ########################## Trading Bots ##########################
-        // Trojan
-        "tro46jTMkb56A3wPepo5HT7JcvX9wFWvR8VaJzgdjEf" => Some(Box::new(NoopParser)),
-
-        // ########################## OTHERS ##########################
-        // JITO tip program
-        "T1pyyaTNZsKv2WcRAB8oVnk93mLJw2XzjtVYqCsaHqt" => Some(Box::new(NoopParser)),
-        // SOL incinerator
-        "F6fmDVCQfvnEq2KR8hhfZSEczfM9JK9fWbCsYJNbTGn7" => Some(Box::new(NoopParser)),
-
-        _ => None,
+    }
+
+    #[test]
+    fn test_register_mock_parser() {
+        let program_id = "Mock111111111111111111111111111111111111111";
+        assert!(get_parser(program_id).is_none());
+
+        global().register(program_id, || Box::new(MockParser));
+        assert!(get_parser(program_id).is_some());
+    }
+
+    #[test]
+    fn test_default_parsers() {
+        let registry = ParserRegistry::new();
+        assert!(registry.get("11111111111111111111111111111111").is_none());
+
+        let registry = ParserRegistry::default();
+        assert!(registry.get("11111111111111111111111111111111").is_some());
+        assert!(registry
+            .get("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4")
+            .is_some());
     }
 }
@@ -1,4 +1,4 @@
-use super::Parser;
+use super::{Parser, ParserError};
 use crate::transaction::wrapper::TransactionWrapper;
 use crate::transaction::InstructionWrapper;
 use anyhow::Result;
@@ -14,7 +14,7 @@ impl Parser for ComputeBudgetProgramParser {
         ix: &InstructionWrapper,
         _tx: &TransactionWrapper,
         _block: &BlockInfo,
-    ) -> Result<ParserResult> {
+    ) -> Result<ParserResult, ParserError> {
         let parsed = parse_compute_budget_instruction(ix.ix)?;
         Ok(ParserResult {
             parsed: true,
@@ -0,0 +1,193 @@
+use crate::transaction::parsers::{Parser, ParserError};
+use crate::transaction::wrapper::TransactionWrapper;
+use crate::transaction::InstructionWrapper;
+use anyhow::Result;
+use arctis_types::{BlockInfo, DexType, ParserResult, ParserResultData, SwapInfo, SwapType};
+
+pub const HELIUM_HNT_PROGRAM_ID: &str = "hntjehzZZScf8so3gLSYW5BtGsYKCDXiMQC5Ds3GQWZ";
+pub const HELIUM_IOT_PROGRAM_ID: &str = "iotEVVZLEywoTn1QdwNPddxPWszn3zFhEot3MfL9fns";
+
+// sha256("global:<instruction>")[..8]
+const STAKE: [u8; 8] = [206, 176, 202, 18, 200, 209, 179, 108];
+const UNSTAKE: [u8; 8] = [90, 95, 107, 42, 205, 124, 50, 225];
+const CLAIM_REWARD: [u8; 8] = [149, 95, 181, 242, 94, 90, 158, 162];
+
+/// prefix of the synthetic token that represents the staked (vote escrowed) position
+pub const VE_TOKEN_PREFIX: &str = "ve:";
+
+pub struct HeliumParser;
+
+impl Parser for HeliumParser {
+    fn parse(
+        &self,
+        ix: &InstructionWrapper,
+        tx: &TransactionWrapper,
+        block: &BlockInfo,
+    ) -> Result<ParserResult, ParserError> {
+        let data = solana_sdk::bs58::decode(&ix.ix.data)
+            .into_vec()
+            .map_err(|err| ParserError::MalformedData(err.to_string()))?;
+        let Some(discriminator) = data.get(..8) else {
+            return Err(ParserError::MalformedData(
+                "Helium: instruction data too short".to_string(),
+            ));
+        };
+
+        let accounts = tx.get_accounts();
+        let ix_accounts = ix.get_account_addresses(&accounts).unwrap_or_default();
+        let get_account = |idx: usize| {
+            ix_accounts
+                .get(idx)
+                .map(|address| address.to_string())
+                .ok_or(ParserError::MissingAccount(format!(
+                    "Helium: account {}",
+                    idx
+                )))
+        };
+
+        match discriminator {
+            d if d == STAKE || d == UNSTAKE => {
+                let amount = data
+                    .get(8..16)
+                    .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                    .ok_or(ParserError::MalformedData(
+                        "Helium: missing stake amount".to_string(),
+                    ))?;
+                // accounts: authority, position, token account, vault, ...
+                let authority = get_account(0)?;
+                let token_account = get_account(2)?;
+                let lookup = tx.get_account_lookup();
+                let token_info = lookup
+                    .get(&token_account)
+                    .ok_or(ParserError::MissingAccount(format!(
+                        "Helium: token account {}",
+                        token_account
+                    )))?;
+                let amount = amount as f64 / 10f64.powi(token_info.decimals as i32);
+                let staked_token = format!("{}{}", VE_TOKEN_PREFIX, token_info.mint);
+
+                // staking is recorded as a swap of the token into its staked position and back
+                let is_stake = d == STAKE;
+                let (token_in, token_out) = if is_stake {
+                    (token_info.mint.clone(), staked_token)
+                } else {
+                    (staked_token, token_info.mint.clone())
+                };
+                let swap = SwapInfo {
+                    slot: block.slot,
+                    block_time: block.block_time,
+                    signer: authority,
+                    signature: tx.get_signature(),
+                    error: false,
+                    dex: DexType::Helium,
+                    swap_type: SwapType::Token,
+                    amount_in: amount,
+                    token_in,
+                    amount_out: amount,
+                    token_out,
+                    routing_path: vec![],
+                };
+                Ok(ParserResult {
+                    parsed: true,
+                    ix_type: if is_stake { "stake" } else { "unstake" }.to_string(),
+                    data: ParserResultData::Swap(swap),
+                })
+            }
+            d if d == CLAIM_REWARD => {
+                // accounts: authority, position, reward token account, ...
+                let authority = get_account(0)?;
+                let reward_account = get_account(2)?;
+                let lookup = tx.get_account_lookup();
+                let reward_info =
+                    lookup
+                        .get(&reward_account)
+                        .ok_or(ParserError::MissingAccount(format!(
+                            "Helium: reward account {}",
+                            reward_account
+                        )))?;
+                Ok(ParserResult {
+                    parsed: true,
+                    ix_type: "claimReward".to_string(),
+                    data: ParserResultData::Reward {
+                        mint: reward_info.mint.clone(),
+                        amount: reward_info.amount_post - reward_info.amount_pre,
+                        authority,
+                    },
+                })
+            }
+            _ => Ok(ParserResult {
+                parsed: false,
+                ix_type: "".to_string(),
+                data: ParserResultData::NoData,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::parsers::get_parser;
+    use crate::utils::TestTransactionBuilder;
+    use anchor_lang::prelude::Pubkey;
+
+    const HNT_MINT: &str = "hntyVP6YFm1Hg25TN9WGLqM12b8TQmcknKrdu1oxWux";
+
+    fn parse_helium_tx(data: Vec<u8>, pre: u64, post: u64) -> ParserResult {
+        let authority = Pubkey::new_unique().to_string();
+        let position = Pubkey::new_unique().to_string();
+        let token_account = Pubkey::new_unique().to_string();
+        let vault = Pubkey::new_unique().to_string();
+        let tx = TestTransactionBuilder::new(&[
+            &authority,
+            &position,
+            &token_account,
+            &vault,
+            HELIUM_HNT_PROGRAM_ID,
+        ])
+        .instruction(4, &[0, 1, 2, 3], &data)
+        .sol_balance(0, 1_000_000_000, 999_995_000)
+        .sol_balance(4, 1, 1)
+        .token_balance(2, HNT_MINT, &authority, 8, pre, post)
+        .build();
+        let tx = TransactionWrapper::new(tx);
+
+        let ix = tx.get_instructions()[0].clone();
+        let ix = InstructionWrapper::new(&ix, 0, 0);
+        let block_info = BlockInfo {
+            slot: 1,
+            block_time: 1_700_000_000,
+            blockhash: "blockhash".to_string(),
+            ..Default::default()
+        };
+        let parser = get_parser(HELIUM_HNT_PROGRAM_ID).unwrap();
+        parser.parse(&ix, &tx, &block_info).unwrap()
+    }
+
+    #[test]
+    fn test_parse_stake() {
+        let mut data = STAKE.to_vec();
+        data.extend(250_000_000u64.to_le_bytes());
+        let result = parse_helium_tx(data, 1_000_000_000, 750_000_000);
+        assert_eq!(result.ix_type, "stake");
+        let ParserResultData::Swap(swap) = result.data else {
+            panic!("expected swap");
+        };
+        assert_eq!(swap.dex, DexType::Helium);
+        assert_eq!(swap.token_in, HNT_MINT);
+        assert_eq!(swap.token_out, format!("{}{}", VE_TOKEN_PREFIX, HNT_MINT));
+        assert_eq!(swap.amount_in, 2.5);
+        assert_eq!(swap.amount_out, 2.5);
+    }
+
+    #[test]
+    fn test_parse_claim_reward() {
+        let result = parse_helium_tx(CLAIM_REWARD.to_vec(), 100_000_000, 150_000_000);
+        assert_eq!(result.ix_type, "claimReward");
+        let ParserResultData::Reward { mint, amount, .. } = result.data else {
+            panic!("expected reward");
+        };
+        assert_eq!(mint, HNT_MINT);
+        assert_eq!(amount, 0.5);
+    }
+}
@@ -1,9 +1,10 @@
-use crate::transaction::parsers::Parser;
+use crate::transaction::parsers::{Parser, ParserError};
 use crate::transaction::wrapper::TransactionWrapper;
 use crate::transaction::InstructionWrapper;
-use crate::utils::{format_with_decimals, WSOL};
-use anyhow::anyhow;
-use arctis_types::{BlockInfo, DexType, ParserResult, ParserResultData, SwapInfo, SwapType};
+use crate::utils::{format_with_decimals_checked, WSOL};
+use arctis_types::{
+    BlockInfo, DexType, ParserResult, ParserResultData, SwapHop, SwapInfo, SwapType,
+};
 use carbon_core::deserialize::CarbonDeserialize;
 use carbon_jupiter_swap_decoder::instructions::swap_event::SwapEvent;
 use indexmap::IndexMap;
@@ -17,7 +18,7 @@ impl Parser for JupiterV6Parser {
         ix: &InstructionWrapper,
         tx: &TransactionWrapper,
         block: &BlockInfo,
-    ) -> anyhow::Result<ParserResult> {
+    ) -> Result<ParserResult, ParserError> {
         // take the inner instructions for the jupiter program index
         // these instructions contain swap events.
         let mut swap_events = tx
@@ -41,12 +42,16 @@ impl Parser for JupiterV6Parser {
             }),
             // if there is one swap event, then there are no intermediate swaps
             // single swap event only
-            Ordering::Equal => parse_swap_instruction(swap_events.pop().unwrap(), block, tx),
+            Ordering::Equal => {
+                let swap_event = swap_events.pop().unwrap();
+                let routing_path = vec![to_swap_hop(&swap_event)];
+                parse_swap_instruction(swap_event, routing_path, block, tx)
+            }
             Ordering::Greater => {
                 // if there are multiple swap events,
                 // for example, token_1 -> SOL -> token_2 -> token_3
-                // we only care about the token_1 and token_3 swap
-                // merge amounts from swap with same input + output
+                // the swap is token_1 -> token_3, the hops are kept as routing path
+                // merge amounts from swap with same input + output (split routes)
                 let merged_swap_events = swap_events.into_iter().fold(
                     IndexMap::<String, SwapEvent>::new(),
                     |mut acc, swap| {
@@ -62,12 +67,15 @@ impl Parser for JupiterV6Parser {
                 );
 
                 // we create a swap event with first and last swap
-                let (_, first_swap) = merged_swap_events
-                    .first()
-                    .ok_or(anyhow!("failed to get first swap"))?;
-                let (_, last_swap) = merged_swap_events
-                    .last()
-                    .ok_or(anyhow!("failed to get last swap"))?;
+                let (_, first_swap) =
+                    merged_swap_events
+                        .first()
+                        .ok_or(ParserError::MalformedData(
+                            "failed to get first swap".to_string(),
+                        ))?;
+                let (_, last_swap) = merged_swap_events.last().ok_or(
+                    ParserError::MalformedData("failed to get last swap".to_string()),
+                )?;
 
                 let swap_event = SwapEvent {
                     amm: Default::default(),
@@ -76,18 +84,32 @@ impl Parser for JupiterV6Parser {
                     output_mint: last_swap.output_mint,
                     output_amount: last_swap.output_amount,
                 };
-                parse_swap_instruction(swap_event, block, tx)
+                let routing_path = merged_swap_events.values().map(to_swap_hop).collect();
+                parse_swap_instruction(swap_event, routing_path, block, tx)
             }
         }
     }
 }
 
+fn to_swap_hop(swap_event: &SwapEvent) -> SwapHop {
+    SwapHop {
+        amm: swap_event.amm.to_string(),
+        input_mint: swap_event.input_mint.to_string(),
+        output_mint: swap_event.output_mint.to_string(),
+        input_amount: swap_event.input_amount,
+        output_amount: swap_event.output_amount,
+    }
+}
+
 fn parse_swap_instruction(
     swap_event: SwapEvent,
+    routing_path: Vec<SwapHop>,
     block: &BlockInfo,
     tx: &TransactionWrapper,
-) -> anyhow::Result<ParserResult> {
-    let BlockInfo { slot, block_time } = *block;
+) -> Result<ParserResult, ParserError> {
+    let BlockInfo {
+        slot, block_time, ..
+    } = *block;
     let signer = tx.get_signer();
     let signature = tx.get_signature();
     let SwapEvent {
@@ -101,7 +123,9 @@ fn parse_swap_instruction(
     let token_in = input_mint.to_string();
     let token_out = output_mint.to_string();
 
-    let swap_type = if token_in == WSOL {
+    let swap_type = if token_in == token_out {
+        SwapType::Arbitrage
+    } else if token_in == WSOL {
         SwapType::Buy
     } else if token_out == WSOL {
         SwapType::Sell
@@ -109,6 +133,23 @@ fn parse_swap_instruction(
         SwapType::Token
     };
 
+    let get_token_amount = |amount: u64, mint: &str| -> Result<f64, ParserError> {
+        let decimals = tx
+            .get_token_decimals(mint)
+            .map_err(|err| ParserError::MissingAccount(err.to_string()))?;
+        Ok(
+            format_with_decimals_checked(amount, decimals).unwrap_or_else(|| {
+                log::warn!(
+                    "Jupiter: amount {} of {} with {} decimals loses precision",
+                    amount,
+                    mint,
+                    decimals
+                );
+                amount as f64 / 10f64.powi(decimals as i32)
+            }),
+        )
+    };
+
     let swap_info = SwapInfo {
         slot,
         block_time,
@@ -117,10 +158,11 @@ fn parse_swap_instruction(
         error: false,
         dex: DexType::Jupiterv6,
         swap_type,
-        amount_in: format_with_decimals(input_amount, tx.get_token_decimals(&token_in)?),
+        amount_in: get_token_amount(input_amount, &token_in)?,
         token_in,
-        amount_out: format_with_decimals(output_amount, tx.get_token_decimals(&token_out)?),
+        amount_out: get_token_amount(output_amount, &token_out)?,
         token_out,
+        routing_path,
     };
 
     Ok(ParserResult {
@@ -172,8 +214,17 @@ mod tests {
                 amount_out: 41.24039,
                 token_out: "ZEXy1pqteRu3n13kdyh4LwPQknkFk3GzmMYMuNadWPo".to_string(),
                 block_time: block_info.block_time,
+                routing_path: vec![],
             })
         );
+
+        let ParserResultData::Swap(swap) = data else {
+            panic!("expected swap");
+        };
+        let first_hop = swap.routing_path.first().unwrap();
+        let last_hop = swap.routing_path.last().unwrap();
+        assert_eq!(first_hop.input_mint, swap.token_in);
+        assert_eq!(last_hop.output_mint, swap.token_out);
     }
 
     #[tokio::test]
@@ -211,6 +262,7 @@ mod tests {
                 amount_out: 771988.318850934,
                 token_out: "uXZ7KL88jMaTLwutH9cF6xkp7dZY9JAP5Xx55Y3AyAc".to_string(),
                 block_time: block_info.block_time,
+                routing_path: vec![],
             })
         );
     }
@@ -249,6 +301,7 @@ mod tests {
                 amount_out: 154.873619,
                 token_out: "7LFeJiV7cfQhwpxUEECpGKmBisfPWkL8FZXFUFBbka5b".to_string(),
                 block_time: block_info.block_time,
+                routing_path: vec![],
             })
         );
     }
@@ -287,6 +340,7 @@ mod tests {
                 amount_out: 8.207473814,
                 token_out: "So11111111111111111111111111111111111111112".to_string(),
                 block_time: block_info.block_time,
+                routing_path: vec![],
             })
         );
     }
@@ -326,6 +380,7 @@ mod tests {
                 amount_out: 266_372.411808,
                 token_out: "HNg5PYJmtqcmzXrv6S9zP1CDKk5BgDuyFBxbvNApump".to_string(),
                 block_time: block_info.block_time,
+                routing_path: vec![],
             })
         );
     }
@@ -359,12 +414,13 @@ mod tests {
                 signature: tx.get_signature(),
                 error: false,
                 dex: DexType::Jupiterv6,
-                swap_type: SwapType::Buy,
+                swap_type: SwapType::Arbitrage,
                 amount_in: 50.507282721,
                 token_in: "So11111111111111111111111111111111111111112".to_string(),
                 amount_out: 50.615414038,
                 token_out: "So11111111111111111111111111111111111111112".to_string(),
                 block_time: block_info.block_time,
+                routing_path: vec![],
             })
         );
     }
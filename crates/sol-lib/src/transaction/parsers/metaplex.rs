@@ -0,0 +1,356 @@
+use crate::transaction::wrapper::TransactionWrapper;
+use crate::transaction::InstructionWrapper;
+
+use super::{Parser, ParserError};
+use anchor_lang::prelude::{borsh, Pubkey};
+use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+use anyhow::{anyhow, Result};
+use arctis_types::{
+    BlockInfo, MetadataUpdate, NewToken, ParserResult, ParserResultData, TokenCreator,
+};
+use std::str::FromStr;
+
+pub const METAPLEX_TOKEN_METADATA_PROGRAM_ID: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+// https://github.com/metaplex-foundation/mpl-token-metadata/blob/main/programs/token-metadata/program/src/instruction/mod.rs
+// The instruction args and the metadata PDA are not taken from the mpl-token-metadata client:
+// it is generated for solana 2 and a newer borsh. Its types implement neither the borsh 0.9 traits
+// of anchor-lang 0.24 (AnchorDeserialize) nor use the anchor Pubkey all other parsers decode into.
+const UPDATE_METADATA_ACCOUNT_V2: u8 = 15;
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct Collection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct Uses {
+    pub use_method: u8, // 0: burn, 1: multiple, 2: single
+    pub remaining: u64,
+    pub total: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct DataV2 {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<Creator>>,
+    pub collection: Option<Collection>,
+    pub uses: Option<Uses>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub enum CollectionDetails {
+    V1 { size: u64 },
+    V2 { padding: [u8; 8] },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct CreateMetadataAccountArgsV3 {
+    pub data: DataV2,
+    pub is_mutable: bool,
+    pub collection_details: Option<CollectionDetails>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct UpdateMetadataAccountArgsV2 {
+    pub data: Option<DataV2>,
+    pub update_authority: Option<Pubkey>,
+    pub primary_sale_happened: Option<bool>,
+    pub is_mutable: Option<bool>,
+}
+
+#[derive(Debug)]
+pub enum MetaplexInstruction {
+    CreateMetadataAccountV3(CreateMetadataAccountArgsV3),
+    UpdateMetadataAccountV2(UpdateMetadataAccountArgsV2),
+    Other(u8),
+}
+
+/// Decode the borsh encoded instruction data. The first byte is the instruction index.
+pub fn parse_metaplex_instruction(data: &[u8]) -> Result<MetaplexInstruction> {
+    let (discriminator, mut buffer) = data
+        .split_first()
+        .ok_or(anyhow!("Metaplex: empty instruction data"))?;
+    // deserialize instead of try_from_slice: older clients append unused bytes
+    match *discriminator {
+        CREATE_METADATA_ACCOUNT_V3 => {
+            let args = CreateMetadataAccountArgsV3::deserialize(&mut buffer)?;
+            Ok(MetaplexInstruction::CreateMetadataAccountV3(args))
+        }
+        UPDATE_METADATA_ACCOUNT_V2 => {
+            let args = UpdateMetadataAccountArgsV2::deserialize(&mut buffer)?;
+            Ok(MetaplexInstruction::UpdateMetadataAccountV2(args))
+        }
+        other => Ok(MetaplexInstruction::Other(other)),
+    }
+}
+
+/// Derives the metadata account of a mint
+pub fn get_metadata_pda(mint: &Pubkey) -> Pubkey {
+    let program_id = Pubkey::from_str(METAPLEX_TOKEN_METADATA_PROGRAM_ID).unwrap();
+    let seeds: &[&[u8]] = &[b"metadata", program_id.as_ref(), mint.as_ref()];
+    Pubkey::find_program_address(seeds, &program_id).0
+}
+
+/// updates only reference the metadata account: the mint is the account or token balance
+/// of the transaction that derives it
+fn find_metadata_mint(tx: &TransactionWrapper, metadata_account: &str) -> Option<String> {
+    let mut candidates = tx.get_accounts();
+    candidates.extend(tx.get_account_lookup().into_values().map(|info| info.mint));
+    candidates.into_iter().find(|candidate| {
+        Pubkey::from_str(candidate)
+            .map(|mint| get_metadata_pda(&mint).to_string() == metadata_account)
+            .unwrap_or(false)
+    })
+}
+
+// on-chain strings can be padded with null bytes
+fn trim_nulls(s: &str) -> String {
+    s.trim_end_matches('\0').to_string()
+}
+
+pub struct MetaplexTokenMetadataParser;
+
+impl Parser for MetaplexTokenMetadataParser {
+    fn parse(
+        &self,
+        ix: &InstructionWrapper,
+        tx: &TransactionWrapper,
+        block: &BlockInfo,
+    ) -> Result<ParserResult, ParserError> {
+        let BlockInfo {
+            slot, block_time, ..
+        } = block;
+
+        let data = solana_sdk::bs58::decode(&ix.ix.data)
+            .into_vec()
+            .map_err(|err| ParserError::MalformedData(err.to_string()))?;
+        let accounts = tx.get_accounts();
+        let ix_accounts = ix.get_account_addresses(&accounts).unwrap_or_default();
+        let get_account = |idx: usize| {
+            ix_accounts
+                .get(idx)
+                .map(|address| address.to_string())
+                .ok_or(ParserError::MissingAccount(format!(
+                    "Metaplex: account {}",
+                    idx
+                )))
+        };
+
+        match parse_metaplex_instruction(&data)? {
+            MetaplexInstruction::CreateMetadataAccountV3(args) => {
+                // accounts: metadata, mint, mint_authority, payer, update_authority, ...
+                let mint = get_account(1)?;
+                let creators = args.data.creators.as_ref().map(|creators| {
+                    creators
+                        .iter()
+                        .map(|creator| TokenCreator {
+                            address: creator.address.to_string(),
+                            verified: creator.verified,
+                            share: creator.share,
+                        })
+                        .collect()
+                });
+                let token = NewToken {
+                    block_time: *block_time,
+                    slot: *slot,
+                    signature: tx.get_signature(),
+                    signer: tx.get_signer(),
+                    factory: METAPLEX_TOKEN_METADATA_PROGRAM_ID.to_string(),
+                    // TODO get from initializeMint inner ix: balances are only available if the mint has a token account
+                    decimals: tx.get_token_decimals(&mint).unwrap_or(0),
+                    mint,
+                    name: trim_nulls(&args.data.name),
+                    symbol: trim_nulls(&args.data.symbol),
+                    uri: trim_nulls(&args.data.uri),
+                    initial_supply: None,
+                    supply: None,
+                    seller_fee_basis_points: Some(args.data.seller_fee_basis_points),
+                    creators,
+                };
+                Ok(ParserResult {
+                    parsed: true,
+                    ix_type: "createMetadataAccountV3".to_string(),
+                    data: ParserResultData::Token(token),
+                })
+            }
+            MetaplexInstruction::UpdateMetadataAccountV2(args) => {
+                // accounts: metadata, update_authority
+                let metadata_account = get_account(0)?;
+                let mint = find_metadata_mint(tx, &metadata_account);
+                let update = MetadataUpdate {
+                    slot: *slot,
+                    block_time: *block_time,
+                    signature: tx.get_signature(),
+                    metadata_account,
+                    mint,
+                    name: args.data.as_ref().map(|data| trim_nulls(&data.name)),
+                    symbol: args.data.as_ref().map(|data| trim_nulls(&data.symbol)),
+                    uri: args.data.as_ref().map(|data| trim_nulls(&data.uri)),
+                    seller_fee_basis_points: args
+                        .data
+                        .as_ref()
+                        .map(|data| data.seller_fee_basis_points),
+                    update_authority: args.update_authority.map(|key| key.to_string()),
+                    primary_sale_happened: args.primary_sale_happened,
+                    is_mutable: args.is_mutable,
+                };
+                Ok(ParserResult {
+                    parsed: true,
+                    ix_type: "updateMetadataAccountV2".to_string(),
+                    data: ParserResultData::MetadataUpdate(update),
+                })
+            }
+            MetaplexInstruction::Other(discriminator) => Ok(ParserResult {
+                parsed: false,
+                ix_type: discriminator.to_string(),
+                data: ParserResultData::NoData,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TestTransactionBuilder;
+
+    fn get_test_data() -> DataV2 {
+        DataV2 {
+            name: "Test Token".to_string(),
+            symbol: "TEST".to_string(),
+            uri: "https://example.com/metadata.json".to_string(),
+            seller_fee_basis_points: 500,
+            creators: Some(vec![Creator {
+                address: Pubkey::new_unique(),
+                verified: true,
+                share: 100,
+            }]),
+            collection: None,
+            uses: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_create_metadata_account_v3() {
+        let args = CreateMetadataAccountArgsV3 {
+            data: get_test_data(),
+            is_mutable: true,
+            collection_details: None,
+        };
+        let mut data = vec![CREATE_METADATA_ACCOUNT_V3];
+        data.extend(args.try_to_vec().unwrap());
+
+        let MetaplexInstruction::CreateMetadataAccountV3(parsed) =
+            parse_metaplex_instruction(&data).unwrap()
+        else {
+            panic!("expected createMetadataAccountV3");
+        };
+        assert_eq!(parsed.data.name, "Test Token");
+        assert_eq!(parsed.data.symbol, "TEST");
+        assert_eq!(parsed.data.seller_fee_basis_points, 500);
+        assert_eq!(parsed.data.creators.unwrap()[0].share, 100);
+        assert!(parsed.is_mutable);
+    }
+
+    #[test]
+    fn test_parser_create_metadata_account_v3() {
+        let data = get_test_data();
+        let creator = data.creators.as_ref().unwrap()[0].address.to_string();
+        let args = CreateMetadataAccountArgsV3 {
+            data,
+            is_mutable: true,
+            collection_details: None,
+        };
+        let mut ix_data = vec![CREATE_METADATA_ACCOUNT_V3];
+        ix_data.extend(args.try_to_vec().unwrap());
+
+        let mint = Pubkey::new_unique().to_string();
+        let metadata = get_metadata_pda(&Pubkey::from_str(&mint).unwrap()).to_string();
+        let tx = TestTransactionBuilder::new(&[
+            "Signer1111111111111111111111111111111111111",
+            &metadata,
+            &mint,
+            METAPLEX_TOKEN_METADATA_PROGRAM_ID,
+        ])
+        .instruction(3, &[1, 2, 0, 0, 0], &ix_data)
+        .build();
+        let tx = TransactionWrapper::new(tx);
+        let ix = tx.get_instructions()[0].clone();
+        let ix = InstructionWrapper::new(&ix, 0, 0);
+        let block_info = BlockInfo {
+            slot: 1,
+            block_time: 1_700_000_000,
+            ..Default::default()
+        };
+
+        let result = MetaplexTokenMetadataParser
+            .parse(&ix, &tx, &block_info)
+            .unwrap();
+        assert_eq!(result.ix_type, "createMetadataAccountV3");
+        let ParserResultData::Token(token) = result.data else {
+            panic!("expected token");
+        };
+        assert_eq!(token.mint, mint);
+        assert_eq!(token.name, "Test Token");
+        assert_eq!(token.seller_fee_basis_points, Some(500));
+        assert_eq!(
+            token.creators,
+            Some(vec![TokenCreator {
+                address: creator,
+                verified: true,
+                share: 100,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_update_metadata_account_v2() {
+        let update_authority = Pubkey::new_unique();
+        let args = UpdateMetadataAccountArgsV2 {
+            data: None,
+            update_authority: Some(update_authority),
+            primary_sale_happened: None,
+            is_mutable: Some(false),
+        };
+        let mut data = vec![UPDATE_METADATA_ACCOUNT_V2];
+        data.extend(args.try_to_vec().unwrap());
+
+        let MetaplexInstruction::UpdateMetadataAccountV2(parsed) =
+            parse_metaplex_instruction(&data).unwrap()
+        else {
+            panic!("expected updateMetadataAccountV2");
+        };
+        assert!(parsed.data.is_none());
+        assert_eq!(parsed.update_authority, Some(update_authority));
+        assert_eq!(parsed.is_mutable, Some(false));
+
+        assert!(matches!(
+            parse_metaplex_instruction(&[1]).unwrap(),
+            MetaplexInstruction::Other(1)
+        ));
+        assert!(parse_metaplex_instruction(&[]).is_err());
+    }
+
+    #[test]
+    fn test_get_metadata_pda() {
+        // metadata account of USDC
+        let mint = Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v").unwrap();
+        assert_eq!(
+            get_metadata_pda(&mint).to_string(),
+            "5x38Kp4hvdomTCnCrAny4UtMUt5rQBdB6px2K1Ui45Wq"
+        );
+    }
+}
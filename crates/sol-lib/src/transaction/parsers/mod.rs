@@ -8,7 +8,18 @@ pub mod sequence_enforcer;
 pub mod system_program;
 pub mod token_program;
 
+// Metaplex
+pub mod metaplex;
+
+// Multisig
+pub mod squads;
+
+// Staking
+pub mod helium;
+
 // Dexes
 mod jupiter;
+pub mod openbook;
 pub mod pumpfun;
 pub mod raydium;
+pub mod raydium_stable;
@@ -0,0 +1,270 @@
+use crate::dexes::openbook::{
+    fill_log_to_swaps, get_market_authority, parse_openbook_log, MarketLotSizes, MarketTokens,
+    OPENBOOK_V2_PROGRAM_ID,
+};
+use crate::transaction::wrapper::TransactionWrapper;
+use crate::transaction::InstructionWrapper;
+
+use super::{Parser, ParserError};
+use anyhow::Result;
+use arctis_types::{BlockInfo, ParserResult, ParserResultData};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Fills are in lots and the lot sizes are only stored in the market account.
+/// Swaps are only emitted for markets with known lot sizes, other fills are skipped.
+#[derive(Default)]
+pub struct OpenBookV2Parser {
+    lot_sizes: Arc<HashMap<String, MarketLotSizes>>,
+}
+
+impl OpenBookV2Parser {
+    /// lot sizes by market address, e.g. loaded from the market accounts before parsing
+    pub fn with_lot_sizes(lot_sizes: Arc<HashMap<String, MarketLotSizes>>) -> Self {
+        OpenBookV2Parser { lot_sizes }
+    }
+}
+
+impl Parser for OpenBookV2Parser {
+    fn parse(
+        &self,
+        ix: &InstructionWrapper,
+        tx: &TransactionWrapper,
+        block: &BlockInfo,
+    ) -> Result<ParserResult, ParserError> {
+        let BlockInfo {
+            slot, block_time, ..
+        } = block;
+
+        let logs = tx.get_log_messages().ok_or(ParserError::MalformedData(
+            "OpenBook: No logs found".to_string(),
+        ))?;
+        let logs = get_program_data_logs(&logs, ix.pix_idx as usize);
+
+        let fills = logs
+            .iter()
+            .filter_map(|log| parse_openbook_log(log).ok().flatten())
+            .collect::<Vec<_>>();
+
+        if fills.is_empty() {
+            return Ok(ParserResult {
+                parsed: false,
+                ix_type: "".to_string(),
+                data: ParserResultData::NoData,
+            });
+        }
+
+        let signature = tx.get_signature();
+        let mut swaps = vec![];
+        for fill in fills {
+            let Some(lot_sizes) = self.lot_sizes.get(&fill.market.to_string()) else {
+                continue;
+            };
+            let Some(tokens) = get_market_tokens(ix, tx, &fill.market)? else {
+                continue;
+            };
+            swaps.extend(fill_log_to_swaps(
+                &fill,
+                &tokens,
+                lot_sizes,
+                &signature,
+                *slot,
+                *block_time,
+            ));
+        }
+
+        if swaps.is_empty() {
+            return Ok(ParserResult {
+                parsed: false,
+                ix_type: "Fill".to_string(),
+                data: ParserResultData::NoData,
+            });
+        }
+
+        Ok(ParserResult {
+            parsed: true,
+            ix_type: "Fill".to_string(),
+            data: ParserResultData::MultiSwap(swaps),
+        })
+    }
+}
+
+/// Returns the "Program data: " logs emitted by the nth top level invocation of the openbook program
+fn get_program_data_logs(logs: &[String], pix_idx: usize) -> Vec<&str> {
+    let invoke = format!("Program {} invoke [1]", OPENBOOK_V2_PROGRAM_ID);
+    let mut invocations: Vec<Vec<&str>> = vec![];
+    let mut stack: Vec<&str> = vec![];
+
+    for log in logs {
+        if let Some(rest) = log.strip_prefix("Program ")
+            && let Some((program_id, _)) = rest.split_once(" invoke [")
+        {
+            if *log == invoke {
+                invocations.push(vec![]);
+            }
+            stack.push(program_id);
+        } else if let Some(rest) = log.strip_prefix("Program ")
+            && let Some((program_id, status)) = rest.split_once(' ')
+            && !program_id.ends_with(':')
+            && (status == "success" || status.starts_with("failed"))
+        {
+            stack.pop();
+        } else if let Some(data) = log.strip_prefix("Program data: ")
+            && stack.last() == Some(&OPENBOOK_V2_PROGRAM_ID)
+            && let Some(current) = invocations.last_mut()
+        {
+            current.push(data);
+        }
+    }
+
+    invocations.into_iter().nth(pix_idx).unwrap_or_default()
+}
+
+/// Resolves the base and quote token from the market vaults passed to the instruction.
+/// Vaults are owned by the market authority, the base vault is always passed before the quote vault.
+fn get_market_tokens(
+    ix: &InstructionWrapper,
+    tx: &TransactionWrapper,
+    market: &anchor_lang::prelude::Pubkey,
+) -> Result<Option<MarketTokens>> {
+    let authority = get_market_authority(market)?.to_string();
+    let accounts = tx.get_accounts();
+    let account_lookup = tx.get_account_lookup();
+
+    let mut mints: Vec<(String, u8)> = vec![];
+    for address in ix.get_account_addresses(&accounts).unwrap_or_default() {
+        if let Some(info) = account_lookup.get(address)
+            && info.owner.as_deref() == Some(authority.as_str())
+            && !mints.iter().any(|(mint, _)| *mint == info.mint)
+        {
+            mints.push((info.mint.clone(), info.decimals));
+        }
+    }
+
+    match mints.as_slice() {
+        [(base_mint, base_decimals), (quote_mint, quote_decimals)] => Ok(Some(MarketTokens {
+            base_mint: base_mint.clone(),
+            base_decimals: *base_decimals,
+            quote_mint: quote_mint.clone(),
+            quote_decimals: *quote_decimals,
+        })),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dexes::openbook::FillLog;
+    use crate::utils::{TestTransactionBuilder, WSOL};
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::{AnchorSerialize, Discriminator};
+    use arctis_types::SwapType;
+    use base64::Engine;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn fill_log(market: Pubkey, taker: Pubkey) -> String {
+        let fill = FillLog {
+            market,
+            taker_side: 0,
+            maker_slot: 0,
+            maker_out: false,
+            timestamp: 1_700_000_000,
+            seq_num: 1,
+            maker: Pubkey::new_unique(),
+            maker_client_order_id: 0,
+            maker_fee: 0,
+            maker_timestamp: 1_700_000_000,
+            taker,
+            taker_client_order_id: 0,
+            taker_fee_ceil: 0,
+            price: 150,
+            quantity: 2,
+        };
+        let mut bytes = FillLog::discriminator().to_vec();
+        bytes.extend(fill.try_to_vec().unwrap());
+        format!(
+            "Program data: {}",
+            base64::prelude::BASE64_STANDARD.encode(bytes)
+        )
+    }
+
+    #[test]
+    fn test_parse_fills() {
+        let market = Pubkey::new_unique();
+        // the vaults of this market are not passed to the instruction
+        let unresolved_market = Pubkey::new_unique();
+        let taker = Pubkey::new_unique();
+        let authority = get_market_authority(&market).unwrap().to_string();
+        let taker_key = taker.to_string();
+        let tx = TestTransactionBuilder::new(&[
+            &taker_key,
+            "BaseVau1t1111111111111111111111111111111111",
+            "QuoteVau1t111111111111111111111111111111111",
+            OPENBOOK_V2_PROGRAM_ID,
+        ])
+        .instruction(3, &[0, 1, 2], &[])
+        .token_balance(1, WSOL, &authority, 9, 0, 0)
+        .token_balance(2, USDC, &authority, 6, 0, 0)
+        .log_messages(&[
+            format!("Program {} invoke [1]", OPENBOOK_V2_PROGRAM_ID),
+            fill_log(unresolved_market, taker),
+            fill_log(market, taker),
+            format!("Program {} success", OPENBOOK_V2_PROGRAM_ID),
+        ])
+        .build();
+        let tx = TransactionWrapper::new(tx);
+        let ix = tx.get_instructions()[0].clone();
+        let ix = InstructionWrapper::new(&ix, 0, 0);
+        let block = BlockInfo {
+            slot: 1,
+            block_time: 1_700_000_000,
+            ..Default::default()
+        };
+
+        // without lot sizes the amounts are unknown
+        let result = OpenBookV2Parser::default().parse(&ix, &tx, &block).unwrap();
+        assert!(!result.parsed);
+
+        let lot_sizes = MarketLotSizes {
+            base_lot_size: 1_000_000,
+            quote_lot_size: 10,
+        };
+        let parser = OpenBookV2Parser::with_lot_sizes(Arc::new(HashMap::from([
+            (market.to_string(), lot_sizes),
+            (unresolved_market.to_string(), lot_sizes),
+        ])));
+        let result = parser.parse(&ix, &tx, &block).unwrap();
+        assert!(result.parsed);
+        let ParserResultData::MultiSwap(swaps) = result.data else {
+            panic!("expected swaps");
+        };
+        // only the fill of the resolved market
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(swaps[0].signer, taker_key);
+        assert_eq!(swaps[0].swap_type, SwapType::Buy);
+        assert_eq!(swaps[0].token_in, USDC);
+        assert_eq!(swaps[0].amount_in, 0.003);
+        assert_eq!(swaps[0].token_out, WSOL);
+        assert_eq!(swaps[0].amount_out, 0.002);
+    }
+
+    #[test]
+    fn test_get_program_data_logs() {
+        let logs = [
+            format!("Program {} invoke [1]", OPENBOOK_V2_PROGRAM_ID),
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]".to_string(),
+            "Program data: other".to_string(),
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success".to_string(),
+            "Program data: first".to_string(),
+            format!("Program {} success", OPENBOOK_V2_PROGRAM_ID),
+            format!("Program {} invoke [1]", OPENBOOK_V2_PROGRAM_ID),
+            "Program data: second".to_string(),
+            format!("Program {} success", OPENBOOK_V2_PROGRAM_ID),
+        ];
+        assert_eq!(get_program_data_logs(&logs, 0), vec!["first"]);
+        assert_eq!(get_program_data_logs(&logs, 1), vec!["second"]);
+        assert!(get_program_data_logs(&logs, 2).is_empty());
+    }
+}
@@ -1,12 +1,12 @@
 use crate::dexes::pumpfun::{
-    parse_pumpfun_log, pumpfun_event_to_swap, PumpfunEventType, PUMPFUN_PROGRAM_ID,
+    parse_pumpfun_log, pumpfun_event_to_swap, CompleteEvent, PumpfunEventType, PUMPFUN_PROGRAM_ID,
 };
 use crate::transaction::wrapper::TransactionWrapper;
 use crate::transaction::InstructionWrapper;
 
-use super::Parser;
-use anyhow::{anyhow, Result};
-use arctis_types::{BlockInfo, NewToken, ParserResult, ParserResultData};
+use super::{Parser, ParserError};
+use anyhow::Result;
+use arctis_types::{BlockInfo, BondingCurveCompletion, NewToken, ParserResult, ParserResultData};
 
 pub struct PumpfunParser;
 
@@ -16,12 +16,16 @@ impl Parser for PumpfunParser {
         ix: &InstructionWrapper,
         tx: &TransactionWrapper,
         block: &BlockInfo,
-    ) -> Result<ParserResult> {
-        let BlockInfo { slot, block_time } = block;
+    ) -> Result<ParserResult, ParserError> {
+        let BlockInfo {
+            slot, block_time, ..
+        } = block;
 
         let pump_idx = ix.pix_idx;
 
-        let logs = tx.get_log_messages().unwrap();
+        let logs = tx.get_log_messages().ok_or(ParserError::MalformedData(
+            "Pumpfun: No logs found".to_string(),
+        ))?;
         // FIXME we might have multiple different programs emitting "Program data: " logs. make method get_pumpfun_logs that checks we are in the correct invoke
         let logs = logs
             .iter()
@@ -29,9 +33,13 @@ impl Parser for PumpfunParser {
             .collect::<Vec<&str>>();
 
         if logs.is_empty() {
-            return Err(anyhow!("No pumpfun logs found"));
+            return Err(ParserError::MalformedData(
+                "No pumpfun logs found".to_string(),
+            ));
         } else if logs.len() <= pump_idx as usize {
-            return Err(anyhow!("Pumpfun: Invalid pumpfun index"));
+            return Err(ParserError::MalformedData(
+                "Pumpfun: Invalid pumpfun index".to_string(),
+            ));
         }
         // else if logs.len() > 1 { return Err(anyhow!("Pumpfun: Multiple logs found")); }
 
@@ -54,6 +62,8 @@ impl Parser for PumpfunParser {
                     decimals: 6, // TODO get from initializeMint2 inner ix
                     initial_supply: Some(1_000_000_000), // TODO get from MintTo inner ix
                     supply: Some(1_000_000_000),
+                    seller_fee_basis_points: None,
+                    creators: None,
                 };
                 Ok(ParserResult {
                     parsed: true,
@@ -72,26 +82,57 @@ impl Parser for PumpfunParser {
                 }
                 let swap_info = swap_info.unwrap();
                 let ix_type = format!("Trade{}", swap_info.swap_type.to_db());
+
+                // the buy that completes the curve emits the complete event right after the trade
+                let complete_event = logs
+                    .get(pump_idx as usize + 1)
+                    .and_then(|log| parse_pumpfun_log(log).ok());
+                if let Some(PumpfunEventType::Complete(complete_event)) = complete_event {
+                    let completion = get_completion(&complete_event, tx, block);
+                    return Ok(ParserResult {
+                        parsed: true,
+                        ix_type,
+                        data: ParserResultData::MultiResult(vec![
+                            ParserResultData::Swap(swap_info),
+                            ParserResultData::BondingCurveComplete(completion),
+                        ]),
+                    });
+                }
+
                 Ok(ParserResult {
                     parsed: true,
                     ix_type,
                     data: ParserResultData::Swap(swap_info),
                 })
             }
-            PumpfunEventType::SetParams(_) => {
-                Ok(ParserResult {
-                    parsed: true,
-                    ix_type: "SetParams".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            PumpfunEventType::Complete(_) => {
+            PumpfunEventType::SetParams(_) => Ok(ParserResult {
+                parsed: true,
+                ix_type: "SetParams".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            PumpfunEventType::Complete(complete_event) => {
+                let completion = get_completion(&complete_event, tx, block);
                 Ok(ParserResult {
                     parsed: true,
                     ix_type: "Complete".to_string(),
-                    data: ParserResultData::NoData,
+                    data: ParserResultData::BondingCurveComplete(completion),
                 })
             }
         }
     }
 }
+
+fn get_completion(
+    event: &CompleteEvent,
+    tx: &TransactionWrapper,
+    block: &BlockInfo,
+) -> BondingCurveCompletion {
+    BondingCurveCompletion {
+        slot: block.slot,
+        block_time: block.block_time,
+        signature: tx.get_signature(),
+        mint: event.mint.to_string(),
+        bonding_curve: event.bonding_curve.to_string(),
+        signer: event.user.to_string(),
+    }
+}
@@ -1,8 +1,9 @@
-use crate::transaction::parsers::Parser;
+use crate::transaction::helper::TokenAccountInfo;
+use crate::transaction::parsers::{Parser, ParserError};
 use crate::transaction::wrapper::TransactionWrapper;
 use crate::transaction::InstructionWrapper;
-use crate::utils::{format_with_decimals, WSOL};
-use anyhow::{anyhow, Result};
+use crate::utils::{format_with_decimals_checked, WSOL};
+use anyhow::Result;
 use arctis_types::{BlockInfo, DexType, ParserResult, ParserResultData, SwapInfo, SwapType};
 use carbon_core::deserialize::CarbonDeserialize;
 use carbon_raydium_amm_v4_decoder::instructions::swap_base_in::SwapBaseIn;
@@ -20,12 +21,42 @@ impl Parser for RaydiumAmmParser {
         ix: &InstructionWrapper,
         tx: &TransactionWrapper,
         block: &BlockInfo,
-    ) -> Result<ParserResult> {
-        let instruction_data = solana_sdk::bs58::decode(&ix.ix.data).into_vec()?;
+    ) -> Result<ParserResult, ParserError> {
+        let instruction_data = solana_sdk::bs58::decode(&ix.ix.data)
+            .into_vec()
+            .map_err(|err| ParserError::MalformedData(err.to_string()))?;
+        // only the vaults of this swap: with several swaps in a tx the other pools' vaults change too
+        let accounts = tx.get_accounts();
+        let writable_accounts: Vec<&String> = ix
+            .ix
+            .accounts
+            .iter()
+            .map(|idx| *idx as usize)
+            .filter(|idx| tx.is_writable_account(*idx))
+            .filter_map(|idx| accounts.get(idx))
+            .collect();
+        let is_vault = |info: &TokenAccountInfo| {
+            info.owner.as_deref() == Some(RAYDIUM_V4_AUTHORITY)
+                && writable_accounts.contains(&&info.address)
+        };
         if let Some(swap_in) = SwapBaseIn::deserialize(&instruction_data) {
-            parse_swap_instruction(Some(swap_in.amount_in), None, block, tx)
+            parse_swap_instruction(
+                Some(swap_in.amount_in),
+                None,
+                block,
+                tx,
+                DexType::RaydiumAmm,
+                is_vault,
+            )
         } else if let Some(swap_out) = SwapBaseOut::deserialize(&instruction_data) {
-            parse_swap_instruction(None, Some(swap_out.amount_out), block, tx)
+            parse_swap_instruction(
+                None,
+                Some(swap_out.amount_out),
+                block,
+                tx,
+                DexType::RaydiumAmm,
+                is_vault,
+            )
         } else {
             Ok(ParserResult {
                 parsed: false,
@@ -36,22 +67,29 @@ impl Parser for RaydiumAmmParser {
     }
 }
 
-fn parse_swap_instruction(
+/// Derives the swap from the balance changes of the pool vaults: the vault that received tokens
+/// is the input, the vault that sent tokens the output. The amount from the instruction data takes precedence.
+pub(super) fn parse_swap_instruction<F>(
     mut amount_in: Option<u64>,
     mut amount_out: Option<u64>,
     block: &BlockInfo,
     tx: &TransactionWrapper,
-) -> Result<ParserResult> {
-    let BlockInfo { slot, block_time } = *block;
+    dex: DexType,
+    is_vault: F,
+) -> Result<ParserResult, ParserError>
+where
+    F: Fn(&TokenAccountInfo) -> bool,
+{
+    let BlockInfo {
+        slot, block_time, ..
+    } = *block;
     let accounts = tx.get_account_lookup();
     let signer = tx.get_signer();
     let signature = tx.get_signature();
     let mut token_in = None;
     let mut token_out = None;
     for (_, info) in accounts {
-        if let Some(sender) = &info.owner
-            && sender == RAYDIUM_V4_AUTHORITY
-        {
+        if is_vault(&info) {
             let amount_pre = info.amount_pre.mul(10f64.powf(info.decimals as f64)) as u64;
             let amount_post = info.amount_post.mul(10f64.powf(info.decimals as f64)) as u64;
             match amount_post.cmp(&amount_pre) {
@@ -88,10 +126,10 @@ fn parse_swap_instruction(
                 (amount_in, amount_out, token_in, token_out)
             }
             _ => {
-                return Err(anyhow!(
+                return Err(ParserError::MalformedData(format!(
                     "failed to parse swap data for Raydium in Txn {:?}",
                     tx.get_signature()
-                ))
+                )))
             }
         };
 
@@ -103,18 +141,31 @@ fn parse_swap_instruction(
         SwapType::Token
     };
 
+    let get_token_amount = |amount: u64, (mint, decimals): &(String, u8)| {
+        format_with_decimals_checked(amount, *decimals).unwrap_or_else(|| {
+            log::warn!(
+                "Raydium: amount {} of {} with {} decimals loses precision",
+                amount,
+                mint,
+                decimals
+            );
+            amount as f64 / 10f64.powi(*decimals as i32)
+        })
+    };
+
     let swap_info = SwapInfo {
         slot,
         block_time,
         signer,
         signature,
         error: false,
-        dex: DexType::RaydiumAmm,
+        dex,
         swap_type,
-        amount_in: format_with_decimals(amount_in, token_in.1),
+        amount_in: get_token_amount(amount_in, &token_in),
         token_in: token_in.0,
-        amount_out: format_with_decimals(amount_out, token_out.1),
+        amount_out: get_token_amount(amount_out, &token_out),
         token_out: token_out.0,
+        routing_path: vec![],
     };
 
     Ok(ParserResult {
@@ -167,6 +218,7 @@ mod tests {
                 amount_out: 1_428.217952,
                 token_out: "A8C3xuqscfmyLrte3VmTqrAq8kgMASius9AFNANwpump".to_string(),
                 block_time: block_info.block_time,
+                routing_path: vec![],
             })
         );
     }
@@ -206,6 +258,7 @@ mod tests {
                 amount_out: 72_068.28102727,
                 token_out: "9HF5nAHD92aGZqZK6aMcQvTNMrsbuFtPNeLL3fJCBUcf".to_string(),
                 block_time: block_info.block_time,
+                routing_path: vec![],
             })
         );
     }
@@ -245,6 +298,7 @@ mod tests {
                 amount_out: 1.17053854,
                 token_out: "So11111111111111111111111111111111111111112".to_string(),
                 block_time: block_info.block_time,
+                routing_path: vec![],
             })
         );
     }
@@ -283,6 +337,7 @@ mod tests {
                 amount_out: 8_673_664_150_225.0,
                 token_out: "4h9uqNqd9XxE39o5j9ky5XBuQJG1LxMavYsPAXmrDQ9Z".to_string(),
                 block_time: block_info.block_time,
+                routing_path: vec![],
             })
         );
     }
@@ -321,6 +376,7 @@ mod tests {
                 amount_out: 0.000005,
                 token_out: "So11111111111111111111111111111111111111112".to_string(),
                 block_time: block_info.block_time,
+                routing_path: vec![],
             })
         );
     }
@@ -0,0 +1,163 @@
+use crate::transaction::helper::TokenAccountInfo;
+use crate::transaction::parsers::raydium::parse_swap_instruction;
+use crate::transaction::parsers::{Parser, ParserError};
+use crate::transaction::wrapper::TransactionWrapper;
+use crate::transaction::InstructionWrapper;
+use anyhow::Result;
+use arctis_types::{BlockInfo, DexType, ParserResult, ParserResultData};
+use carbon_core::deserialize::CarbonDeserialize;
+use carbon_raydium_amm_v4_decoder::instructions::swap_base_in::SwapBaseIn;
+use carbon_raydium_amm_v4_decoder::instructions::swap_base_out::SwapBaseOut;
+
+pub const RAYDIUM_STABLE_AMM_PROGRAM_ID: &str = "5quBtoiQqxF9Jv6KYKctB59NT3gtFD2QuEAqwTKdEinX";
+
+// accounts: token program, amm, amm authority, open orders, base vault, quote vault, model data, serum ...
+const BASE_VAULT_ACCOUNT_INDEX: usize = 4;
+const QUOTE_VAULT_ACCOUNT_INDEX: usize = 5;
+
+/// The stable swap AMM uses the same instruction layout as v4 (swapBaseIn = 9, swapBaseOut = 11)
+pub struct RaydiumStableAmmParser;
+
+impl Parser for RaydiumStableAmmParser {
+    fn parse(
+        &self,
+        ix: &InstructionWrapper,
+        tx: &TransactionWrapper,
+        block: &BlockInfo,
+    ) -> Result<ParserResult, ParserError> {
+        let instruction_data = solana_sdk::bs58::decode(&ix.ix.data)
+            .into_vec()
+            .map_err(|err| ParserError::MalformedData(err.to_string()))?;
+
+        let accounts = tx.get_accounts();
+        let ix_accounts = ix.get_account_addresses(&accounts).unwrap_or_default();
+        let get_vault = |idx: usize| {
+            ix_accounts
+                .get(idx)
+                .map(|address| address.to_string())
+                .ok_or(ParserError::MissingAccount(format!(
+                    "Raydium stable: vault account {}",
+                    idx
+                )))
+        };
+
+        let (amount_in, amount_out) =
+            if let Some(swap_in) = SwapBaseIn::deserialize(&instruction_data) {
+                (Some(swap_in.amount_in), None)
+            } else if let Some(swap_out) = SwapBaseOut::deserialize(&instruction_data) {
+                (None, Some(swap_out.amount_out))
+            } else {
+                return Ok(ParserResult {
+                    parsed: false,
+                    ix_type: "".to_string(),
+                    data: ParserResultData::NoData,
+                });
+            };
+
+        let vaults = [
+            get_vault(BASE_VAULT_ACCOUNT_INDEX)?,
+            get_vault(QUOTE_VAULT_ACCOUNT_INDEX)?,
+        ];
+        parse_swap_instruction(
+            amount_in,
+            amount_out,
+            block,
+            tx,
+            DexType::RaydiumStableAmm,
+            |info: &TokenAccountInfo| vaults.contains(&info.address),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::parsers::get_parser;
+    use crate::utils::{TestTransactionBuilder, WSOL};
+    use arctis_types::{SwapInfo, SwapType};
+
+    const MSOL: &str = "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So";
+    const SIGNER: &str = "Signer1111111111111111111111111111111111111";
+    const BASE_VAULT: &str = "BaseVau1t1111111111111111111111111111111111";
+    const QUOTE_VAULT: &str = "QuoteVau1t111111111111111111111111111111111";
+
+    const VAULT_OWNER: &str = "5quBtoiQqxF9Jv6KYKctB59NT3gtFD2QuEAqwTKdEinX";
+
+    /// mSOL / SOL pool: base vault holds mSOL, quote vault holds WSOL (raw amounts, 9 decimals)
+    fn stable_swap_tx(data: &[u8], msol: (u64, u64), wsol: (u64, u64)) -> TransactionWrapper {
+        // only the vault positions matter: ix account 4 = base vault, 5 = quote vault
+        let tx = TestTransactionBuilder::new(&[
+            SIGNER,
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+            "Amm1111111111111111111111111111111111111111",
+            "Authority111111111111111111111111111111111",
+            "Market1111111111111111111111111111111111111",
+            BASE_VAULT,
+            QUOTE_VAULT,
+            RAYDIUM_STABLE_AMM_PROGRAM_ID,
+        ])
+        .instruction(7, &[1, 2, 3, 4, 5, 6, 0], data)
+        .sol_balance(0, 1_000_000_000, 999_995_000)
+        .sol_balance(7, 1, 1)
+        .token_balance(5, MSOL, VAULT_OWNER, 9, msol.0, msol.1)
+        .token_balance(6, WSOL, VAULT_OWNER, 9, wsol.0, wsol.1)
+        .build();
+        TransactionWrapper::new(tx)
+    }
+
+    fn swap_data(discriminator: u8, amount: u64, other_amount: u64) -> Vec<u8> {
+        let mut data = vec![discriminator];
+        data.extend(amount.to_le_bytes());
+        data.extend(other_amount.to_le_bytes());
+        data
+    }
+
+    fn parse(tx: &TransactionWrapper) -> SwapInfo {
+        let ix = tx.get_instructions()[0].clone();
+        let ix = InstructionWrapper::new(&ix, 0, 0);
+        let block_info = BlockInfo {
+            slot: 1,
+            block_time: 1_700_000_000,
+            blockhash: "blockhash".to_string(),
+            ..Default::default()
+        };
+        let parser = get_parser(RAYDIUM_STABLE_AMM_PROGRAM_ID).unwrap();
+        let ParserResultData::Swap(swap) = parser.parse(&ix, tx, &block_info).unwrap().data else {
+            panic!("expected swap");
+        };
+        swap
+    }
+
+    #[test]
+    fn test_stable_swap_sol_to_msol() {
+        // swapBaseIn 1 SOL -> 0.5 mSOL
+        let tx = stable_swap_tx(
+            &swap_data(9, 1_000_000_000, 0),
+            (100_000_000_000, 99_500_000_000),
+            (50_000_000_000, 51_000_000_000),
+        );
+        let swap = parse(&tx);
+        assert_eq!(swap.dex, DexType::RaydiumStableAmm);
+        assert_eq!(swap.swap_type, SwapType::Buy);
+        assert_eq!(swap.token_in, WSOL);
+        assert_eq!(swap.amount_in, 1.0);
+        assert_eq!(swap.token_out, MSOL);
+        assert_eq!(swap.amount_out, 0.5);
+    }
+
+    #[test]
+    fn test_stable_swap_msol_to_sol() {
+        // swapBaseOut: receive exactly 2 SOL for 1.75 mSOL
+        let tx = stable_swap_tx(
+            &swap_data(11, 5_000_000_000, 2_000_000_000),
+            (100_000_000_000, 101_750_000_000),
+            (50_000_000_000, 48_000_000_000),
+        );
+        let swap = parse(&tx);
+        assert_eq!(swap.swap_type, SwapType::Sell);
+        assert_eq!(swap.token_in, MSOL);
+        assert_eq!(swap.amount_in, 1.75);
+        assert_eq!(swap.token_out, WSOL);
+        assert_eq!(swap.amount_out, 2.0);
+    }
+}
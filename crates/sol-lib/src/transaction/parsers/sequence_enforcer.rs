@@ -1,7 +1,7 @@
 use crate::transaction::wrapper::TransactionWrapper;
 use crate::transaction::InstructionWrapper;
 
-use super::Parser;
+use super::{Parser, ParserError};
 use anyhow::Result;
 use arctis_types::{BlockInfo, ParserResult, ParserResultData};
 
@@ -13,7 +13,7 @@ impl Parser for SequenceEnforcerParser {
         _ix: &InstructionWrapper,
         _tx: &TransactionWrapper,
         _block: &BlockInfo,
-    ) -> Result<ParserResult> {
+    ) -> Result<ParserResult, ParserError> {
         Ok(ParserResult {
             parsed: true,
             ix_type: "sequence_enforcer".to_string(),
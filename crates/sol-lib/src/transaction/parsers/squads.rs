@@ -0,0 +1,313 @@
+use crate::transaction::parsers::{get_parser, Parser, ParserError};
+use crate::transaction::wrapper::TransactionWrapper;
+use crate::transaction::InstructionWrapper;
+use anchor_lang::prelude::Pubkey;
+use anyhow::Result;
+use arctis_types::{BlockInfo, ParserResult, ParserResultData};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+
+pub const SQUADS_V3_PROGRAM_ID: &str = "SMPLecH534NA9acpos4G6x7uf3LWbCAwZQE9e8ZekMu";
+pub const SQUADS_V4_PROGRAM_ID: &str = "SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52Znn";
+
+// sha256("global:<instruction>")[..8]
+const V3_CREATE_TRANSACTION: [u8; 8] = [227, 193, 53, 239, 55, 126, 112, 105];
+const V3_APPROVE_TRANSACTION: [u8; 8] = [224, 39, 88, 181, 36, 59, 155, 122];
+const V3_EXECUTE_TRANSACTION: [u8; 8] = [231, 173, 49, 91, 235, 24, 68, 19];
+const V4_VAULT_TRANSACTION_CREATE: [u8; 8] = [48, 250, 78, 168, 208, 226, 218, 211];
+const V4_PROPOSAL_APPROVE: [u8; 8] = [144, 37, 164, 136, 188, 216, 42, 248];
+const V4_VAULT_TRANSACTION_EXECUTE: [u8; 8] = [194, 8, 161, 87, 153, 164, 25, 171];
+
+/// the transaction index is only stored in the transaction account.
+/// it is recovered from the transaction PDA by checking the most likely indexes
+const MAX_TRANSACTION_INDEX: u32 = 4096;
+
+/// the search costs up to MAX_TRANSACTION_INDEX PDA derivations, results (and misses) are kept
+/// by transaction address. the cache is cleared once it holds MAX_CACHED_INDEXES entries
+const MAX_CACHED_INDEXES: usize = 10_000;
+
+static TRANSACTION_INDEX_CACHE: OnceLock<RwLock<HashMap<String, Option<u32>>>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SquadsVersion {
+    V3,
+    V4,
+}
+
+/// Derives the address of the multisig transaction account for `index`
+pub fn get_transaction_pda(version: SquadsVersion, multisig: &Pubkey, index: u32) -> Pubkey {
+    match version {
+        SquadsVersion::V3 => {
+            let program_id = Pubkey::from_str(SQUADS_V3_PROGRAM_ID).unwrap();
+            let seeds: &[&[u8]] = &[
+                b"squad",
+                multisig.as_ref(),
+                &index.to_le_bytes(),
+                b"transaction",
+            ];
+            Pubkey::find_program_address(seeds, &program_id).0
+        }
+        SquadsVersion::V4 => {
+            let program_id = Pubkey::from_str(SQUADS_V4_PROGRAM_ID).unwrap();
+            let seeds: &[&[u8]] = &[
+                b"multisig",
+                multisig.as_ref(),
+                b"transaction",
+                &(index as u64).to_le_bytes(),
+            ];
+            Pubkey::find_program_address(seeds, &program_id).0
+        }
+    }
+}
+
+/// Returns None if the index is larger than MAX_TRANSACTION_INDEX
+pub fn find_transaction_index(
+    version: SquadsVersion,
+    multisig: &str,
+    transaction: &str,
+) -> Option<u32> {
+    let cache = TRANSACTION_INDEX_CACHE.get_or_init(Default::default);
+    if let Some(index) = cache.read().unwrap().get(transaction) {
+        return *index;
+    }
+
+    let index = search_transaction_index(version, multisig, transaction);
+    let mut cache = cache.write().unwrap();
+    if cache.len() >= MAX_CACHED_INDEXES {
+        cache.clear();
+    }
+    cache.insert(transaction.to_string(), index);
+    index
+}
+
+fn search_transaction_index(
+    version: SquadsVersion,
+    multisig: &str,
+    transaction: &str,
+) -> Option<u32> {
+    let multisig = Pubkey::from_str(multisig).ok()?;
+    let transaction = Pubkey::from_str(transaction).ok()?;
+    // indexes start at 1
+    (1..=MAX_TRANSACTION_INDEX)
+        .find(|index| get_transaction_pda(version, &multisig, *index) == transaction)
+}
+
+pub struct SquadsParser {
+    pub version: SquadsVersion,
+}
+
+impl Parser for SquadsParser {
+    fn parse(
+        &self,
+        ix: &InstructionWrapper,
+        tx: &TransactionWrapper,
+        block: &BlockInfo,
+    ) -> Result<ParserResult, ParserError> {
+        let data = solana_sdk::bs58::decode(&ix.ix.data)
+            .into_vec()
+            .map_err(|err| ParserError::MalformedData(err.to_string()))?;
+        let Some(discriminator) = data.get(..8) else {
+            return Err(ParserError::MalformedData(
+                "Squads: instruction data too short".to_string(),
+            ));
+        };
+
+        let accounts = tx.get_accounts();
+        let ix_accounts = ix.get_account_addresses(&accounts).unwrap_or_default();
+        let get_account = |idx: usize| {
+            ix_accounts
+                .get(idx)
+                .map(|address| address.to_string())
+                .ok_or(ParserError::MissingAccount(format!(
+                    "Squads: account {}",
+                    idx
+                )))
+        };
+
+        let ix_type = match (self.version, discriminator) {
+            (SquadsVersion::V3, d) if d == V3_CREATE_TRANSACTION => "createTransaction",
+            (SquadsVersion::V3, d) if d == V3_APPROVE_TRANSACTION => "approveTransaction",
+            (SquadsVersion::V4, d) if d == V4_VAULT_TRANSACTION_CREATE => "createTransaction",
+            (SquadsVersion::V4, d) if d == V4_PROPOSAL_APPROVE => "approveTransaction",
+            (SquadsVersion::V3, d) if d == V3_EXECUTE_TRANSACTION => {
+                // accounts: multisig, transaction, member, ...
+                let multisig = get_account(0)?;
+                let transaction = get_account(1)?;
+                let executed_by = get_account(2)?;
+                return self.parse_execution(ix, tx, block, multisig, transaction, executed_by);
+            }
+            (SquadsVersion::V4, d) if d == V4_VAULT_TRANSACTION_EXECUTE => {
+                // accounts: multisig, proposal, transaction, member, ...
+                let multisig = get_account(0)?;
+                let transaction = get_account(2)?;
+                let executed_by = get_account(3)?;
+                return self.parse_execution(ix, tx, block, multisig, transaction, executed_by);
+            }
+            _ => {
+                return Ok(ParserResult {
+                    parsed: false,
+                    ix_type: "".to_string(),
+                    data: ParserResultData::NoData,
+                })
+            }
+        };
+
+        // proposals and votes don't move funds
+        Ok(ParserResult {
+            parsed: true,
+            ix_type: ix_type.to_string(),
+            data: ParserResultData::NoData,
+        })
+    }
+}
+
+impl SquadsParser {
+    /// The executed instructions are the direct CPIs of the squads program (stack height 2).
+    /// Each one is passed to the parser of its program. Instructions that can't be parsed are skipped.
+    fn parse_execution(
+        &self,
+        ix: &InstructionWrapper,
+        tx: &TransactionWrapper,
+        block: &BlockInfo,
+        multisig: String,
+        transaction: String,
+        executed_by: String,
+    ) -> Result<ParserResult, ParserError> {
+        let transaction_index = find_transaction_index(self.version, &multisig, &transaction);
+        let mut results = vec![ParserResultData::MultisigExecution {
+            multisig,
+            transaction_index,
+            executed_by,
+        }];
+
+        let accounts = tx.get_accounts();
+        let inner_instructions =
+            tx.get_compiled_inner_instructions_for_instruction(ix.ix_idx as u8)?;
+        for inner_ix in inner_instructions
+            .iter()
+            .filter(|inner_ix| inner_ix.stack_height.is_none_or(|height| height == 2))
+        {
+            let Some(program_id) = accounts.get(inner_ix.program_id_index as usize) else {
+                continue;
+            };
+            let Some(parser) = get_parser(program_id) else {
+                continue;
+            };
+            let inner_wrapped = InstructionWrapper::new(inner_ix, ix.ix_idx, ix.pix_idx);
+            if let Ok(result) = parser.parse(&inner_wrapped, tx, block)
+                && result.parsed
+            {
+                results.push(result.data);
+            }
+        }
+
+        Ok(ParserResult {
+            parsed: true,
+            ix_type: "executeTransaction".to_string(),
+            data: ParserResultData::MultiResult(results),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TestTransactionBuilder;
+    use arctis_types::SolTransfer;
+
+    fn transfer_data(lamports: u64) -> Vec<u8> {
+        // system program transfer: u32 instruction index + u64 lamports
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend(lamports.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_find_transaction_index() {
+        let multisig = Pubkey::new_unique();
+        for version in [SquadsVersion::V3, SquadsVersion::V4] {
+            let transaction = get_transaction_pda(version, &multisig, 42);
+            assert_eq!(
+                find_transaction_index(version, &multisig.to_string(), &transaction.to_string()),
+                Some(42)
+            );
+            // served from the cache
+            assert_eq!(
+                find_transaction_index(version, &multisig.to_string(), &transaction.to_string()),
+                Some(42)
+            );
+        }
+        let unknown = Pubkey::new_unique().to_string();
+        for _ in 0..2 {
+            assert_eq!(
+                find_transaction_index(SquadsVersion::V4, &multisig.to_string(), &unknown),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_vault_transaction_execute() {
+        let multisig = Pubkey::new_unique();
+        let transaction = get_transaction_pda(SquadsVersion::V4, &multisig, 7);
+        let member = Pubkey::new_unique().to_string();
+        let vault = Pubkey::new_unique().to_string();
+        let recipient = Pubkey::new_unique().to_string();
+        let multisig_key = multisig.to_string();
+        let proposal = Pubkey::new_unique().to_string();
+        let transaction_key = transaction.to_string();
+        let tx = TestTransactionBuilder::new(&[
+            &member,
+            &multisig_key,
+            &proposal,
+            &transaction_key,
+            &vault,
+            &recipient,
+            "11111111111111111111111111111111",
+            SQUADS_V4_PROGRAM_ID,
+        ])
+        .header(1, 0, 2)
+        .instruction(7, &[1, 2, 3, 0, 4, 5, 6], &V4_VAULT_TRANSACTION_EXECUTE)
+        .inner_instruction(0, 6, &[4, 5], &transfer_data(2_000_000_000))
+        .sol_balance(0, 1_000_000_000, 999_995_000)
+        .sol_balance(4, 5_000_000_000, 3_000_000_000)
+        .sol_balance(5, 0, 2_000_000_000)
+        .sol_balance(6, 1, 1)
+        .sol_balance(7, 1, 1)
+        .build();
+        let tx = TransactionWrapper::new(tx);
+
+        let ix = tx.get_instructions()[0].clone();
+        let ix = InstructionWrapper::new(&ix, 0, 0);
+        let block_info = BlockInfo {
+            slot: 1,
+            block_time: 1_700_000_000,
+            blockhash: "blockhash".to_string(),
+            ..Default::default()
+        };
+        let parser = get_parser(SQUADS_V4_PROGRAM_ID).unwrap();
+        let result = parser.parse(&ix, &tx, &block_info).unwrap();
+        assert_eq!(result.ix_type, "executeTransaction");
+
+        let results = result.data.flatten();
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            *results[0],
+            ParserResultData::MultisigExecution {
+                multisig: multisig.to_string(),
+                transaction_index: Some(7),
+                executed_by: member,
+            }
+        );
+        let ParserResultData::SolTransfer(SolTransfer {
+            from, to, lamports, ..
+        }) = results[1]
+        else {
+            panic!("expected sol transfer");
+        };
+        assert_eq!(from, &vault);
+        assert_eq!(to, &recipient);
+        assert_eq!(*lamports, 2_000_000_000);
+    }
+}
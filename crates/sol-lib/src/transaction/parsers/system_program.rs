@@ -1,7 +1,7 @@
-use super::base::Parser;
+use super::base::{Parser, ParserError};
 use crate::transaction::wrapper::TransactionWrapper;
 use crate::transaction::{parse_ui_instruction, InstructionWrapper};
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use arctis_types::{AccountInfo, BlockInfo, ParserResult, ParserResultData, SolTransfer};
 use solana_sdk::native_token::lamports_to_sol;
 
@@ -13,16 +13,18 @@ impl Parser for SystemProgramParser {
         ix: &InstructionWrapper,
         tx: &TransactionWrapper,
         block: &BlockInfo,
-    ) -> Result<ParserResult> {
-        let BlockInfo { slot, block_time } = block;
+    ) -> Result<ParserResult, ParserError> {
+        let BlockInfo {
+            slot, block_time, ..
+        } = block;
 
         let accounts = tx.get_accounts();
-        let ix_parsed = parse_ui_instruction(ix.ix, &accounts).unwrap();
-
-        if ix_parsed.program_id != "11111111111111111111111111111111" {
-            return Err(anyhow!("Invalid program id: {}", ix_parsed.program_id));
+        if ix.get_program_address(&accounts) != "11111111111111111111111111111111" {
+            return Err(ParserError::NotMyInstruction);
         }
 
+        let ix_parsed = parse_ui_instruction(ix.ix, &accounts).unwrap();
+
         let signature = tx.get_signature();
 
         let ix_type = ix_parsed.parsed["type"].as_str().unwrap();
@@ -43,6 +45,20 @@ impl Parser for SystemProgramParser {
                     lamports: ix_parsed.parsed["info"]["lamports"].as_u64().unwrap(),
                     sol: lamports_to_sol(ix_parsed.parsed["info"]["lamports"].as_u64().unwrap()),
                 };
+                // the parsed amount is authoritative. a different delta means there are other
+                // lamport movements for the account (fees, rent, multiple transfers)
+                if let Some(received) = tx.get_total_sol_received_by_account(&sol_transfer.to)
+                    && sol_transfer.from != sol_transfer.to
+                    && received != sol_transfer.lamports as i64
+                {
+                    log::debug!(
+                        "SystemProgram: transfer of {} lamports to {} but balance changed by {} in {}",
+                        sol_transfer.lamports,
+                        sol_transfer.to,
+                        received,
+                        sol_transfer.signature
+                    );
+                }
                 Ok(ParserResult {
                     parsed: true,
                     ix_type: "transfer".to_string(),
@@ -76,48 +92,36 @@ impl Parser for SystemProgramParser {
                     data: ParserResultData::Account(account_info),
                 })
             }
-            "createAccount" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "createAccount".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            "initializeNonce" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "initializeNonce".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            "advanceNonce" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "advanceNonce".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            "withdrawFromNonce" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "withdrawFromNonce".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            "transferWithSeed" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "transferWithSeed".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            _ => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: ix_type.to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
+            "createAccount" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "createAccount".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            "initializeNonce" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "initializeNonce".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            "advanceNonce" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "advanceNonce".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            "withdrawFromNonce" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "withdrawFromNonce".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            "transferWithSeed" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "transferWithSeed".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            _ => Ok(ParserResult {
+                parsed: false,
+                ix_type: ix_type.to_string(),
+                data: ParserResultData::NoData,
+            }),
         }
     }
 }
@@ -1,11 +1,11 @@
 use crate::transaction::wrapper::TransactionWrapper;
 use crate::transaction::{parse_ui_instruction, InstructionWrapper};
-use anyhow::{Ok, Result};
+use anyhow::Result;
 use arctis_types::{
     AccountInfo, BlockInfo, ParserResult, ParserResultData, SplTokenTransfer, SupplyChange,
 };
 
-use super::Parser;
+use super::{Parser, ParserError};
 
 pub struct TokenProgramParser;
 
@@ -16,8 +16,11 @@ impl Parser for TokenProgramParser {
         ix: &InstructionWrapper,
         tx: &TransactionWrapper,
         block: &BlockInfo,
-    ) -> Result<ParserResult> {
+    ) -> Result<ParserResult, ParserError> {
         let accounts = tx.get_accounts();
+        if ix.get_program_address(&accounts) != "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA" {
+            return Err(ParserError::NotMyInstruction);
+        }
 
         let signature = tx.get_signature();
 
@@ -45,20 +48,18 @@ impl Parser for TokenProgramParser {
                     data: ParserResultData::TokenTransfer(spl_transfer),
                 })
             }
-            "approve" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "approve".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
+            "approve" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "approve".to_string(),
+                data: ParserResultData::NoData,
+            }),
             // https://github.com/solana-labs/solana-program-library/blob/master/token/program/src/instruction.rs#L214
             "closeAccount" => {
                 let lookup = tx.get_account_lookup();
 
                 if ix_parsed.parsed["info"]["owner"].as_str().is_none() {
-                    return Err(anyhow::anyhow!(
-                        "closeAccount: multisig account not supported"
+                    return Err(ParserError::Unsupported(
+                        "closeAccount: multisig account".to_string(),
                     ));
                 }
 
@@ -139,34 +140,26 @@ impl Parser for TokenProgramParser {
                     data: ParserResultData::Account(account_info),
                 })
             }
-            "initializeAccount2" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "initializeAccount2".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            "initializeAccount3" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "initializeAccount3".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            "initializeImmutableOwner" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "initializeImmutableOwner".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            "approveChecked" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "approveChecked".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
+            "initializeAccount2" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "initializeAccount2".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            "initializeAccount3" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "initializeAccount3".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            "initializeImmutableOwner" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "initializeImmutableOwner".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            "approveChecked" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "approveChecked".to_string(),
+                data: ParserResultData::NoData,
+            }),
             // https://github.com/solana-labs/solana-program-library/blob/master/token/program/src/instruction.rs#L378
             "syncNative" => {
                 // this does not really impact calculations
@@ -177,27 +170,21 @@ impl Parser for TokenProgramParser {
                     data: ParserResultData::NoData,
                 })
             }
-            "initializeMint" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "initializeMint".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            "mintTo" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "mintTo".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            "mintToChecked" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "mintToChecked".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
+            "initializeMint" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "initializeMint".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            "mintTo" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "mintTo".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            "mintToChecked" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "mintToChecked".to_string(),
+                data: ParserResultData::NoData,
+            }),
             "burn" => {
                 let signature = tx.get_signature();
 
@@ -224,34 +211,26 @@ impl Parser for TokenProgramParser {
                     data: ParserResultData::Supply(supply_change),
                 })
             }
-            "burnChecked" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "burnChecked".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            "setAuthority" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "setAuthority".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            "revoke" => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: "revoke".to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
-            _ => {
-                Ok(ParserResult {
-                    parsed: false,
-                    ix_type: ix_type.to_string(),
-                    data: ParserResultData::NoData,
-                })
-            }
+            "burnChecked" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "burnChecked".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            "setAuthority" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "setAuthority".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            "revoke" => Ok(ParserResult {
+                parsed: false,
+                ix_type: "revoke".to_string(),
+                data: ParserResultData::NoData,
+            }),
+            _ => Ok(ParserResult {
+                parsed: false,
+                ix_type: ix_type.to_string(),
+                data: ParserResultData::NoData,
+            }),
         }
     }
 }
@@ -262,16 +241,18 @@ fn parse_transfer(
     block_info: &BlockInfo,
     signature: String,
 ) -> SplTokenTransfer {
-    let BlockInfo { slot, block_time } = block_info;
+    let BlockInfo {
+        slot, block_time, ..
+    } = block_info;
 
-    let amount = match parsed["amount"].as_str() {
+    let raw_amount = match parsed["amount"].as_str() {
         // transfer:
-        Some(a) => a.parse::<f64>().unwrap(),
+        Some(a) => a.parse::<u64>().unwrap(),
         // transfer_checked:
         None => parsed["tokenAmount"]["amount"]
             .as_str()
             .unwrap()
-            .parse::<f64>()
+            .parse::<u64>()
             .unwrap(),
     };
 
@@ -281,7 +262,8 @@ fn parse_transfer(
         signature,
         from_acc: parsed["source"].as_str().unwrap().to_string(),
         to_acc: parsed["destination"].as_str().unwrap().to_string(),
-        amount,
+        amount: raw_amount as f64,
+        raw_amount,
         authority: parsed["authority"].as_str().map(|a| a.to_string()),
         // derived values
         from: None,
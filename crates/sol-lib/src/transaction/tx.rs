@@ -1,26 +1,205 @@
 use std::str::FromStr;
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use bincode::Options;
+use serde_json::json;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_client::rpc_config::RpcTransactionConfig;
+use solana_client::rpc_config::{RpcSimulateTransactionConfig, RpcTransactionConfig};
+use solana_client::rpc_response::{Response, RpcSimulateTransactionResult};
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::message::VersionedMessage;
 use solana_sdk::signature::Signature;
-use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransactionWithStatusMeta,
+    UiTransactionEncoding,
+};
+
+use crate::client::get_block_commitment;
 
 pub async fn get_transaction(
     rpc_client: &Arc<RpcClient>,
     signature: &str,
+    commitment: CommitmentConfig,
 ) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
     let sig = Signature::from_str(signature)?;
 
     let config = RpcTransactionConfig {
         // encoding: Some(UiTransactionEncoding::JsonParsed),
         encoding: Some(UiTransactionEncoding::Json),
-        commitment: Some(CommitmentConfig::confirmed()),
+        commitment: Some(get_block_commitment(commitment)),
         max_supported_transaction_version: Some(0),
     };
 
     let transaction = rpc_client.get_transaction_with_config(&sig, config).await?;
     Ok(transaction)
 }
+
+fn deserialize_exact<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(bytes)
+        .ok()
+}
+
+/// Decodes a bincode serialized transaction or message, as raw bytes or base64 text.
+/// Messages get default signatures: they can only be simulated without signature verification
+pub fn decode_transaction(data: &[u8]) -> Result<VersionedTransaction> {
+    let decode = |bytes: &[u8]| {
+        if let Some(tx) = deserialize_exact::<VersionedTransaction>(bytes) {
+            return Some(tx);
+        }
+        let message = deserialize_exact::<VersionedMessage>(bytes)?;
+        let num_signatures = message.header().num_required_signatures as usize;
+        Some(VersionedTransaction {
+            signatures: vec![Signature::default(); num_signatures],
+            message,
+        })
+    };
+    if let Some(tx) = decode(data) {
+        return Ok(tx);
+    }
+    base64::prelude::BASE64_STANDARD
+        .decode(data.trim_ascii())
+        .ok()
+        .and_then(|bytes| decode(&bytes))
+        .ok_or(anyhow!(
+            "Data is neither a serialized transaction nor a message"
+        ))
+}
+/// Simulates `tx` against the latest blockhash without signature verification
+pub async fn simulate_transaction(
+    rpc_client: &Arc<RpcClient>,
+    tx: &VersionedTransaction,
+    commitment: CommitmentConfig,
+) -> Result<Response<RpcSimulateTransactionResult>> {
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(commitment),
+        inner_instructions: true,
+        ..Default::default()
+    };
+    let result = rpc_client
+        .simulate_transaction_with_config(tx, config)
+        .await?;
+    Ok(result)
+}
+
+/// Builds the json encoded transaction of a simulation so it can be parsed like a fetched one.
+/// The simulation doesn't return balances: SOL and token balances are empty and
+/// accounts of address lookup tables are not resolved
+pub fn get_simulated_transaction(
+    tx: &VersionedTransaction,
+    simulation: &RpcSimulateTransactionResult,
+) -> Result<EncodedTransactionWithStatusMeta> {
+    let message = &tx.message;
+    let header = message.header();
+    let account_keys: Vec<String> = message
+        .static_account_keys()
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    let instructions: Vec<serde_json::Value> = message
+        .instructions()
+        .iter()
+        .map(|ix| {
+            json!({
+                "programIdIndex": ix.program_id_index,
+                "accounts": ix.accounts,
+                "data": solana_sdk::bs58::encode(&ix.data).into_string(),
+                "stackHeight": null
+            })
+        })
+        .collect();
+    let balances = vec![0u64; account_keys.len()];
+    let status = match &simulation.err {
+        Some(err) => json!({ "Err": err }),
+        None => json!({ "Ok": null }),
+    };
+    let version = match message {
+        VersionedMessage::Legacy(_) => json!("legacy"),
+        VersionedMessage::V0(_) => json!(0),
+    };
+
+    let tx = json!({
+        "transaction": {
+            "signatures": tx.signatures.iter().map(|sig| sig.to_string()).collect::<Vec<_>>(),
+            "message": {
+                "header": {
+                    "numRequiredSignatures": header.num_required_signatures,
+                    "numReadonlySignedAccounts": header.num_readonly_signed_accounts,
+                    "numReadonlyUnsignedAccounts": header.num_readonly_unsigned_accounts
+                },
+                "accountKeys": account_keys,
+                "recentBlockhash": message.recent_blockhash().to_string(),
+                "instructions": instructions
+            }
+        },
+        "meta": {
+            "err": simulation.err,
+            "status": status,
+            "fee": 0,
+            "preBalances": balances,
+            "postBalances": balances,
+            "innerInstructions": simulation.inner_instructions.clone().unwrap_or_default(),
+            "logMessages": simulation.logs.clone().unwrap_or_default(),
+            "preTokenBalances": [],
+            "postTokenBalances": [],
+            "loadedAddresses": { "writable": [], "readonly": [] },
+            "computeUnitsConsumed": simulation.units_consumed
+        },
+        "version": version
+    });
+    Ok(serde_json::from_value(tx)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::wrapper::TransactionWrapper;
+    use solana_sdk::message::Message;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::system_instruction;
+
+    #[test]
+    fn test_decode_and_build_simulated_transaction() {
+        let payer = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let ix = system_instruction::transfer(&payer, &recipient, 1_000_000);
+        let message = VersionedMessage::Legacy(Message::new(&[ix], Some(&payer)));
+
+        // a message is accepted as raw bytes or base64 and gets default signatures
+        let message_bytes = bincode::serialize(&message).unwrap();
+        let tx = decode_transaction(&message_bytes).unwrap();
+        assert_eq!(tx.signatures, vec![Signature::default()]);
+        let base64 = base64::prelude::BASE64_STANDARD.encode(&message_bytes);
+        assert_eq!(decode_transaction(base64.as_bytes()).unwrap(), tx);
+
+        let tx_bytes = bincode::serialize(&tx).unwrap();
+        assert_eq!(decode_transaction(&tx_bytes).unwrap(), tx);
+        assert!(decode_transaction(b"not a transaction").is_err());
+
+        let simulation = RpcSimulateTransactionResult {
+            err: None,
+            logs: Some(vec![
+                "Program 11111111111111111111111111111111 success".to_string()
+            ]),
+            accounts: None,
+            units_consumed: Some(150),
+            return_data: None,
+            inner_instructions: None,
+            replacement_blockhash: None,
+        };
+        let simulated = get_simulated_transaction(&tx, &simulation).unwrap();
+        let wrapper = TransactionWrapper::new(simulated);
+        assert_eq!(wrapper.get_signer(), payer.to_string());
+        assert_eq!(wrapper.get_accounts()[1], recipient.to_string());
+        assert_eq!(wrapper.get_instructions().len(), 1);
+        assert_eq!(wrapper.get_compute_units_consumed(), 150);
+        assert!(!wrapper.is_error());
+    }
+}
@@ -10,9 +10,9 @@ use solana_transaction_status::{
 };
 
 use super::helper::{
-    get_accounts, get_inner_instructions, get_token_account_lookup, get_token_decimals,
-    get_transaction_data, get_transaction_message, get_transaction_meta, get_transaction_signature,
-    get_transaction_signatures, has_error, TokenAccountInfo,
+    get_accounts, get_all_inner_instructions, get_inner_instructions, get_token_account_lookup,
+    get_token_decimals, get_transaction_data, get_transaction_message, get_transaction_meta,
+    get_transaction_signature, get_transaction_signatures, has_error, TokenAccountInfo,
 };
 
 pub struct TransactionWrapper {
@@ -101,11 +101,85 @@ impl TransactionWrapper {
         get_transaction_meta(&self.tx)
     }
 
+    /// writable accounts loaded from address lookup tables, in `get_accounts` after the static keys
+    pub fn get_loaded_writable_addresses(&self) -> Vec<String> {
+        match &self.get_transaction_meta().loaded_addresses {
+            OptionSerializer::Some(loaded) => loaded.writable.clone(),
+            _ => vec![],
+        }
+    }
+
+    /// readonly accounts loaded from address lookup tables, the last accounts of `get_accounts`
+    pub fn get_loaded_readonly_addresses(&self) -> Vec<String> {
+        match &self.get_transaction_meta().loaded_addresses {
+            OptionSerializer::Some(loaded) => loaded.readonly.clone(),
+            _ => vec![],
+        }
+    }
+
+    /// Whether the account at `account_index` of `get_accounts` is writable according to the
+    /// message header and the loaded addresses. Program ids are not demoted to readonly
+    pub fn is_writable_account(&self, account_index: usize) -> bool {
+        let message = self.get_transaction_message();
+        let header = &message.header;
+        let num_static = message.account_keys.len();
+        let num_signed = header.num_required_signatures as usize;
+        if account_index < num_signed {
+            account_index < num_signed - header.num_readonly_signed_accounts as usize
+        } else if account_index < num_static {
+            account_index < num_static - header.num_readonly_unsigned_accounts as usize
+        } else {
+            account_index - num_static < self.get_loaded_writable_addresses().len()
+        }
+    }
+
     pub fn get_instructions(&self) -> Vec<UiCompiledInstruction> {
         let message = self.get_transaction_message();
         message.instructions.clone()
     }
 
+    /// deduplicated program ids of all top level instructions
+    pub fn get_all_program_ids(&self) -> Vec<String> {
+        let mut program_ids: Vec<String> = vec![];
+        for ix in self.get_transaction_message().instructions.iter() {
+            let program_id = &self.accounts[ix.program_id_index as usize];
+            if !program_ids.contains(program_id) {
+                program_ids.push(program_id.clone());
+            }
+        }
+        program_ids
+    }
+
+    /// deduplicated program ids of all top level and inner instructions
+    pub fn get_all_program_ids_with_inner(&self) -> Vec<String> {
+        let mut program_ids = self.get_all_program_ids();
+        let OptionSerializer::Some(inner_instructions) =
+            &self.get_transaction_meta().inner_instructions
+        else {
+            return program_ids;
+        };
+        for ix in inner_instructions
+            .iter()
+            .flat_map(|inner| &inner.instructions)
+        {
+            let UiInstruction::Compiled(ix) = ix else {
+                continue;
+            };
+            let program_id = &self.accounts[ix.program_id_index as usize];
+            if !program_ids.contains(program_id) {
+                program_ids.push(program_id.clone());
+            }
+        }
+        program_ids
+    }
+
+    /// true if the program is invoked by a top level or inner instruction
+    pub fn touches_program(&self, program_id: &str) -> bool {
+        self.get_all_program_ids_with_inner()
+            .iter()
+            .any(|id| id == program_id)
+    }
+
     pub fn get_inner_ix_count(&self) -> u8 {
         let meta = self.get_transaction_meta();
         meta.inner_instructions
@@ -117,6 +191,16 @@ impl TransactionWrapper {
         get_inner_instructions(&self.tx, program_id)
     }
 
+    /// inner instructions of every top level instruction of the program.
+    /// unlike get_inner_instructions this supports multiple calls to the same program
+    pub fn get_all_inner_instructions_for_program(
+        &self,
+        program_id: &str,
+    ) -> Vec<(u8, Vec<UiCompiledInstruction>)> {
+        get_all_inner_instructions(&self.tx, program_id)
+    }
+
+    /// inner instructions (cpi) of the top level instruction at ix_idx
     pub fn get_compiled_inner_instructions_for_instruction(
         &self,
         ix_idx: u8,
@@ -142,6 +226,49 @@ impl TransactionWrapper {
             .collect::<Vec<UiCompiledInstruction>>())
     }
 
+    /// lamport delta (post - pre) per account
+    /// pre/post balances are parallel to the account keys incl. loaded addresses
+    pub fn get_sol_balance_changes(&self) -> HashMap<String, i64> {
+        let meta = self.get_transaction_meta();
+        self.accounts
+            .iter()
+            .zip(meta.pre_balances.iter().zip(meta.post_balances.iter()))
+            .map(|(account, (pre, post))| (account.clone(), *post as i64 - *pre as i64))
+            .collect()
+    }
+
+    /// (pre, post) lamport balance of an account touched by this transaction
+    pub fn get_sol_balance_for_account(&self, address: &str) -> Option<(u64, u64)> {
+        let meta = self.get_transaction_meta();
+        let idx = self.accounts.iter().position(|a| a == address)?;
+        let pre = meta.pre_balances.get(idx)?;
+        let post = meta.post_balances.get(idx)?;
+        Some((*pre, *post))
+    }
+
+    /// lamport balance before the tx, 0 if the index is out of range
+    pub fn get_pre_sol_balance(&self, account_index: usize) -> u64 {
+        let meta = self.get_transaction_meta();
+        meta.pre_balances.get(account_index).copied().unwrap_or(0)
+    }
+
+    /// lamport balance after the tx, 0 if the index is out of range
+    pub fn get_post_sol_balance(&self, account_index: usize) -> u64 {
+        let meta = self.get_transaction_meta();
+        meta.post_balances.get(account_index).copied().unwrap_or(0)
+    }
+
+    /// lamport delta of the fee payer incl. fees: negative means SOL left the signer's account
+    pub fn get_signer_sol_spent(&self) -> i64 {
+        self.get_post_sol_balance(0) as i64 - self.get_pre_sol_balance(0) as i64
+    }
+
+    /// lamport delta (post - pre) of an account touched by this transaction
+    pub fn get_total_sol_received_by_account(&self, address: &str) -> Option<i64> {
+        let (pre, post) = self.get_sol_balance_for_account(address)?;
+        Some(post as i64 - pre as i64)
+    }
+
     pub fn get_account_lookup(&self) -> HashMap<String, TokenAccountInfo> {
         let accounts = self.get_accounts().clone();
         let tx = self.get_tx();
@@ -149,6 +276,23 @@ impl TransactionWrapper {
         get_token_account_lookup(tx, &accounts, false)
     }
 
+    /// raw token amount of a token account before the tx, read from the exact `amount` string
+    pub fn get_pre_token_raw_amount(&self, address: &str) -> Option<u64> {
+        let meta = self.get_transaction_meta();
+        let OptionSerializer::Some(balances) = &meta.pre_token_balances else {
+            return None;
+        };
+        balances
+            .iter()
+            .find(|balance| {
+                self.accounts
+                    .get(balance.account_index as usize)
+                    .map(String::as_str)
+                    == Some(address)
+            })
+            .and_then(|balance| balance.ui_token_amount.amount.parse::<u64>().ok())
+    }
+
     pub fn get_inner_token_transfers(&self, _program_id: &str) -> Result<Vec<SplTokenTransfer>> {
         /*
         let accounts = self.get_accounts().clone();
@@ -170,3 +314,117 @@ impl TransactionWrapper {
         Some(logs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{get_test_transaction, TestTransactionBuilder};
+
+    #[test]
+    fn test_is_writable_account() {
+        // signer, readonly signer, writable, readonly program
+        let tx = TestTransactionBuilder::new(&["signer", "cosigner", "pool", "program"])
+            .signatures(&["sig1", "sig2"])
+            .header(2, 1, 1)
+            .loaded_addresses(&["vault_a", "vault_b"], &["authority"])
+            .build();
+        let tx = TransactionWrapper::new(tx);
+
+        assert_eq!(
+            tx.get_loaded_writable_addresses(),
+            vec!["vault_a", "vault_b"]
+        );
+        assert_eq!(tx.get_loaded_readonly_addresses(), vec!["authority"]);
+        let writable: Vec<bool> = (0..tx.get_accounts().len())
+            .map(|idx| tx.is_writable_account(idx))
+            .collect();
+        assert_eq!(writable, vec![true, false, true, false, true, true, false]);
+    }
+
+    #[test]
+    fn test_get_pre_token_raw_amount() {
+        // not exactly representable as f64
+        let raw_amount = 12_345_678_901_234_567_891;
+        let tx = TestTransactionBuilder::new(&["signer", "token_account", "program"])
+            .token_balance(1, "mint", "signer", 9, raw_amount, 0)
+            .build();
+        let tx = TransactionWrapper::new(tx);
+
+        assert_eq!(
+            tx.get_pre_token_raw_amount("token_account"),
+            Some(raw_amount)
+        );
+        assert_eq!(tx.get_pre_token_raw_amount("signer"), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_sol_balance_changes() {
+        // raydium wsol swap: contains native SOL movements and token transfers
+        let sig = "5RbkAPyAxV6nx4hafHXyz5JDHB62MMjyG3x8dkrzH5ZfYDaWHfhQAsw1y4k5qARARBtYqzsmcGtAKdD8nLrrVHsa";
+        let tx = TransactionWrapper::new(get_test_transaction(sig).await.transaction);
+
+        let changes = tx.get_sol_balance_changes();
+        assert_eq!(changes.len(), tx.get_accounts().len());
+
+        // lamports are conserved except for the burned / collected fee
+        let total: i64 = changes.values().sum();
+        assert_eq!(total, -(tx.get_fee() as i64));
+
+        let signer = tx.get_signer();
+        let (pre, post) = tx.get_sol_balance_for_account(&signer).unwrap();
+        assert_eq!(changes[&signer], post as i64 - pre as i64);
+
+        assert!(tx
+            .get_sol_balance_for_account("11111111111111111111111111111112")
+            .is_none());
+        assert_eq!(tx.get_signer_sol_spent(), changes[&signer]);
+        assert_eq!(
+            tx.get_total_sol_received_by_account(&signer),
+            Some(changes[&signer])
+        );
+        assert_eq!(tx.get_pre_sol_balance(0), pre);
+        assert_eq!(tx.get_post_sol_balance(0), post);
+        assert_eq!(tx.get_pre_sol_balance(usize::MAX), 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_all_program_ids() {
+        // jupiter swap with compute budget instructions
+        let sig = "5fSkM83WUxgFbqwfwenfuLdygyCpjqMBzsMJPV7kv6AT6vau6ZygW3eimcFHX8wukM5YcgjV37EH5TzKvbmfqk3d";
+        let tx = TransactionWrapper::new(get_test_transaction(sig).await.transaction);
+
+        let program_ids = tx.get_all_program_ids();
+        assert!(program_ids.contains(&"JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4".to_string()));
+        assert!(program_ids.contains(&"ComputeBudget111111111111111111111111111111".to_string()));
+        // compute budget is invoked multiple times
+        assert!(program_ids.len() < tx.get_instructions().len());
+
+        // swaps are executed via cpi
+        assert!(!program_ids.contains(&"TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA".to_string()));
+        assert!(tx.touches_program("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"));
+        assert!(tx.get_all_program_ids_with_inner().len() > program_ids.len());
+        assert!(!tx.touches_program("Vote111111111111111111111111111111111111111"));
+    }
+
+    #[tokio::test]
+    async fn test_get_all_inner_instructions_for_program() {
+        let sig = "5fSkM83WUxgFbqwfwenfuLdygyCpjqMBzsMJPV7kv6AT6vau6ZygW3eimcFHX8wukM5YcgjV37EH5TzKvbmfqk3d";
+        let tx = TransactionWrapper::new(get_test_transaction(sig).await.transaction);
+
+        let inner = tx
+            .get_all_inner_instructions_for_program("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+        assert_eq!(inner.len(), 1);
+        let (ix_idx, instructions) = &inner[0];
+        assert_eq!(*ix_idx, 3);
+        assert!(!instructions.is_empty());
+        assert_eq!(
+            instructions,
+            &tx.get_compiled_inner_instructions_for_instruction(*ix_idx)
+                .unwrap()
+        );
+
+        assert!(tx
+            .get_all_inner_instructions_for_program("Vote111111111111111111111111111111111111111")
+            .is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use anyhow::{anyhow, Result};
 use chrono::DateTime;
 
 pub const WSOL: &str = "So11111111111111111111111111111111111111112";
@@ -45,12 +46,92 @@ pub fn format_with_decimals(amount: u64, decimals: u8) -> f64 {
     amount / 10u64.pow(decimals as u32) as f64
 }
 
+/// Like `format_with_decimals` but returns None if the f64 is off by more than 6 significant
+/// figures or the decimals overflow the divisor.
+pub fn format_with_decimals_checked(amount: u64, decimals: u8) -> Option<f64> {
+    let divisor = 10u64.checked_pow(decimals as u32)?;
+    let value = amount as f64 / divisor as f64;
+    // convert back to raw units and compare with integer arithmetic
+    let raw = (value * divisor as f64).round() as u128;
+    if raw.abs_diff(amount as u128) * 1_000_000 > amount as u128 {
+        return None;
+    }
+    Some(value)
+}
+
+/// Exact decimal string of a raw token amount without trailing zeros, e.g. 1_500_000 with 6
+/// decimals is "1.5".
+pub fn format_with_decimals_precise(amount: u64, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let digits = format!("{:0>width$}", amount, width = decimals + 1);
+    let (int_part, frac_part) = digits.split_at(digits.len() - decimals);
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.is_empty() {
+        int_part.to_string()
+    } else {
+        format!("{}.{}", int_part, frac_part)
+    }
+}
+
+/// Parses a decimal string into raw token units, the inverse of `format_with_decimals_precise`.
+pub fn parse_token_amount(amount_str: &str, decimals: u8) -> Result<u64> {
+    let (int_part, frac_part) = amount_str.split_once('.').unwrap_or((amount_str, ""));
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !is_digits(int_part)
+        || !is_digits(frac_part)
+    {
+        return Err(anyhow!("Invalid token amount: {}", amount_str));
+    }
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.len() > decimals as usize {
+        return Err(anyhow!(
+            "Token amount {} has more than {} decimals",
+            amount_str,
+            decimals
+        ));
+    }
+    let digits = format!(
+        "{}{:0<width$}",
+        int_part,
+        frac_part,
+        width = decimals as usize
+    );
+    digits
+        .parse::<u64>()
+        .map_err(|_| anyhow!("Token amount {} overflows u64", amount_str))
+}
+
+/// priority fee in SOL for a compute unit limit and price in microlamports per CU
+pub fn get_effective_priority_fee_sol(c_unit_limit: u64, priority_fee_microlamports: u64) -> f64 {
+    // micro-lamports -> lamports -> SOL
+    (c_unit_limit as f64 * priority_fee_microlamports as f64) / 1_000_000.0 / 1_000_000_000.0
+}
+
+// This function accepts a flexible number of channels (up to 3)
+/// `extra` fields are merged into the log object if the logger writes json
+pub fn log_message(channels: &[&str], message: &str, extra: Option<serde_json::Value>) {
+    let formatted_channels = channels.join("][");
+    match extra {
+        Some(extra) => log::info!(
+            extra = log::kv::Value::from_serde(&extra);
+            "[{}] {}",
+            formatted_channels,
+            message
+        ),
+        None => log::info!("[{}] {}", formatted_channels, message),
+    }
+}
+
 #[cfg(test)]
 use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
 
 #[cfg(test)]
 pub async fn get_test_transaction(sig: &str) -> EncodedConfirmedTransactionWithStatusMeta {
-    use crate::client::get_client;
+    use crate::client::{get_client, CommitmentConfig};
 
     dotenvy::dotenv().ok();
 
@@ -59,7 +140,7 @@ pub async fn get_test_transaction(sig: &str) -> EncodedConfirmedTransactionWithS
     let rpc_client = get_client(&rpc_url);
 
     // TODO cache transaction
-    crate::transaction::tx::get_transaction(&rpc_client, sig)
+    crate::transaction::tx::get_transaction(&rpc_client, sig, CommitmentConfig::confirmed())
         .await
         .unwrap()
 }
@@ -84,6 +165,8 @@ pub async fn get_test_data(sig: &str, ix_index: usize) -> TestData {
     let block_info = BlockInfo {
         slot: tx.slot,
         block_time: tx.block_time.unwrap(),
+        blockhash: "".to_string(),
+        ..Default::default()
     };
     let tx = TransactionWrapper::new(tx.transaction);
 
@@ -92,3 +175,236 @@ pub async fn get_test_data(sig: &str, ix_index: usize) -> TestData {
 
     TestData { tx, block_info, ix }
 }
+
+#[cfg(any(test, feature = "test-utils"))]
+use arctis_types::EncodedTransactionWithStatusMeta;
+#[cfg(any(test, feature = "test-utils"))]
+use serde_json::{json, Value};
+
+/// Builds a json encoded transaction for tests that don't need a mainnet transaction.
+/// The header defaults to one signer and one readonly account (the program, last account key).
+/// Accounts without a balance have 0 lamports
+#[cfg(any(test, feature = "test-utils"))]
+pub struct TestTransactionBuilder {
+    signatures: Vec<String>,
+    account_keys: Vec<String>,
+    header: (u8, u8, u8),
+    instructions: Vec<Value>,
+    inner_instructions: Vec<Value>,
+    sol_balances: Vec<(usize, u64, u64)>,
+    pre_token_balances: Vec<Value>,
+    post_token_balances: Vec<Value>,
+    loaded_writable: Vec<String>,
+    loaded_readonly: Vec<String>,
+    log_messages: Vec<String>,
+    version: Option<u8>,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl TestTransactionBuilder {
+    pub fn new(account_keys: &[&str]) -> Self {
+        TestTransactionBuilder {
+            signatures: vec!["sig".to_string()],
+            account_keys: account_keys.iter().map(|key| key.to_string()).collect(),
+            header: (1, 0, 1),
+            instructions: vec![],
+            inner_instructions: vec![],
+            sol_balances: vec![],
+            pre_token_balances: vec![],
+            post_token_balances: vec![],
+            loaded_writable: vec![],
+            loaded_readonly: vec![],
+            log_messages: vec![],
+            version: None,
+        }
+    }
+
+    pub fn signatures(mut self, signatures: &[&str]) -> Self {
+        self.signatures = signatures.iter().map(|sig| sig.to_string()).collect();
+        self
+    }
+
+    /// numRequiredSignatures, numReadonlySignedAccounts, numReadonlyUnsignedAccounts
+    pub fn header(mut self, required: u8, readonly_signed: u8, readonly_unsigned: u8) -> Self {
+        self.header = (required, readonly_signed, readonly_unsigned);
+        self
+    }
+
+    pub fn instruction(mut self, program_id_index: u8, accounts: &[u8], data: &[u8]) -> Self {
+        self.instructions.push(json!({
+            "programIdIndex": program_id_index,
+            "accounts": accounts,
+            "data": solana_sdk::bs58::encode(data).into_string(),
+            "stackHeight": null
+        }));
+        self
+    }
+
+    /// cpi of the top level instruction `index`
+    pub fn inner_instruction(
+        mut self,
+        index: u8,
+        program_id_index: u8,
+        accounts: &[u8],
+        data: &[u8],
+    ) -> Self {
+        self.inner_instructions.push(json!({
+            "index": index,
+            "instructions": [{
+                "programIdIndex": program_id_index,
+                "accounts": accounts,
+                "data": solana_sdk::bs58::encode(data).into_string(),
+                "stackHeight": 2
+            }]
+        }));
+        self
+    }
+
+    /// lamports before and after the transaction
+    pub fn sol_balance(mut self, account_index: usize, pre: u64, post: u64) -> Self {
+        self.sol_balances.push((account_index, pre, post));
+        self
+    }
+
+    /// raw token amounts before and after the transaction, the ui amounts are derived
+    pub fn token_balance(
+        mut self,
+        account_index: u8,
+        mint: &str,
+        owner: &str,
+        decimals: u8,
+        pre: u64,
+        post: u64,
+    ) -> Self {
+        let balance = |amount: u64| {
+            let ui_amount = format_with_decimals_precise(amount, decimals);
+            json!({
+                "accountIndex": account_index,
+                "mint": mint,
+                "owner": owner,
+                "programId": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+                "uiTokenAmount": {
+                    "uiAmount": ui_amount.parse::<f64>().unwrap(),
+                    "decimals": decimals,
+                    "amount": amount.to_string(),
+                    "uiAmountString": ui_amount
+                }
+            })
+        };
+        self.pre_token_balances.push(balance(pre));
+        self.post_token_balances.push(balance(post));
+        self
+    }
+
+    /// addresses loaded from lookup tables, this makes it a v0 transaction
+    pub fn loaded_addresses(mut self, writable: &[&str], readonly: &[&str]) -> Self {
+        self.loaded_writable = writable.iter().map(|key| key.to_string()).collect();
+        self.loaded_readonly = readonly.iter().map(|key| key.to_string()).collect();
+        self.version = Some(0);
+        self
+    }
+
+    pub fn log_messages(mut self, log_messages: &[String]) -> Self {
+        self.log_messages = log_messages.to_vec();
+        self
+    }
+
+    pub fn to_json(&self) -> Value {
+        let account_count =
+            self.account_keys.len() + self.loaded_writable.len() + self.loaded_readonly.len();
+        let mut pre_balances = vec![0u64; account_count];
+        let mut post_balances = vec![0u64; account_count];
+        for (account_index, pre, post) in &self.sol_balances {
+            pre_balances[*account_index] = *pre;
+            post_balances[*account_index] = *post;
+        }
+        let (required, readonly_signed, readonly_unsigned) = self.header;
+        let mut tx = json!({
+            "transaction": {
+                "signatures": self.signatures,
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": required,
+                        "numReadonlySignedAccounts": readonly_signed,
+                        "numReadonlyUnsignedAccounts": readonly_unsigned
+                    },
+                    "accountKeys": self.account_keys,
+                    "recentBlockhash": "11111111111111111111111111111111",
+                    "instructions": self.instructions
+                }
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": pre_balances,
+                "postBalances": post_balances,
+                "innerInstructions": self.inner_instructions,
+                "logMessages": self.log_messages,
+                "preTokenBalances": self.pre_token_balances,
+                "postTokenBalances": self.post_token_balances,
+                "loadedAddresses": {
+                    "writable": self.loaded_writable,
+                    "readonly": self.loaded_readonly
+                },
+                "computeUnitsConsumed": 50_000
+            }
+        });
+        if let Some(version) = self.version {
+            tx["transaction"]["message"]["addressTableLookups"] = json!([]);
+            tx["version"] = json!(version);
+        }
+        tx
+    }
+
+    pub fn build(&self) -> EncodedTransactionWithStatusMeta {
+        serde_json::from_value(self.to_json()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_with_decimals_precise() {
+        assert_eq!(format_with_decimals_precise(1_500_000, 6), "1.5");
+        assert_eq!(format_with_decimals_precise(1, 9), "0.000000001");
+        assert_eq!(format_with_decimals_precise(2_000_000_000, 9), "2");
+        assert_eq!(
+            format_with_decimals_precise(u64::MAX, 0),
+            "18446744073709551615"
+        );
+        assert_eq!(
+            format_with_decimals_precise(u64::MAX, 9),
+            "18446744073.709551615"
+        );
+    }
+
+    #[test]
+    fn test_parse_token_amount() {
+        assert_eq!(parse_token_amount("1.5", 6).unwrap(), 1_500_000);
+        assert_eq!(parse_token_amount(".5", 1).unwrap(), 5);
+        assert_eq!(parse_token_amount("2.000", 0).unwrap(), 2);
+        assert_eq!(
+            parse_token_amount("18446744073709551615", 0).unwrap(),
+            u64::MAX
+        );
+        assert!(parse_token_amount("0.0000001", 6).is_err());
+        assert!(parse_token_amount("18446744073709551616", 0).is_err());
+        assert!(parse_token_amount("-1", 6).is_err());
+        assert!(parse_token_amount(".", 6).is_err());
+
+        for (amount, decimals) in [(1_500_000, 6), (u64::MAX, 0), (u64::MAX, 19), (0, 9)] {
+            let formatted = format_with_decimals_precise(amount, decimals);
+            assert_eq!(parse_token_amount(&formatted, decimals).unwrap(), amount);
+        }
+    }
+
+    #[test]
+    fn test_format_with_decimals_checked() {
+        assert_eq!(format_with_decimals_checked(1_500_000, 6), Some(1.5));
+        assert!(format_with_decimals_checked(u64::MAX, 0).is_some());
+        assert_eq!(format_with_decimals_checked(1, 20), None);
+    }
+}